@@ -2,26 +2,68 @@ use anchor_lang::prelude::*;
 
 declare_id!("CQ2VvuR8Du2WQq1XWmzBKxmK4arc7BVReWMxUMW3nJs5");
 
+/// Longest name a counter can have. The name is a PDA seed, and seeds can't be any longer.
+pub const MAX_NAME_LEN: usize = 32;
+
 #[program]
 pub mod anchor_counter {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    pub fn initialize(ctx: Context<Initialize>, name: String) -> Result<()> {
         let counter = &mut ctx.accounts.counter;
         counter.count = 0;
+        counter.authority = ctx.accounts.user.key();
+        counter.cooldown_slots = 0;
+        counter.name = name;
         Ok(())
     }
 
     pub fn increment(ctx: Context<Increment>) -> Result<()> {
+        let slot = Clock::get()?.slot;
         let counter = &mut ctx.accounts.counter;
+        let user_increments = &mut ctx.accounts.user_increments;
+
+        //a user who has never incremented this counter has no cooldown to wait out
+        if user_increments.count > 0 {
+            let ready_at = user_increments
+                .last_increment_slot
+                .saturating_add(counter.cooldown_slots);
+            if slot < ready_at {
+                msg!("Cooldown ends at slot {}, now at {}", ready_at, slot);
+                return err!(CounterError::CooldownActive);
+            }
+        }
+
+        counter.count += 1;
+        user_increments.count += 1;
+        user_increments.last_increment_slot = slot;
+        Ok(())
+    }
+
+    /// How many slots a user has to wait between two increments of this counter. 0 turns the
+    /// cooldown off.
+    pub fn set_cooldown(ctx: Context<SetCooldown>, cooldown_slots: u64) -> Result<()> {
+        ctx.accounts.counter.cooldown_slots = cooldown_slots;
+        Ok(())
+    }
+
+    pub fn initialize_zero_copy(ctx: Context<InitializeZeroCopy>) -> Result<()> {
+        let mut counter = ctx.accounts.counter.load_init()?;
+        counter.count = 0;
+        Ok(())
+    }
+
+    pub fn increment_zero_copy(ctx: Context<IncrementZeroCopy>) -> Result<()> {
+        let mut counter = ctx.accounts.counter.load_mut()?;
         counter.count += 1;
         Ok(())
     }
 }
 
 #[derive(Accounts)]
+#[instruction(name: String)]
 pub struct Initialize<'info> {
-    #[account(init, payer=user, space = 8+8, seeds = [b"counter"], bump)]
+    #[account(init, payer=user, space = 8+Counter::SPACE, seeds = [b"counter", Counter::name_seed(&name)?], bump)]
     pub counter: Account<'info, Counter>,
     #[account(mut)]
     pub user: Signer<'info>,
@@ -30,13 +72,91 @@ pub struct Initialize<'info> {
 
 #[derive(Accounts)]
 pub struct Increment<'info> {
-    #[account(mut, seeds = [b"counter"], bump)]
+    #[account(mut, seeds = [b"counter", counter.name.as_bytes()], bump)]
     pub counter: Account<'info, Counter>,
     #[account(mut)]
     pub user: Signer<'info>,
+    #[account(init_if_needed, payer=user, space = 8+UserIncrements::SPACE, seeds = [b"user_increments", counter.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_increments: Account<'info, UserIncrements>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCooldown<'info> {
+    #[account(mut, seeds = [b"counter", counter.name.as_bytes()], bump, has_one = authority)]
+    pub counter: Account<'info, Counter>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeZeroCopy<'info> {
+    #[account(init, payer=user, space = 8+std::mem::size_of::<ZeroCopyCounter>(), seeds = [b"zero_copy_counter"], bump)]
+    pub counter: AccountLoader<'info, ZeroCopyCounter>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IncrementZeroCopy<'info> {
+    #[account(mut, seeds = [b"zero_copy_counter"], bump)]
+    pub counter: AccountLoader<'info, ZeroCopyCounter>,
+    #[account(mut)]
+    pub user: Signer<'info>,
 }
 
 #[account]
 pub struct Counter {
     pub count: u64,
+    /// Whoever initialized the counter. The only one who can change the cooldown.
+    pub authority: Pubkey,
+    /// Slots a user has to wait between two increments
+    pub cooldown_slots: u64,
+    /// Also the second seed of the counter's PDA, so every name gets its own counter.
+    /// Last, so the fixed size fields before it stay at the same offsets.
+    pub name: String,
+}
+
+impl Counter {
+    /// Space for the longest name, so every counter is the same size
+    pub const SPACE: usize = 8 + 32 + 8 + 4 + MAX_NAME_LEN;
+
+    /// Checks the name before it's used as a seed. Deriving a PDA from a seed that's too long
+    /// aborts the program, so without this a long name fails with no useful error. An empty seed
+    /// adds nothing to the hash, so an empty name would get the old singleton `[b"counter"]` PDA.
+    pub fn name_seed(name: &str) -> Result<&[u8]> {
+        require!(!name.is_empty(), CounterError::NameEmpty);
+        require!(name.len() <= MAX_NAME_LEN, CounterError::NameTooLong);
+        Ok(name.as_bytes())
+    }
+}
+
+/// One user's increments of one counter, created the first time they increment it
+#[account]
+pub struct UserIncrements {
+    pub count: u64,
+    pub last_increment_slot: u64,
+}
+
+impl UserIncrements {
+    pub const SPACE: usize = 8 + 8;
+}
+
+/// Same counter, but read and written in place through `AccountLoader` instead of being
+/// Borsh deserialized and reserialized on every instruction
+#[account(zero_copy)]
+pub struct ZeroCopyCounter {
+    pub count: u64,
+    /// Room for future fields (last updated slot, per user tallies) without a realloc
+    pub reserved: [u64; 15],
+}
+
+#[error_code]
+pub enum CounterError {
+    #[msg("Counter names can be at most 32 bytes")]
+    NameTooLong,
+    #[msg("This user incremented the counter too recently, wait for the cooldown")]
+    CooldownActive,
+    #[msg("Counter names can't be empty")]
+    NameEmpty,
 }
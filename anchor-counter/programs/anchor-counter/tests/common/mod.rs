@@ -0,0 +1,232 @@
+//! Shared set up and assertion helpers for the anchor_counter integration tests.
+//! Each file in `tests/` is its own crate, so every test file pulls these in with `mod common;`
+//! and not every test file uses every helper.
+#![allow(dead_code)]
+
+use anchor_lang::{
+    error::ErrorCode,
+    prelude::Pubkey,
+    solana_program::{self},
+    system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+/// Name of the counter the tests set up, unless they pick their own
+pub const COUNTER_NAME: &str = "deploys";
+
+/// Struct set up to hold the validator, an optional user account, and the PDA of the `COUNTER_NAME` counter.
+/// Use SetUpTest::new() to create a new instance.
+pub struct SetUpTest {
+    pub validator: ProgramTest,
+    pub user: Keypair,
+    pub counter_pda: Pubkey,
+}
+
+/// Returns the validator, an optional funded user account, and the counter PDA
+impl SetUpTest {
+    pub fn new() -> Self {
+        //Both of these work
+
+        // let mut validator = ProgramTest::default();
+        // validator.add_program("anchor_counter", anchor_counter::ID, None);
+        let mut validator = ProgramTest::new("anchor_counter", anchor_counter::ID, None);
+
+        //create a new user and fund with 1 SOL
+        let user = add_funded_user(&mut validator);
+
+        //get the counter PDA -- uses the same seeds we used in the anchor program
+        let counter_pda = counter_pda(COUNTER_NAME);
+
+        Self {
+            validator,
+            user,
+            counter_pda,
+        }
+    }
+}
+
+///The PDA of the counter with this name
+pub fn counter_pda(name: &str) -> Pubkey {
+    let (counter_pda, _) =
+        Pubkey::find_program_address(&[b"counter", name.as_bytes()], &anchor_counter::ID);
+    counter_pda
+}
+
+///The PDA that tracks this user's increments of this counter
+pub fn user_increments_pda(counter: &Pubkey, user: &Pubkey) -> Pubkey {
+    let (user_increments_pda, _) = Pubkey::find_program_address(
+        &[b"user_increments", counter.as_ref(), user.as_ref()],
+        &anchor_counter::ID,
+    );
+    user_increments_pda
+}
+
+///Creates a new user and funds it with 1 SOL by adding it to the validator / ledger
+///Must be called before the validator is started
+pub fn add_funded_user(validator: &mut ProgramTest) -> Keypair {
+    let user = Keypair::new();
+    validator.add_account(
+        user.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    user
+}
+
+///Function that initializes the counter account with the given name
+///Useful for testing things you want to fail but need to initialize the counter account first
+pub async fn initialize(
+    ctx: &mut ProgramTestContext,
+    user: &Keypair,
+    counter_pda: &Pubkey,
+    name: &str,
+) -> anyhow::Result<()> {
+    let init_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Initialize {
+            counter: *counter_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Initialize {
+            name: name.to_string(),
+        }
+        .data(),
+    };
+
+    let init_tx = Transaction::new_signed_with_payer(
+        &[init_ix],
+        Some(&user.pubkey()),
+        &[&user],
+        ctx.last_blockhash,
+    );
+
+    ctx.banks_client.process_transaction(init_tx).await.unwrap();
+
+    Ok(())
+}
+
+///Increments the counter in its own transaction. Gets a new blockhash first, so calling it twice
+///in a row doesn't send the same transaction twice.
+pub async fn increment(
+    ctx: &mut ProgramTestContext,
+    user: &Keypair,
+    counter_pda: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: *counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+
+    process_instruction(ctx, increment_ix, user).await
+}
+
+///Sets the counter's cooldown, signed by `authority`
+pub async fn set_cooldown(
+    ctx: &mut ProgramTestContext,
+    authority: &Keypair,
+    counter_pda: &Pubkey,
+    cooldown_slots: u64,
+) -> Result<(), BanksClientError> {
+    let set_cooldown_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::SetCooldown {
+            counter: *counter_pda,
+            authority: authority.pubkey(),
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::SetCooldown { cooldown_slots }.data(),
+    };
+
+    process_instruction(ctx, set_cooldown_ix, authority).await
+}
+
+async fn process_instruction(
+    ctx: &mut ProgramTestContext,
+    ix: Instruction,
+    signer: &Keypair,
+) -> Result<(), BanksClientError> {
+    ctx.last_blockhash = ctx.get_new_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        ctx.last_blockhash,
+    );
+
+    ctx.banks_client.process_transaction(tx).await
+}
+
+/// Unwraps a failed `process_transaction` result down to the index of the instruction that failed
+/// and the `InstructionError` it failed with. Panics if the transaction succeeded or failed some other way.
+pub fn instruction_error(res: Result<(), BanksClientError>) -> (u8, InstructionError) {
+    let tx_err = match res {
+        Ok(()) => panic!("expected the transaction to fail but it succeeded"),
+        Err(BanksClientError::TransactionError(err)) => err,
+        Err(BanksClientError::SimulationError { err, .. }) => err,
+        Err(err) => panic!("expected a transaction error, got {:?}", err),
+    };
+
+    match tx_err {
+        TransactionError::InstructionError(index, ix_err) => (index, ix_err),
+        err => panic!("expected an instruction error, got {:?}", err),
+    }
+}
+
+/// Asserts the transaction failed at instruction `index` with exactly `expected`
+pub fn assert_instruction_error(
+    res: Result<(), BanksClientError>,
+    index: u8,
+    expected: InstructionError,
+) {
+    let (failed_index, ix_err) = instruction_error(res);
+    assert_eq!(failed_index, index, "wrong instruction failed");
+    assert_eq!(ix_err, expected);
+}
+
+/// Asserts the transaction failed at instruction `index` with the anchor `ErrorCode` we expect.
+/// Anchor errors reach the client as `InstructionError::Custom(code)`.
+pub fn assert_anchor_error(res: Result<(), BanksClientError>, index: u8, expected: ErrorCode) {
+    let name = format!("{:?}", expected);
+    let (failed_index, ix_err) = instruction_error(res);
+    assert_eq!(failed_index, index, "wrong instruction failed");
+    assert_eq!(
+        ix_err,
+        InstructionError::Custom(expected as u32),
+        "expected anchor error {}",
+        name
+    );
+}
+
+/// Fetch the account from the ProgramTestContext and deserialize it.
+/// Taken from the MarginFi Github tests: https://github.com/mrgnlabs/marginfi-v2/blob/main/test-utils/src/test.rs#L468
+pub async fn load_and_deserialize<T: AccountDeserialize>(
+    mut ctx: ProgramTestContext,
+    address: Pubkey,
+) -> T {
+    let account = ctx
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap() //unwraps the Result into an Option<Account>
+        .unwrap(); //unwraps the Option<Account> into an Account
+
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
@@ -0,0 +1,318 @@
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{self},
+    system_program, InstructionData, ToAccountMetas,
+};
+use anyhow::anyhow;
+use solana_program::instruction::Instruction;
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+mod common;
+use common::*;
+
+/// Checked-in compute unit baseline. Regenerate with `UPDATE_CU_BASELINE=1 cargo test-sbf --test compute_units`
+const BASELINE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/compute_units.baseline");
+
+/// Used when the baseline file doesn't set `threshold_pct`
+const DEFAULT_THRESHOLD_PCT: u64 = 5;
+
+#[tokio::test]
+async fn test_compute_units() -> anyhow::Result<()> {
+    let measured = measure_compute_units().await?;
+
+    let update = std::env::var("UPDATE_CU_BASELINE").is_ok();
+    let baseline = match std::fs::read_to_string(BASELINE_PATH) {
+        Ok(contents) => Some(Baseline::parse(&contents)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let threshold_pct = baseline
+        .as_ref()
+        .map_or(DEFAULT_THRESHOLD_PCT, |b| b.threshold_pct);
+    let old_units = baseline.map(|b| b.units).unwrap_or_default();
+
+    let (table, regressions, untracked, removed) = compare(&old_units, &measured, threshold_pct);
+    println!("{}", table);
+
+    //only ever write the tracked file when asked to, a plain test run just checks it
+    if update {
+        let new_baseline = Baseline {
+            threshold_pct,
+            units: measured,
+        };
+        std::fs::write(BASELINE_PATH, new_baseline.render())?;
+        println!(
+            "wrote compute unit baseline to {}, commit it",
+            BASELINE_PATH
+        );
+        return Ok(());
+    }
+
+    if old_units.is_empty() {
+        return Err(anyhow!(
+            "no compute units in the baseline at {}\n{}\nRerun with UPDATE_CU_BASELINE=1 and commit the baseline",
+            BASELINE_PATH,
+            table
+        ));
+    }
+
+    if !untracked.is_empty() {
+        return Err(anyhow!(
+            "compute units aren't in the baseline for: {}\n{}\nRerun with UPDATE_CU_BASELINE=1 and commit the baseline",
+            untracked.join(", "),
+            table
+        ));
+    }
+
+    if !removed.is_empty() {
+        return Err(anyhow!(
+            "compute units are in the baseline but weren't measured for: {}\n{}\nRerun with UPDATE_CU_BASELINE=1 and commit the baseline",
+            removed.join(", "),
+            table
+        ));
+    }
+
+    if !regressions.is_empty() {
+        return Err(anyhow!(
+            "compute units regressed more than {}% for: {}\n{}\nIf this is expected, rerun with UPDATE_CU_BASELINE=1 and commit the baseline",
+            threshold_pct,
+            regressions.join(", "),
+            table
+        ));
+    }
+
+    Ok(())
+}
+
+/// Runs every instruction in its own transaction and records the compute units it consumed.
+/// Add new instructions here so they are tracked in the baseline.
+async fn measure_compute_units() -> anyhow::Result<BTreeMap<String, u64>> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+
+    let mut context = validator.start_with_context().await;
+    let mut units = BTreeMap::new();
+
+    let init_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Initialize {
+            counter: counter_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Initialize {
+            name: COUNTER_NAME.to_string(),
+        }
+        .data(),
+    };
+    let cu = compute_units_for(&mut context, init_ix, &user).await?;
+    units.insert("initialize".to_string(), cu);
+
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+    let cu = compute_units_for(&mut context, increment_ix, &user).await?;
+    units.insert("increment".to_string(), cu);
+
+    let set_cooldown_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::SetCooldown {
+            counter: counter_pda,
+            authority: user.pubkey(),
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::SetCooldown {
+            cooldown_slots: 100,
+        }
+        .data(),
+    };
+    let cu = compute_units_for(&mut context, set_cooldown_ix, &user).await?;
+    units.insert("set_cooldown".to_string(), cu);
+
+    //the zero copy path, so the two can be compared side by side in the table
+    let (zero_copy_pda, _) =
+        Pubkey::find_program_address(&[b"zero_copy_counter"], &anchor_counter::ID);
+
+    let init_zero_copy_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::InitializeZeroCopy {
+            counter: zero_copy_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::InitializeZeroCopy {}.data(),
+    };
+    let cu = compute_units_for(&mut context, init_zero_copy_ix, &user).await?;
+    units.insert("initialize_zero_copy".to_string(), cu);
+
+    let increment_zero_copy_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::IncrementZeroCopy {
+            counter: zero_copy_pda,
+            user: user.pubkey(),
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::IncrementZeroCopy {}.data(),
+    };
+    let cu = compute_units_for(&mut context, increment_zero_copy_ix, &user).await?;
+    units.insert("increment_zero_copy".to_string(), cu);
+
+    Ok(units)
+}
+
+/// Sends `ix` as the only instruction in a transaction and returns the compute units from the
+/// transaction metadata. The instruction has to succeed for the number to mean anything.
+async fn compute_units_for(
+    context: &mut ProgramTestContext,
+    ix: Instruction,
+    signer: &Keypair,
+) -> anyhow::Result<u64> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        context.last_blockhash,
+    );
+
+    let res = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await?;
+    res.result?;
+
+    let metadata = res
+        .metadata
+        .ok_or_else(|| anyhow!("no transaction metadata returned"))?;
+
+    Ok(metadata.compute_units_consumed)
+}
+
+/// Builds the instruction -> old CU -> new CU table and returns it with the instructions that
+/// grew by more than `threshold_pct`, the measured instructions the baseline doesn't have and the
+/// baseline instructions that weren't measured
+fn compare(
+    old: &BTreeMap<String, u64>,
+    new: &BTreeMap<String, u64>,
+    threshold_pct: u64,
+) -> (String, Vec<String>, Vec<String>, Vec<String>) {
+    let mut table = String::new();
+    let mut regressions = Vec::new();
+    let mut untracked = Vec::new();
+    let mut removed = Vec::new();
+
+    writeln!(
+        table,
+        "{:<24} {:>10} {:>10} {:>10}",
+        "instruction", "old CU", "new CU", "change"
+    )
+    .unwrap();
+
+    let names: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for name in names {
+        let (old_cu, new_cu) = (old.get(name), new.get(name));
+        let change = match (old_cu, new_cu) {
+            (Some(&old_cu), Some(&new_cu)) => {
+                // integer math so the threshold check is exact
+                if new_cu * 100 > old_cu * (100 + threshold_pct) {
+                    regressions.push(name.clone());
+                }
+                format!("{:+}", new_cu as i64 - old_cu as i64)
+            }
+            (None, Some(_)) => {
+                untracked.push(name.clone());
+                "new".to_string()
+            }
+            (Some(_), None) => {
+                removed.push(name.clone());
+                "removed".to_string()
+            }
+            (None, None) => unreachable!(),
+        };
+
+        writeln!(
+            table,
+            "{:<24} {:>10} {:>10} {:>10}",
+            name,
+            old_cu.map_or("-".to_string(), u64::to_string),
+            new_cu.map_or("-".to_string(), u64::to_string),
+            change
+        )
+        .unwrap();
+    }
+
+    (table, regressions, untracked, removed)
+}
+
+/// The baseline file is a list of `key = value` lines with `#` comments.
+/// `threshold_pct` is the allowed growth in percent, every other key is an instruction name.
+struct Baseline {
+    threshold_pct: u64,
+    units: BTreeMap<String, u64>,
+}
+
+impl Baseline {
+    fn parse(contents: &str) -> anyhow::Result<Self> {
+        let mut threshold_pct = DEFAULT_THRESHOLD_PCT;
+        let mut units = BTreeMap::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("bad baseline line: {}", line))?;
+            let value: u64 = value.trim().parse()?;
+
+            match key.trim() {
+                "threshold_pct" => threshold_pct = value,
+                name => {
+                    units.insert(name.to_string(), value);
+                }
+            }
+        }
+
+        Ok(Self {
+            threshold_pct,
+            units,
+        })
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        writeln!(
+            out,
+            "# Compute units consumed per anchor_counter instruction."
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "# Regenerate with: UPDATE_CU_BASELINE=1 cargo test-sbf --test compute_units"
+        )
+        .unwrap();
+        writeln!(out, "threshold_pct = {}", self.threshold_pct).unwrap();
+        for (name, units) in &self.units {
+            writeln!(out, "{} = {}", name, units).unwrap();
+        }
+        out
+    }
+}
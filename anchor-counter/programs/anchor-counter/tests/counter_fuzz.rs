@@ -0,0 +1,522 @@
+use anchor_counter::CounterError;
+use anchor_counter_interface::ZeroCopyCounterPod;
+use anchor_lang::{
+    error::ErrorCode,
+    prelude::Pubkey,
+    solana_program::{self},
+    system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use proptest::prelude::*;
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program_test::{tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account, clock::Clock, compute_budget::ComputeBudgetInstruction, signature::Keypair,
+    signer::Signer, transaction::Transaction,
+};
+
+mod common;
+use common::*;
+
+/// How many funded users the generated sequences can pick from
+const NUM_USERS: usize = 3;
+
+/// Longest sequence of instructions a single test case will run
+const MAX_OPS: usize = 12;
+
+/// How many named counters the generated sequences can pick from, see `counter_name`
+const NUM_COUNTERS: usize = 3;
+
+/// The non-zero cooldown `SetCooldown` picks. `WarpSlots` moves the bank by up to twice this, so
+/// sequences see both increments inside and after the cooldown.
+const COOLDOWN_SLOTS: u64 = 10;
+
+/// Name of counter `counter`. The last one is as long as a name can be.
+fn counter_name(counter: usize) -> String {
+    match counter {
+        0 => COUNTER_NAME.to_string(),
+        1 => "builds".to_string(),
+        _ => "n".repeat(anchor_counter::MAX_NAME_LEN),
+    }
+}
+
+/// One step of a generated sequence. Each step is sent as its own transaction, except
+/// `WarpSlots`, which only moves the bank forward.
+/// New instructions in the program should get a variant here and a rule in `CounterModel::apply`.
+#[derive(Clone, Debug)]
+enum Op {
+    Initialize {
+        user: usize,
+        counter: usize,
+    },
+    /// A name one byte over the limit, there's no PDA for it
+    InitializeLongName {
+        user: usize,
+    },
+    Increment {
+        user: usize,
+        counter: usize,
+    },
+    IncrementBogus {
+        user: usize,
+        target: BogusTarget,
+    },
+    InitializeZeroCopy {
+        user: usize,
+    },
+    IncrementZeroCopy {
+        user: usize,
+    },
+    SetCooldown {
+        user: usize,
+        counter: usize,
+        cooldown_slots: u64,
+    },
+    WarpSlots {
+        slots: u64,
+    },
+}
+
+/// Accounts that are not the counter PDA but get passed in as the `counter` anyway
+#[derive(Clone, Debug)]
+enum BogusTarget {
+    /// The signing user's own system-owned account
+    UserAccount,
+    /// A PDA of our program derived from the wrong seed, which never gets initialized
+    WrongSeedPda,
+}
+
+/// What the model expects the instruction under test to do
+#[derive(Clone, Debug, PartialEq)]
+enum Outcome {
+    Success,
+    Failure(InstructionError),
+}
+
+impl Outcome {
+    fn anchor(code: ErrorCode) -> Self {
+        Outcome::Failure(InstructionError::Custom(code as u32))
+    }
+}
+
+/// Model of one user's `UserIncrements` account for one counter
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct UserIncrementsModel {
+    count: u64,
+    last_increment_slot: u64,
+}
+
+/// Model of one named `Counter`. `None` in `count` means its PDA hasn't been initialized yet.
+#[derive(Clone, Debug, Default)]
+struct NamedCounterModel {
+    count: Option<u64>,
+    /// The user who initialized the counter
+    authority: Option<usize>,
+    cooldown_slots: u64,
+    /// `None` until the user first increments the counter, which creates the account
+    user_increments: [Option<UserIncrementsModel>; NUM_USERS],
+}
+
+/// Pure Rust model of the on-chain `Counter`s and `ZeroCopyCounter`.
+/// `None` means that counter's PDA hasn't been initialized yet.
+#[derive(Clone, Debug, Default)]
+struct CounterModel {
+    counters: [NamedCounterModel; NUM_COUNTERS],
+    zero_copy_count: Option<u64>,
+    /// The bank's slot, only `WarpSlots` moves it
+    slot: u64,
+}
+
+impl CounterModel {
+    fn new(slot: u64) -> Self {
+        Self {
+            slot,
+            ..Self::default()
+        }
+    }
+
+    /// Applies `op` to the model and returns what the program should do with it
+    fn apply(&mut self, op: &Op) -> Outcome {
+        let slot = self.slot;
+        match op {
+            Op::Initialize { user, counter } => {
+                let counter = &mut self.counters[*counter];
+                match counter.count {
+                    None => {
+                        counter.count = Some(0);
+                        counter.authority = Some(*user);
+                        Outcome::Success
+                    }
+                    //system program create_account/allocate fails with AccountAlreadyInUse
+                    Some(_) => Outcome::Failure(InstructionError::Custom(0)),
+                }
+            }
+            Op::InitializeLongName { .. } => {
+                Outcome::Failure(InstructionError::Custom(CounterError::NameTooLong.into()))
+            }
+            Op::Increment { user, counter } => {
+                let counter = &mut self.counters[*counter];
+                let Some(count) = counter.count else {
+                    return Outcome::anchor(ErrorCode::AccountNotInitialized);
+                };
+                let user_increments = counter.user_increments[*user].unwrap_or_default();
+                //a user who has never incremented the counter has no cooldown to wait out
+                if user_increments.count > 0
+                    && slot < user_increments.last_increment_slot + counter.cooldown_slots
+                {
+                    return Outcome::Failure(InstructionError::Custom(
+                        CounterError::CooldownActive.into(),
+                    ));
+                }
+                counter.count = Some(count + 1);
+                counter.user_increments[*user] = Some(UserIncrementsModel {
+                    count: user_increments.count + 1,
+                    last_increment_slot: slot,
+                });
+                Outcome::Success
+            }
+            Op::IncrementBogus { target, .. } => match target {
+                BogusTarget::UserAccount => Outcome::anchor(ErrorCode::AccountOwnedByWrongProgram),
+                BogusTarget::WrongSeedPda => Outcome::anchor(ErrorCode::AccountNotInitialized),
+            },
+            Op::InitializeZeroCopy { .. } => match self.zero_copy_count {
+                None => {
+                    self.zero_copy_count = Some(0);
+                    Outcome::Success
+                }
+                Some(_) => Outcome::Failure(InstructionError::Custom(0)),
+            },
+            Op::IncrementZeroCopy { .. } => match self.zero_copy_count {
+                //AccountLoader goes straight to the owner check, there's no AccountNotInitialized
+                None => Outcome::anchor(ErrorCode::AccountOwnedByWrongProgram),
+                Some(count) => {
+                    self.zero_copy_count = Some(count + 1);
+                    Outcome::Success
+                }
+            },
+            Op::SetCooldown {
+                user,
+                counter,
+                cooldown_slots,
+            } => {
+                let counter = &mut self.counters[*counter];
+                match counter.authority {
+                    None => Outcome::anchor(ErrorCode::AccountNotInitialized),
+                    Some(authority) if authority != *user => {
+                        Outcome::anchor(ErrorCode::ConstraintHasOne)
+                    }
+                    Some(_) => {
+                        counter.cooldown_slots = *cooldown_slots;
+                        Outcome::Success
+                    }
+                }
+            }
+            Op::WarpSlots { slots } => {
+                self.slot += slots;
+                Outcome::Success
+            }
+        }
+    }
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    let bogus_target = prop_oneof![
+        Just(BogusTarget::UserAccount),
+        Just(BogusTarget::WrongSeedPda)
+    ];
+
+    //weighted towards increments since those are the interesting state transitions
+    prop_oneof![
+        2 => (0..NUM_USERS, 0..NUM_COUNTERS).prop_map(|(user, counter)| Op::Initialize { user, counter }),
+        1 => (0..NUM_USERS).prop_map(|user| Op::InitializeLongName { user }),
+        4 => (0..NUM_USERS, 0..NUM_COUNTERS).prop_map(|(user, counter)| Op::Increment { user, counter }),
+        1 => (0..NUM_USERS, bogus_target).prop_map(|(user, target)| Op::IncrementBogus { user, target }),
+        1 => (0..NUM_USERS).prop_map(|user| Op::InitializeZeroCopy { user }),
+        2 => (0..NUM_USERS).prop_map(|user| Op::IncrementZeroCopy { user }),
+        2 => (0..NUM_USERS, 0..NUM_COUNTERS, prop_oneof![Just(0), Just(COOLDOWN_SLOTS)])
+            .prop_map(|(user, counter, cooldown_slots)| Op::SetCooldown { user, counter, cooldown_slots }),
+        2 => (1..=2 * COOLDOWN_SLOTS).prop_map(|slots| Op::WarpSlots { slots }),
+    ]
+}
+
+proptest! {
+    // every case spins up a fresh ProgramTest bank, so keep the case count modest
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn fuzz_counter_against_model(ops in prop::collection::vec(op_strategy(), 1..=MAX_OPS)) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(run_sequence(ops))?;
+    }
+}
+
+/// Runs the whole sequence against a fresh validator, checking every step against the model
+async fn run_sequence(ops: Vec<Op>) -> Result<(), TestCaseError> {
+    let mut validator = ProgramTest::new("anchor_counter", anchor_counter::ID, None);
+    let users: Vec<Keypair> = (0..NUM_USERS)
+        .map(|_| add_funded_user(&mut validator))
+        .collect();
+
+    let pdas = Pdas::new(&users);
+
+    let mut context = validator.start_with_context().await;
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let mut model = CounterModel::new(clock.slot);
+
+    for (step, op) in ops.iter().enumerate() {
+        let user = match op {
+            Op::Initialize { user, .. }
+            | Op::InitializeLongName { user }
+            | Op::Increment { user, .. }
+            | Op::IncrementBogus { user, .. }
+            | Op::InitializeZeroCopy { user }
+            | Op::IncrementZeroCopy { user }
+            | Op::SetCooldown { user, .. } => &users[*user],
+            Op::WarpSlots { slots } => {
+                model.apply(op);
+                context
+                    .warp_to_slot(model.slot)
+                    .map_err(|e| TestCaseError::fail(format!("warp by {}: {:?}", slots, e)))?;
+                //the old blockhash may not survive the warp
+                context.last_blockhash = context
+                    .get_new_latest_blockhash()
+                    .await
+                    .map_err(|e| TestCaseError::fail(format!("new blockhash: {:?}", e)))?;
+                check_state(&mut context, &model, &pdas).await?;
+                continue;
+            }
+        };
+
+        let ix = match op {
+            Op::Initialize { counter, .. } => initialize_ix(
+                pdas.counters[*counter],
+                user.pubkey(),
+                counter_name(*counter),
+            ),
+            //there's no PDA for a name this long, so any address will do
+            Op::InitializeLongName { .. } => initialize_ix(
+                pdas.wrong_seed,
+                user.pubkey(),
+                "n".repeat(anchor_counter::MAX_NAME_LEN + 1),
+            ),
+            Op::Increment { counter, .. } => increment_ix(pdas.counters[*counter], user.pubkey()),
+            Op::IncrementBogus { target, .. } => match target {
+                BogusTarget::UserAccount => increment_ix(user.pubkey(), user.pubkey()),
+                BogusTarget::WrongSeedPda => increment_ix(pdas.wrong_seed, user.pubkey()),
+            },
+            Op::InitializeZeroCopy { .. } => initialize_zero_copy_ix(pdas.zero_copy, user.pubkey()),
+            Op::IncrementZeroCopy { .. } => increment_zero_copy_ix(pdas.zero_copy, user.pubkey()),
+            Op::SetCooldown {
+                counter,
+                cooldown_slots,
+                ..
+            } => set_cooldown_ix(pdas.counters[*counter], user.pubkey(), *cooldown_slots),
+            Op::WarpSlots { .. } => unreachable!("handled above"),
+        };
+
+        //ProgramTest doesn't move the blockhash between transactions, so two identical
+        //increments would get rejected as AlreadyProcessed. The compute budget ix makes every
+        //transaction unique, which also means the instruction under test is always at index 1.
+        let unique_ix = ComputeBudgetInstruction::set_compute_unit_limit(200_000 + step as u32);
+        let tx = Transaction::new_signed_with_payer(
+            &[unique_ix, ix],
+            Some(&user.pubkey()),
+            &[user],
+            context.last_blockhash,
+        );
+
+        let res = context.banks_client.process_transaction(tx).await;
+
+        match model.apply(op) {
+            Outcome::Success => {
+                prop_assert!(
+                    res.is_ok(),
+                    "step {} {:?} should succeed, got {:?}",
+                    step,
+                    op,
+                    res
+                )
+            }
+            Outcome::Failure(expected) => {
+                prop_assert!(
+                    res.is_err(),
+                    "step {} {:?} should fail with {:?}",
+                    step,
+                    op,
+                    expected
+                );
+                let (index, ix_err) = instruction_error(res);
+                prop_assert_eq!(
+                    index,
+                    1,
+                    "step {} {:?} failed at the wrong instruction",
+                    step,
+                    op
+                );
+                prop_assert_eq!(ix_err, expected, "step {} {:?}", step, op);
+            }
+        }
+
+        check_state(&mut context, &model, &pdas).await?;
+    }
+
+    Ok(())
+}
+
+/// The accounts the generated instructions point at
+struct Pdas {
+    /// By the index of their name in `counter_name`
+    counters: [Pubkey; NUM_COUNTERS],
+    /// Each user's `UserIncrements` of each counter, by counter then user
+    user_increments: [[Pubkey; NUM_USERS]; NUM_COUNTERS],
+    zero_copy: Pubkey,
+    /// Derived from the wrong seed, should never exist
+    wrong_seed: Pubkey,
+}
+
+impl Pdas {
+    fn new(users: &[Keypair]) -> Self {
+        let pda = |seed: &[u8]| Pubkey::find_program_address(&[seed], &anchor_counter::ID).0;
+        let counters: [Pubkey; NUM_COUNTERS] =
+            std::array::from_fn(|counter| counter_pda(&counter_name(counter)));
+        Self {
+            counters,
+            user_increments: counters.map(|counter| {
+                std::array::from_fn(|user| user_increments_pda(&counter, &users[user].pubkey()))
+            }),
+            zero_copy: pda(b"zero_copy_counter"),
+            wrong_seed: pda(b"counter_bad"),
+        }
+    }
+}
+
+/// Compares the on-chain accounts with the model after a step
+async fn check_state(
+    context: &mut ProgramTestContext,
+    model: &CounterModel,
+    pdas: &Pdas,
+) -> Result<(), TestCaseError> {
+    for (i, (model, pda)) in model.counters.iter().zip(pdas.counters).enumerate() {
+        let counter_acct = get_account(context, pda).await;
+
+        match (model.count, counter_acct) {
+            (None, None) => {}
+            (Some(count), Some(account)) => {
+                let counter =
+                    anchor_counter::Counter::try_deserialize(&mut account.data.as_slice())
+                        .map_err(|e| TestCaseError::fail(format!("bad counter data: {:?}", e)))?;
+                prop_assert_eq!(counter.count, count);
+                prop_assert_eq!(counter.cooldown_slots, model.cooldown_slots);
+                prop_assert_eq!(counter.name, counter_name(i));
+            }
+            (expected, actual) => prop_assert!(
+                false,
+                "model count {:?} but counter {} account is {:?}",
+                expected,
+                i,
+                actual
+            ),
+        }
+
+        for (user, expected) in model.user_increments.iter().enumerate() {
+            let actual = match get_account(context, pdas.user_increments[i][user]).await {
+                None => None,
+                Some(account) => {
+                    let user_increments = anchor_counter::UserIncrements::try_deserialize(
+                        &mut account.data.as_slice(),
+                    )
+                    .map_err(|e| {
+                        TestCaseError::fail(format!("bad user increments data: {:?}", e))
+                    })?;
+                    Some(UserIncrementsModel {
+                        count: user_increments.count,
+                        last_increment_slot: user_increments.last_increment_slot,
+                    })
+                }
+            };
+            prop_assert_eq!(actual, *expected, "counter {} user {}", i, user);
+        }
+    }
+
+    let zero_copy_acct = get_account(context, pdas.zero_copy).await;
+
+    match (model.zero_copy_count, zero_copy_acct) {
+        (None, None) => {}
+        (Some(count), Some(account)) => {
+            let counter = ZeroCopyCounterPod::from_account_data(&account.data)
+                .map_err(|e| TestCaseError::fail(format!("bad zero copy counter data: {:?}", e)))?;
+            prop_assert_eq!(counter.count, count);
+        }
+        (expected, actual) => prop_assert!(
+            false,
+            "model zero copy count {:?} but zero copy counter account is {:?}",
+            expected,
+            actual
+        ),
+    }
+
+    prop_assert!(get_account(context, pdas.wrong_seed).await.is_none());
+
+    Ok(())
+}
+
+async fn get_account(context: &mut ProgramTestContext, address: Pubkey) -> Option<Account> {
+    context.banks_client.get_account(address).await.unwrap()
+}
+
+fn initialize_ix(counter: Pubkey, user: Pubkey, name: String) -> Instruction {
+    Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Initialize {
+            counter,
+            user,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Initialize { name }.data(),
+    }
+}
+
+fn increment_ix(counter: Pubkey, user: Pubkey) -> Instruction {
+    Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter,
+            user,
+            user_increments: user_increments_pda(&counter, &user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    }
+}
+
+fn initialize_zero_copy_ix(counter: Pubkey, user: Pubkey) -> Instruction {
+    Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::InitializeZeroCopy {
+            counter,
+            user,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::InitializeZeroCopy {}.data(),
+    }
+}
+
+fn increment_zero_copy_ix(counter: Pubkey, user: Pubkey) -> Instruction {
+    Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::IncrementZeroCopy { counter, user }
+            .to_account_metas(None),
+        data: anchor_counter::instruction::IncrementZeroCopy {}.data(),
+    }
+}
+
+fn set_cooldown_ix(counter: Pubkey, authority: Pubkey, cooldown_slots: u64) -> Instruction {
+    Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::SetCooldown { counter, authority }
+            .to_account_metas(None),
+        data: anchor_counter::instruction::SetCooldown { cooldown_slots }.data(),
+    }
+}
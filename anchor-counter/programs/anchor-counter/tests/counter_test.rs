@@ -1,12 +1,26 @@
+use anchor_counter_interface::{
+    counter_authority_filter, counter_count_filter, counter_data_size_filter,
+    counter_program_accounts_config, decode_counters, COUNTER_ACCOUNT_DISCM, COUNTER_ACCOUNT_LEN,
+    COUNTER_AUTHORITY_OFFSET, COUNTER_COOLDOWN_OFFSET, COUNTER_COUNT_OFFSET, COUNTER_MAX_NAME_LEN,
+    COUNTER_NAME_OFFSET,
+};
 use anchor_lang::{
-    prelude::Pubkey,
+    error::ErrorCode,
+    prelude::{Pubkey, Rent},
     solana_program::{self},
-    system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+    system_program, AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas,
 };
 use anyhow::Ok;
-use solana_program::instruction::Instruction;
-use solana_program_test::{tokio, ProgramTest, ProgramTestContext};
-use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+use solana_program::instruction::{Instruction, InstructionError};
+use solana_program_test::tokio;
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+mod common;
+use common::*;
 
 #[tokio::test]
 async fn test_initialize() {
@@ -26,7 +40,10 @@ async fn test_initialize() {
             system_program: system_program::ID,
         }
         .to_account_metas(None),
-        data: anchor_counter::instruction::Initialize {}.data(),
+        data: anchor_counter::instruction::Initialize {
+            name: COUNTER_NAME.to_string(),
+        }
+        .data(),
     };
 
     let init_tx = Transaction::new_signed_with_payer(
@@ -45,6 +62,9 @@ async fn test_initialize() {
     let counter: anchor_counter::Counter = load_and_deserialize(context, counter_pda).await;
 
     assert_eq!(counter.count, 0);
+    assert_eq!(counter.authority, user.pubkey());
+    assert_eq!(counter.cooldown_slots, 0);
+    assert_eq!(counter.name, COUNTER_NAME);
 }
 
 #[tokio::test]
@@ -65,7 +85,10 @@ async fn test_increment() {
             system_program: system_program::ID,
         }
         .to_account_metas(None),
-        data: anchor_counter::instruction::Initialize {}.data(),
+        data: anchor_counter::instruction::Initialize {
+            name: COUNTER_NAME.to_string(),
+        }
+        .data(),
     };
 
     let increment_ix = Instruction {
@@ -73,6 +96,8 @@ async fn test_increment() {
         accounts: anchor_counter::accounts::Increment {
             counter: counter_pda,
             user: context.payer.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &context.payer.pubkey()),
+            system_program: system_program::ID,
         }
         .to_account_metas(None),
         data: anchor_counter::instruction::Increment {}.data(),
@@ -85,10 +110,11 @@ async fn test_increment() {
         context.last_blockhash,
     );
 
-    let _res = context
+    context
         .banks_client
         .process_transaction(init_increment_tx)
-        .await;
+        .await
+        .unwrap();
 
     let counter: anchor_counter::Counter = load_and_deserialize(context, counter_pda).await;
 
@@ -113,7 +139,10 @@ async fn test_double_increment() -> anyhow::Result<()> {
             system_program: system_program::ID,
         }
         .to_account_metas(None),
-        data: anchor_counter::instruction::Initialize {}.data(),
+        data: anchor_counter::instruction::Initialize {
+            name: COUNTER_NAME.to_string(),
+        }
+        .data(),
     };
 
     let increment_ix = Instruction {
@@ -121,6 +150,8 @@ async fn test_double_increment() -> anyhow::Result<()> {
         accounts: anchor_counter::accounts::Increment {
             counter: counter_pda,
             user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
         }
         .to_account_metas(None),
         data: anchor_counter::instruction::Increment {}.data(),
@@ -135,10 +166,11 @@ async fn test_double_increment() -> anyhow::Result<()> {
         context.last_blockhash,
     );
 
-    let _res = context
+    context
         .banks_client
         .process_transaction(init_increment_tx)
-        .await;
+        .await
+        .unwrap();
 
     let counter: anchor_counter::Counter = load_and_deserialize(context, counter_pda).await;
 
@@ -157,7 +189,7 @@ async fn test_bogus_counter_acct() -> anyhow::Result<()> {
 
     let mut context = validator.start_with_context().await;
 
-    initialize(&mut context, &user, &counter_pda).await?;
+    initialize(&mut context, &user, &counter_pda, COUNTER_NAME).await?;
 
     //let (bogus_pda, _) = Pubkey::find_program_address(&[b"counter_bad"], &anchor_counter::ID);
 
@@ -166,6 +198,8 @@ async fn test_bogus_counter_acct() -> anyhow::Result<()> {
         accounts: anchor_counter::accounts::Increment {
             counter: user.pubkey(), /*bogus_pda*/
             user: user.pubkey(),
+            user_increments: user_increments_pda(&user.pubkey(), &user.pubkey()),
+            system_program: system_program::ID,
         }
         .to_account_metas(None),
         data: anchor_counter::instruction::Increment {}.data(),
@@ -180,92 +214,615 @@ async fn test_bogus_counter_acct() -> anyhow::Result<()> {
 
     let res = context.banks_client.process_transaction(increment_tx).await;
 
-    assert!(res.is_err());
+    //the user account is owned by the system program, so anchor rejects it before even looking at the seeds
+    assert_anchor_error(res, 0, ErrorCode::AccountOwnedByWrongProgram);
 
     Ok(())
 }
 
-/// Struct set up to hold the validator, an optional user account, and the counter PDA.
-/// Use SetUpTest::new() to create a new instance.
-pub struct SetUpTest {
-    pub validator: ProgramTest,
-    pub user: Keypair,
-    pub counter_pda: Pubkey,
-}
+#[tokio::test]
+async fn test_increment_before_initialize() -> anyhow::Result<()> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
 
-/// Returns the validator, an optional funded user account, and the counter PDA
-impl SetUpTest {
-    pub fn new() -> Self {
-        //Both of these work
-
-        // let mut validator = ProgramTest::default();
-        // validator.add_program("anchor_counter", anchor_counter::ID, None);
-        let mut validator = ProgramTest::new("anchor_counter", anchor_counter::ID, None);
-
-        //create a new user and fund with 1 SOL
-        //add the user to the validator / ledger
-        let user = Keypair::new();
-        validator.add_account(
-            user.pubkey(),
-            Account {
-                lamports: 1_000_000_000,
-                ..Account::default()
-            },
-        );
-
-        //get the counter PDA -- uses the same seed we used in the anchor program
-        let (counter_pda, _) = Pubkey::find_program_address(&[b"counter"], &anchor_counter::ID);
-
-        Self {
-            validator,
-            user,
-            counter_pda,
+    let mut context = validator.start_with_context().await;
+
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
         }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+
+    let increment_tx = Transaction::new_signed_with_payer(
+        &[increment_ix],
+        Some(&user.pubkey()),
+        &[&user],
+        context.last_blockhash,
+    );
+
+    let res = context.banks_client.process_transaction(increment_tx).await;
+
+    assert_anchor_error(res, 0, ErrorCode::AccountNotInitialized);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrong_seeds_counter_acct() -> anyhow::Result<()> {
+    let SetUpTest {
+        mut validator,
+        user,
+        counter_pda: _,
+    } = SetUpTest::new();
+
+    //a perfectly valid Counter account owned by our program, just not at the PDA for its name
+    let bogus_counter = Pubkey::new_unique();
+    let mut data = Vec::new();
+    anchor_counter::Counter {
+        count: 0,
+        authority: user.pubkey(),
+        cooldown_slots: 0,
+        name: COUNTER_NAME.to_string(),
     }
+    .try_serialize(&mut data)?;
+    validator.add_account(
+        bogus_counter,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: anchor_counter::ID,
+            ..Account::default()
+        },
+    );
+
+    let mut context = validator.start_with_context().await;
+
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: bogus_counter,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&bogus_counter, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+
+    let increment_tx = Transaction::new_signed_with_payer(
+        &[increment_ix],
+        Some(&user.pubkey()),
+        &[&user],
+        context.last_blockhash,
+    );
+
+    let res = context.banks_client.process_transaction(increment_tx).await;
+
+    assert_anchor_error(res, 0, ErrorCode::ConstraintSeeds);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrong_discriminator_counter_acct() -> anyhow::Result<()> {
+    let SetUpTest {
+        mut validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+
+    //owned by our program and at the right address, but the data isn't a Counter
+    validator.add_account(
+        counter_pda,
+        Account {
+            lamports: Rent::default().minimum_balance(16),
+            data: vec![0u8; 16],
+            owner: anchor_counter::ID,
+            ..Account::default()
+        },
+    );
+
+    let mut context = validator.start_with_context().await;
+
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+
+    let increment_tx = Transaction::new_signed_with_payer(
+        &[increment_ix],
+        Some(&user.pubkey()),
+        &[&user],
+        context.last_blockhash,
+    );
+
+    let res = context.banks_client.process_transaction(increment_tx).await;
+
+    assert_anchor_error(res, 0, ErrorCode::AccountDiscriminatorMismatch);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_double_initialize() -> anyhow::Result<()> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+
+    let mut context = validator.start_with_context().await;
+
+    initialize(&mut context, &user, &counter_pda, COUNTER_NAME).await?;
+
+    //the second init is bundled after an increment so we can check the failing index is the init
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+
+    let init_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Initialize {
+            counter: counter_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Initialize {
+            name: COUNTER_NAME.to_string(),
+        }
+        .data(),
+    };
+
+    let increment_init_tx = Transaction::new_signed_with_payer(
+        &[increment_ix, init_ix],
+        Some(&user.pubkey()),
+        &[&user],
+        context.last_blockhash,
+    );
+
+    let res = context
+        .banks_client
+        .process_transaction(increment_init_tx)
+        .await;
+
+    //`init` CPIs into the system program's create_account, which fails with AccountAlreadyInUse (0)
+    //this is the "custom program error: 0x0" the counter_client matches on
+    assert_instruction_error(res, 1, InstructionError::Custom(0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_named_counters_are_separate() -> anyhow::Result<()> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+
+    let mut context = validator.start_with_context().await;
+
+    //the local counter_pda shadows the helper
+    let builds_pda = common::counter_pda("builds");
+    initialize(&mut context, &user, &counter_pda, COUNTER_NAME).await?;
+    initialize(&mut context, &user, &builds_pda, "builds").await?;
+
+    let increment_ix = |counter| Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+
+    let increment_tx = Transaction::new_signed_with_payer(
+        &[
+            increment_ix(counter_pda),
+            increment_ix(counter_pda),
+            increment_ix(builds_pda),
+        ],
+        Some(&user.pubkey()),
+        &[&user],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(increment_tx)
+        .await
+        .unwrap();
+
+    let builds_acct = context.banks_client.get_account(builds_pda).await?.unwrap();
+    let builds = anchor_counter::Counter::try_deserialize(&mut builds_acct.data.as_slice())?;
+    assert_eq!(builds.count, 1);
+    assert_eq!(builds.name, "builds");
+
+    let deploys: anchor_counter::Counter = load_and_deserialize(context, counter_pda).await;
+    assert_eq!(deploys.count, 2);
+    assert_eq!(deploys.name, COUNTER_NAME);
+
+    Ok(())
 }
 
-///Function that initializes the counter account
-///Useful for testing things you want to fail but need to initialize the counter account first
-pub async fn initialize(
-    ctx: &mut ProgramTestContext,
-    user: &Keypair,
-    counter_pda: &Pubkey,
-) -> anyhow::Result<()> {
+#[tokio::test]
+async fn test_name_too_long() -> anyhow::Result<()> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda: _,
+    } = SetUpTest::new();
+
+    let mut context = validator.start_with_context().await;
+
+    //there's no PDA for a name this long, so any address will do
+    let name = "n".repeat(anchor_counter::MAX_NAME_LEN + 1);
     let init_ix = Instruction {
         program_id: anchor_counter::ID,
         accounts: anchor_counter::accounts::Initialize {
-            counter: *counter_pda,
+            counter: Pubkey::new_unique(),
             user: user.pubkey(),
             system_program: system_program::ID,
         }
         .to_account_metas(None),
-        data: anchor_counter::instruction::Initialize {}.data(),
+        data: anchor_counter::instruction::Initialize { name }.data(),
     };
 
     let init_tx = Transaction::new_signed_with_payer(
         &[init_ix],
         Some(&user.pubkey()),
         &[&user],
-        ctx.last_blockhash,
+        context.last_blockhash,
     );
 
-    ctx.banks_client.process_transaction(init_tx).await.unwrap();
+    let res = context.banks_client.process_transaction(init_tx).await;
+
+    assert_instruction_error(
+        res,
+        0,
+        InstructionError::Custom(anchor_counter::CounterError::NameTooLong.into()),
+    );
 
     Ok(())
 }
 
-/// Fetch the account from the ProgramTestContext and deserialize it.
-/// Taken from the MarginFi Github tests: https://github.com/mrgnlabs/marginfi-v2/blob/main/test-utils/src/test.rs#L468
-pub async fn load_and_deserialize<T: AccountDeserialize>(
-    mut ctx: ProgramTestContext,
-    address: Pubkey,
-) -> T {
-    let account = ctx
+#[tokio::test]
+async fn test_name_empty() -> anyhow::Result<()> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda: _,
+    } = SetUpTest::new();
+
+    let mut context = validator.start_with_context().await;
+
+    //an empty seed adds nothing, so this is also the PDA of the old single `[b"counter"]` counter
+    let empty_pda = counter_pda("");
+    let (singleton_pda, _) = Pubkey::find_program_address(&[b"counter"], &anchor_counter::ID);
+    assert_eq!(empty_pda, singleton_pda);
+
+    let init_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Initialize {
+            counter: empty_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Initialize {
+            name: String::new(),
+        }
+        .data(),
+    };
+
+    let init_tx = Transaction::new_signed_with_payer(
+        &[init_ix],
+        Some(&user.pubkey()),
+        &[&user],
+        context.last_blockhash,
+    );
+
+    let res = context.banks_client.process_transaction(init_tx).await;
+
+    assert_instruction_error(
+        res,
+        0,
+        InstructionError::Custom(anchor_counter::CounterError::NameEmpty.into()),
+    );
+    assert!(context.banks_client.get_account(empty_pda).await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cooldown() -> anyhow::Result<()> {
+    let SetUpTest {
+        mut validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+    let other_user = add_funded_user(&mut validator);
+
+    let mut context = validator.start_with_context().await;
+
+    initialize(&mut context, &user, &counter_pda, COUNTER_NAME).await?;
+    set_cooldown(&mut context, &user, &counter_pda, 100).await?;
+
+    //warping first pins down the slot of the first increment
+    let start = 1_000;
+    context.warp_to_slot(start)?;
+    increment(&mut context, &user, &counter_pda).await?;
+
+    //halfway through the cooldown the same user is turned away
+    context.warp_to_slot(start + 50)?;
+    let res = increment(&mut context, &user, &counter_pda).await;
+    assert_instruction_error(
+        res,
+        0,
+        InstructionError::Custom(anchor_counter::CounterError::CooldownActive.into()),
+    );
+
+    //the cooldown is per user, so someone who hasn't incremented yet goes right through
+    increment(&mut context, &other_user, &counter_pda).await?;
+
+    //and once it's over the first user can increment again
+    context.warp_to_slot(start + 100)?;
+    increment(&mut context, &user, &counter_pda).await?;
+
+    let counter_acct = context
         .banks_client
-        .get_account(address)
+        .get_account(counter_pda)
+        .await?
+        .unwrap();
+    let counter = anchor_counter::Counter::try_deserialize(&mut counter_acct.data.as_slice())?;
+    assert_eq!(counter.count, 3);
+
+    let user_increments: anchor_counter::UserIncrements =
+        load_and_deserialize(context, user_increments_pda(&counter_pda, &user.pubkey())).await;
+    assert_eq!(user_increments.count, 2);
+    assert_eq!(user_increments.last_increment_slot, start + 100);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cooldown_off() -> anyhow::Result<()> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+
+    let mut context = validator.start_with_context().await;
+
+    initialize(&mut context, &user, &counter_pda, COUNTER_NAME).await?;
+    set_cooldown(&mut context, &user, &counter_pda, 100).await?;
+    increment(&mut context, &user, &counter_pda).await?;
+
+    //setting it back to 0 lets the user increment again in the same slot
+    set_cooldown(&mut context, &user, &counter_pda, 0).await?;
+    increment(&mut context, &user, &counter_pda).await?;
+
+    let counter: anchor_counter::Counter = load_and_deserialize(context, counter_pda).await;
+    assert_eq!(counter.count, 2);
+    assert_eq!(counter.cooldown_slots, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_cooldown_not_authority() -> anyhow::Result<()> {
+    let SetUpTest {
+        mut validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+    let other_user = add_funded_user(&mut validator);
+
+    let mut context = validator.start_with_context().await;
+
+    initialize(&mut context, &user, &counter_pda, COUNTER_NAME).await?;
+
+    let res = set_cooldown(&mut context, &other_user, &counter_pda, 100).await;
+
+    assert_anchor_error(res, 0, ErrorCode::ConstraintHasOne);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scan_helpers_match_counter_layout() -> anyhow::Result<()> {
+    let SetUpTest {
+        mut validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+
+    //has the Counter discriminator, so the scan finds it, but is too short to be a Counter
+    let truncated = Pubkey::new_unique();
+    validator.add_account(
+        truncated,
+        Account {
+            lamports: Rent::default().minimum_balance(8),
+            data: COUNTER_ACCOUNT_DISCM.to_vec(),
+            owner: anchor_counter::ID,
+            ..Account::default()
+        },
+    );
+
+    let mut context = validator.start_with_context().await;
+
+    initialize(&mut context, &user, &counter_pda, COUNTER_NAME).await?;
+
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+    let increment_tx = Transaction::new_signed_with_payer(
+        &[increment_ix],
+        Some(&user.pubkey()),
+        &[&user],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(increment_tx)
+        .await
+        .unwrap();
+
+    let counter_acct = context
+        .banks_client
+        .get_account(counter_pda)
+        .await?
+        .unwrap();
+    let truncated_acct = context.banks_client.get_account(truncated).await?.unwrap();
+
+    //the interface's offsets have to line up with what the program actually writes
+    let data = &counter_acct.data;
+    assert_eq!(COUNTER_MAX_NAME_LEN, anchor_counter::MAX_NAME_LEN);
+    assert_eq!(data.len(), COUNTER_ACCOUNT_LEN);
+    assert_eq!(
+        data[COUNTER_COUNT_OFFSET..COUNTER_COUNT_OFFSET + 8],
+        1u64.to_le_bytes()
+    );
+    assert_eq!(
+        data[COUNTER_AUTHORITY_OFFSET..COUNTER_AUTHORITY_OFFSET + 32],
+        user.pubkey().to_bytes()
+    );
+    assert_eq!(
+        data[COUNTER_COOLDOWN_OFFSET..COUNTER_COOLDOWN_OFFSET + 8],
+        0u64.to_le_bytes()
+    );
+    assert_eq!(
+        data[COUNTER_NAME_OFFSET..COUNTER_NAME_OFFSET + 4],
+        (COUNTER_NAME.len() as u32).to_le_bytes()
+    );
+
+    //run the filters the way the rpc node would
+    let counter_shared = AccountSharedData::from(counter_acct.clone());
+    let truncated_shared = AccountSharedData::from(truncated_acct.clone());
+    let config = counter_program_accounts_config();
+    for filter in config.filters.as_ref().unwrap() {
+        assert!(filter.allows(&counter_shared));
+        assert!(filter.allows(&truncated_shared));
+    }
+    assert!(counter_data_size_filter().allows(&counter_shared));
+    assert!(!counter_data_size_filter().allows(&truncated_shared));
+    assert!(counter_count_filter(1).allows(&counter_shared));
+    assert!(!counter_count_filter(0).allows(&counter_shared));
+    assert!(counter_authority_filter(&user.pubkey()).allows(&counter_shared));
+    assert!(!counter_authority_filter(&Pubkey::new_unique()).allows(&counter_shared));
+
+    let decoded = decode_counters(vec![
+        (counter_pda, counter_acct),
+        (truncated, truncated_acct),
+    ]);
+    assert_eq!(decoded.counters.len(), 1);
+    assert_eq!(decoded.counters[0].0, counter_pda);
+    assert_eq!(decoded.counters[0].1.count, 1);
+    assert_eq!(decoded.counters[0].1.name, COUNTER_NAME);
+    assert_eq!(decoded.undecodable.len(), 1);
+    assert_eq!(decoded.undecodable[0].0, truncated);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_zero_copy_increment() -> anyhow::Result<()> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda: _,
+    } = SetUpTest::new();
+
+    let mut context = validator.start_with_context().await;
+
+    let (zero_copy_pda, _) =
+        Pubkey::find_program_address(&[b"zero_copy_counter"], &anchor_counter::ID);
+
+    let init_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::InitializeZeroCopy {
+            counter: zero_copy_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::InitializeZeroCopy {}.data(),
+    };
+
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::IncrementZeroCopy {
+            counter: zero_copy_pda,
+            user: user.pubkey(),
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::IncrementZeroCopy {}.data(),
+    };
+
+    let init_increment_tx = Transaction::new_signed_with_payer(
+        &[init_ix, increment_ix.clone(), increment_ix],
+        Some(&user.pubkey()),
+        &[&user],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(init_increment_tx)
         .await
-        .unwrap() //unwraps the Result into an Option<Account>
-        .unwrap(); //unwraps the Option<Account> into an Account
+        .unwrap();
 
-    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    let account = context
+        .banks_client
+        .get_account(zero_copy_pda)
+        .await?
+        .unwrap();
+
+    //zero copy accounts can't go through AccountDeserialize, so read them the way a client would
+    //with the bytemuck view from the interface crate
+    let counter = anchor_counter_interface::ZeroCopyCounterPod::from_account_data(&account.data)?;
+    assert_eq!(counter.count, 2);
+    assert_eq!(
+        account.data.len(),
+        8 + std::mem::size_of::<anchor_counter::ZeroCopyCounter>()
+    );
+
+    Ok(())
 }
@@ -0,0 +1,342 @@
+use anchor_counter_interface::{
+    AnchorCounterProgramIx, CounterAccount, IncrementKeys, InitializeIxArgs, InitializeKeys,
+    SetCooldownIxArgs, SetCooldownKeys, COUNTER_ACCOUNT_DISCM,
+};
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{self},
+    system_program, InstructionData, ToAccountMetas,
+};
+use solana_program::instruction::Instruction;
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
+use std::fmt::Write;
+
+mod common;
+use common::*;
+
+// Snapshots live in tests/snapshots. When one changes, review it with `cargo insta review`
+// (cargo install cargo-insta) or accept everything with INSTA_UPDATE=always.
+
+#[test]
+fn snapshot_instruction_data() {
+    let user = Keypair::new();
+    let counter_pda = counter_pda(COUNTER_NAME);
+    let labels = Labels::new(counter_pda, user.pubkey());
+
+    let anchor_init_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Initialize {
+            counter: counter_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Initialize {
+            name: COUNTER_NAME.to_string(),
+        }
+        .data(),
+    };
+    let interface_init_ix = anchor_counter_interface::initialize_ix_with_program_id(
+        anchor_counter::ID,
+        InitializeKeys {
+            counter: counter_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        },
+        InitializeIxArgs {
+            name: COUNTER_NAME.to_string(),
+        },
+    )
+    .unwrap();
+
+    let anchor_increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+    let interface_increment_ix = anchor_counter_interface::increment_ix_with_program_id(
+        anchor_counter::ID,
+        IncrementKeys {
+            counter: counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
+        },
+    )
+    .unwrap();
+
+    let anchor_set_cooldown_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::SetCooldown {
+            counter: counter_pda,
+            authority: user.pubkey(),
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::SetCooldown {
+            cooldown_slots: 100,
+        }
+        .data(),
+    };
+    let interface_set_cooldown_ix = anchor_counter_interface::set_cooldown_ix_with_program_id(
+        anchor_counter::ID,
+        SetCooldownKeys {
+            counter: counter_pda,
+            authority: user.pubkey(),
+        },
+        SetCooldownIxArgs {
+            cooldown_slots: 100,
+        },
+    )
+    .unwrap();
+
+    //the generated interface has to agree with the program byte for byte
+    assert_eq!(anchor_init_ix, interface_init_ix);
+    assert_eq!(anchor_increment_ix, interface_increment_ix);
+    assert_eq!(anchor_set_cooldown_ix, interface_set_cooldown_ix);
+
+    let mut out = String::new();
+    for (source, ix) in [
+        ("anchor_counter::instruction::Initialize", &anchor_init_ix),
+        (
+            "anchor_counter_interface::initialize_ix",
+            &interface_init_ix,
+        ),
+        (
+            "anchor_counter::instruction::Increment",
+            &anchor_increment_ix,
+        ),
+        (
+            "anchor_counter_interface::increment_ix",
+            &interface_increment_ix,
+        ),
+        (
+            "anchor_counter::instruction::SetCooldown",
+            &anchor_set_cooldown_ix,
+        ),
+        (
+            "anchor_counter_interface::set_cooldown_ix",
+            &interface_set_cooldown_ix,
+        ),
+    ] {
+        writeln!(out, "{}", source).unwrap();
+        out.push_str(&render_instruction(ix, &labels));
+        out.push('\n');
+    }
+
+    insta::assert_snapshot!("instruction_data", out);
+}
+
+#[tokio::test]
+async fn snapshot_initialize_and_increment() -> anyhow::Result<()> {
+    let SetUpTest {
+        validator,
+        user,
+        counter_pda,
+    } = SetUpTest::new();
+
+    let mut context = validator.start_with_context().await;
+    let labels = Labels::new(counter_pda, user.pubkey());
+
+    let init_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Initialize {
+            counter: counter_pda,
+            user: user.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Initialize {
+            name: COUNTER_NAME.to_string(),
+        }
+        .data(),
+    };
+
+    let logs = process_with_logs(&mut context, init_ix, &user).await?;
+    insta::assert_snapshot!("initialize_logs", render_logs(&logs));
+    insta::assert_snapshot!(
+        "initialize_account",
+        render_counter_account(&get_account_data(&mut context, counter_pda).await, &labels)
+    );
+
+    let increment_ix = Instruction {
+        program_id: anchor_counter::ID,
+        accounts: anchor_counter::accounts::Increment {
+            counter: counter_pda,
+            user: user.pubkey(),
+            user_increments: user_increments_pda(&counter_pda, &user.pubkey()),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_counter::instruction::Increment {}.data(),
+    };
+
+    let logs = process_with_logs(&mut context, increment_ix.clone(), &user).await?;
+    insta::assert_snapshot!("increment_logs", render_logs(&logs));
+    insta::assert_snapshot!(
+        "increment_account",
+        render_counter_account(&get_account_data(&mut context, counter_pda).await, &labels)
+    );
+
+    //same instruction again needs a new blockhash or it's rejected as already processed
+    context.last_blockhash = context.get_new_latest_blockhash().await?;
+    process_with_logs(&mut context, increment_ix, &user).await?;
+    insta::assert_snapshot!(
+        "double_increment_account",
+        render_counter_account(&get_account_data(&mut context, counter_pda).await, &labels)
+    );
+
+    Ok(())
+}
+
+/// Sends `ix` in its own transaction, asserts it succeeded and returns the program logs
+async fn process_with_logs(
+    context: &mut ProgramTestContext,
+    ix: Instruction,
+    signer: &Keypair,
+) -> anyhow::Result<Vec<String>> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        context.last_blockhash,
+    );
+
+    let res = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await?;
+    res.result?;
+
+    Ok(res.metadata.map(|m| m.log_messages).unwrap_or_default())
+}
+
+async fn get_account_data(context: &mut ProgramTestContext, address: Pubkey) -> Vec<u8> {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+/// One log per line. The compute units consumed are redacted since `compute_units.rs` already
+/// guards those and we don't want every CU change to show up as a log diff too.
+fn render_logs(logs: &[String]) -> String {
+    let mut out = String::new();
+    for log in logs {
+        match log.split_once(" consumed ") {
+            Some((program, rest)) => {
+                let rest = rest.split_once(' ').map_or("", |(_, rest)| rest);
+                writeln!(out, "{} consumed [CU] {}", program, rest).unwrap();
+            }
+            None => writeln!(out, "{}", log).unwrap(),
+        }
+    }
+    out
+}
+
+/// Raw account bytes followed by what the interface crate decodes them to, with the authority
+/// swapped for its label
+fn render_counter_account(data: &[u8], labels: &Labels) -> String {
+    let mut out = String::new();
+    writeln!(out, "len: {}", data.len()).unwrap();
+    writeln!(out, "raw: {}", hex(data)).unwrap();
+
+    let discm_ok = data.len() >= 8 && data[..8] == COUNTER_ACCOUNT_DISCM;
+    writeln!(
+        out,
+        "discriminator: {}",
+        if discm_ok {
+            "COUNTER_ACCOUNT_DISCM"
+        } else {
+            "unknown"
+        }
+    )
+    .unwrap();
+
+    match CounterAccount::deserialize(data) {
+        Ok(CounterAccount(counter)) => writeln!(out, "decoded: {:?}", counter).unwrap(),
+        Err(e) => writeln!(out, "decoded: error {}", e).unwrap(),
+    }
+    labels.redact(out)
+}
+
+/// Instruction data and account metas, with the keys swapped for labels so the snapshot doesn't
+/// depend on the randomly generated user
+fn render_instruction(ix: &Instruction, labels: &Labels) -> String {
+    let mut out = String::new();
+    writeln!(out, "program: {}", labels.label(&ix.program_id)).unwrap();
+    writeln!(out, "data: {}", hex(&ix.data)).unwrap();
+
+    match AnchorCounterProgramIx::deserialize(&ix.data) {
+        Ok(decoded) => writeln!(out, "decoded: {:?}", decoded).unwrap(),
+        Err(e) => writeln!(out, "decoded: error {}", e).unwrap(),
+    }
+
+    writeln!(out, "accounts:").unwrap();
+    for meta in &ix.accounts {
+        writeln!(
+            out,
+            "  {}{}{}",
+            labels.label(&meta.pubkey),
+            if meta.is_writable { " writable" } else { "" },
+            if meta.is_signer { " signer" } else { "" }
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Names for the pubkeys that show up in the snapshots
+struct Labels(Vec<(Pubkey, &'static str)>);
+
+impl Labels {
+    fn new(counter_pda: Pubkey, user: Pubkey) -> Self {
+        Self(vec![
+            (anchor_counter::ID, "anchor_counter"),
+            (counter_pda, "counter_pda"),
+            (user, "user"),
+            (
+                user_increments_pda(&counter_pda, &user),
+                "user_increments_pda",
+            ),
+            (system_program::ID, "system_program"),
+        ])
+    }
+
+    fn label(&self, key: &Pubkey) -> String {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map_or(key.to_string(), |(_, label)| label.to_string())
+    }
+
+    /// Swaps every key in `text` for its label, whether it's written out in base58 or as the
+    /// hex bytes `hex` prints
+    fn redact(&self, mut text: String) -> String {
+        for (key, label) in &self.0 {
+            text = text.replace(&key.to_string(), label);
+            //the system program is all zeros, which would match any long enough run of zeros
+            if *key != system_program::ID {
+                text = text.replace(&hex(key.as_ref()), &format!("[{}]", label));
+            }
+        }
+        text
+    }
+}
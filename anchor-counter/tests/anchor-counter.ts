@@ -9,16 +9,17 @@ describe('anchor-counter', () => {
     const provider = anchor.AnchorProvider.local()
     anchor.setProvider(provider)
     const program = anchor.workspace.AnchorCounter as Program<AnchorCounter>
+    const name = 'deploys'
 
     it('initializes the counter', async () => {
         const [counter, _] = anchor.web3.PublicKey.findProgramAddressSync(
-            [Buffer.from('counter')],
+            [Buffer.from('counter'), Buffer.from(name)],
             program.programId,
         )
 
         //anchor automatically fills the user of Account type Signer with the provider and the SystemProgram
         await program.methods
-            .initialize()
+            .initialize(name)
             .accounts({
                 counter,
             })
@@ -26,13 +27,21 @@ describe('anchor-counter', () => {
 
         const counterAccount = await program.account.counter.fetch(counter)
         expect(counterAccount.count.toNumber()).to.equal(0)
+        expect(counterAccount.name).to.equal(name)
+        expect(counterAccount.authority.toBase58()).to.equal(provider.wallet.publicKey.toBase58())
+        expect(counterAccount.cooldownSlots.toNumber()).to.equal(0)
 
         console.log('counter address', counter.toBase58())
     })
 
     it('increments the counter', async () => {
         const [counter, _] = anchor.web3.PublicKey.findProgramAddressSync(
-            [Buffer.from('counter')],
+            [Buffer.from('counter'), Buffer.from(name)],
+            program.programId,
+        )
+        //created on the first increment, tracks when this user last incremented
+        const [userIncrements] = anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from('user_increments'), counter.toBuffer(), provider.wallet.publicKey.toBuffer()],
             program.programId,
         )
 
@@ -40,6 +49,7 @@ describe('anchor-counter', () => {
             .increment()
             .accounts({
                 counter,
+                userIncrements,
             })
             .rpc()
 
@@ -50,11 +60,15 @@ describe('anchor-counter', () => {
             .increment()
             .accounts({
                 counter,
+                userIncrements,
             })
             .rpc()
 
         const counterAccount2 = await program.account.counter.fetch(counter)
         expect(counterAccount2.count.toNumber()).to.equal(2)
+
+        const userIncrementsAccount = await program.account.userIncrements.fetch(userIncrements)
+        expect(userIncrementsAccount.count.toNumber()).to.equal(2)
     })
 
     // it('will this fail if we put in a random counter account', async () => {
@@ -1,9 +1,13 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
 pub const COUNTER_ACCOUNT_DISCM: [u8; 8] = [255, 176, 4, 245, 188, 253, 124, 25];
 #[derive(Clone, Debug, BorshDeserialize, BorshSerialize, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Counter {
     pub count: u64,
+    pub authority: Pubkey,
+    pub cooldown_slots: u64,
+    pub name: String,
 }
 #[derive(Clone, Debug, PartialEq)]
 pub struct CounterAccount(pub Counter);
@@ -14,15 +18,13 @@ impl CounterAccount {
         let mut maybe_discm = [0u8; 8];
         reader.read_exact(&mut maybe_discm)?;
         if maybe_discm != COUNTER_ACCOUNT_DISCM {
-            return Err(
-                std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!(
-                        "discm does not match. Expected: {:?}. Received: {:?}",
-                        COUNTER_ACCOUNT_DISCM, maybe_discm
-                    ),
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "discm does not match. Expected: {:?}. Received: {:?}",
+                    COUNTER_ACCOUNT_DISCM, maybe_discm
                 ),
-            );
+            ));
         }
         Ok(Self(Counter::deserialize(&mut reader)?))
     }
@@ -36,3 +38,75 @@ impl CounterAccount {
         Ok(data)
     }
 }
+pub const USER_INCREMENTS_ACCOUNT_DISCM: [u8; 8] = [146, 98, 136, 5, 76, 118, 196, 174];
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UserIncrements {
+    pub count: u64,
+    pub last_increment_slot: u64,
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserIncrementsAccount(pub UserIncrements);
+impl UserIncrementsAccount {
+    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
+        use std::io::Read;
+        let mut reader = buf;
+        let mut maybe_discm = [0u8; 8];
+        reader.read_exact(&mut maybe_discm)?;
+        if maybe_discm != USER_INCREMENTS_ACCOUNT_DISCM {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "discm does not match. Expected: {:?}. Received: {:?}",
+                    USER_INCREMENTS_ACCOUNT_DISCM, maybe_discm
+                ),
+            ));
+        }
+        Ok(Self(UserIncrements::deserialize(&mut reader)?))
+    }
+    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&USER_INCREMENTS_ACCOUNT_DISCM)?;
+        self.0.serialize(&mut writer)
+    }
+    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.serialize(&mut data)?;
+        Ok(data)
+    }
+}
+pub const ZERO_COPY_COUNTER_ACCOUNT_DISCM: [u8; 8] = [214, 173, 3, 97, 167, 71, 224, 184];
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZeroCopyCounter {
+    pub count: u64,
+    pub reserved: [u64; 15],
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZeroCopyCounterAccount(pub ZeroCopyCounter);
+impl ZeroCopyCounterAccount {
+    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
+        use std::io::Read;
+        let mut reader = buf;
+        let mut maybe_discm = [0u8; 8];
+        reader.read_exact(&mut maybe_discm)?;
+        if maybe_discm != ZERO_COPY_COUNTER_ACCOUNT_DISCM {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "discm does not match. Expected: {:?}. Received: {:?}",
+                    ZERO_COPY_COUNTER_ACCOUNT_DISCM, maybe_discm
+                ),
+            ));
+        }
+        Ok(Self(ZeroCopyCounter::deserialize(&mut reader)?))
+    }
+    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&ZERO_COPY_COUNTER_ACCOUNT_DISCM)?;
+        self.0.serialize(&mut writer)
+    }
+    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.serialize(&mut data)?;
+        Ok(data)
+    }
+}
@@ -0,0 +1,37 @@
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
+use thiserror::Error;
+#[derive(Clone, Copy, Debug, Eq, Error, num_derive::FromPrimitive, PartialEq)]
+pub enum AnchorCounterError {
+    #[error("Counter names can be at most 32 bytes")]
+    NameTooLong = 6000,
+    #[error("This user incremented the counter too recently, wait for the cooldown")]
+    CooldownActive = 6001,
+    #[error("Counter names can't be empty")]
+    NameEmpty = 6002,
+}
+impl From<AnchorCounterError> for ProgramError {
+    fn from(e: AnchorCounterError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+impl<T> DecodeError<T> for AnchorCounterError {
+    fn type_of() -> &'static str {
+        "AnchorCounterError"
+    }
+}
+impl PrintProgramError for AnchorCounterError {
+    fn print<E>(&self)
+    where
+        E: 'static
+            + std::error::Error
+            + DecodeError<E>
+            + PrintProgramError
+            + num_traits::FromPrimitive,
+    {
+        msg!(&self.to_string());
+    }
+}
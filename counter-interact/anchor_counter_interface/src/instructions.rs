@@ -1,14 +1,20 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
     program::{invoke, invoke_signed},
-    pubkey::Pubkey, program_error::ProgramError,
+    program_error::ProgramError,
+    pubkey::Pubkey,
 };
 use std::io::Read;
 #[derive(Clone, Debug, PartialEq)]
 pub enum AnchorCounterProgramIx {
-    Initialize,
+    Initialize(InitializeIxArgs),
     Increment,
+    SetCooldown(SetCooldownIxArgs),
+    InitializeZeroCopy,
+    IncrementZeroCopy,
 }
 impl AnchorCounterProgramIx {
     pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
@@ -16,22 +22,34 @@ impl AnchorCounterProgramIx {
         let mut maybe_discm = [0u8; 8];
         reader.read_exact(&mut maybe_discm)?;
         match maybe_discm {
-            INITIALIZE_IX_DISCM => Ok(Self::Initialize),
+            INITIALIZE_IX_DISCM => Ok(Self::Initialize(InitializeIxArgs::deserialize(
+                &mut reader,
+            )?)),
             INCREMENT_IX_DISCM => Ok(Self::Increment),
-            _ => {
-                Err(
-                    std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("discm {:?} not found", maybe_discm),
-                    ),
-                )
-            }
+            SET_COOLDOWN_IX_DISCM => Ok(Self::SetCooldown(SetCooldownIxArgs::deserialize(
+                &mut reader,
+            )?)),
+            INITIALIZE_ZERO_COPY_IX_DISCM => Ok(Self::InitializeZeroCopy),
+            INCREMENT_ZERO_COPY_IX_DISCM => Ok(Self::IncrementZeroCopy),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("discm {:?} not found", maybe_discm),
+            )),
         }
     }
     pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
         match self {
-            Self::Initialize => writer.write_all(&INITIALIZE_IX_DISCM),
+            Self::Initialize(args) => {
+                writer.write_all(&INITIALIZE_IX_DISCM)?;
+                args.serialize(&mut writer)
+            }
             Self::Increment => writer.write_all(&INCREMENT_IX_DISCM),
+            Self::SetCooldown(args) => {
+                writer.write_all(&SET_COOLDOWN_IX_DISCM)?;
+                args.serialize(&mut writer)
+            }
+            Self::InitializeZeroCopy => writer.write_all(&INITIALIZE_ZERO_COPY_IX_DISCM),
+            Self::IncrementZeroCopy => writer.write_all(&INCREMENT_ZERO_COPY_IX_DISCM),
         }
     }
     pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
@@ -108,7 +126,8 @@ impl From<[Pubkey; INITIALIZE_IX_ACCOUNTS_LEN]> for InitializeKeys {
     }
 }
 impl<'info> From<InitializeAccounts<'_, 'info>>
-for [AccountInfo<'info>; INITIALIZE_IX_ACCOUNTS_LEN] {
+    for [AccountInfo<'info>; INITIALIZE_IX_ACCOUNTS_LEN]
+{
     fn from(accounts: InitializeAccounts<'_, 'info>) -> Self {
         [
             accounts.counter.clone(),
@@ -118,7 +137,8 @@ for [AccountInfo<'info>; INITIALIZE_IX_ACCOUNTS_LEN] {
     }
 }
 impl<'me, 'info> From<&'me [AccountInfo<'info>; INITIALIZE_IX_ACCOUNTS_LEN]>
-for InitializeAccounts<'me, 'info> {
+    for InitializeAccounts<'me, 'info>
+{
     fn from(arr: &'me [AccountInfo<'info>; INITIALIZE_IX_ACCOUNTS_LEN]) -> Self {
         Self {
             counter: &arr[0],
@@ -128,28 +148,37 @@ for InitializeAccounts<'me, 'info> {
     }
 }
 pub const INITIALIZE_IX_DISCM: [u8; 8] = [175, 175, 109, 31, 13, 152, 155, 237];
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InitializeIxArgs {
+    pub name: String,
+}
 #[derive(Clone, Debug, PartialEq)]
-pub struct InitializeIxData;
+pub struct InitializeIxData(pub InitializeIxArgs);
+impl From<InitializeIxArgs> for InitializeIxData {
+    fn from(args: InitializeIxArgs) -> Self {
+        Self(args)
+    }
+}
 impl InitializeIxData {
     pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
         let mut reader = buf;
         let mut maybe_discm = [0u8; 8];
         reader.read_exact(&mut maybe_discm)?;
         if maybe_discm != INITIALIZE_IX_DISCM {
-            return Err(
-                std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!(
-                        "discm does not match. Expected: {:?}. Received: {:?}",
-                        INITIALIZE_IX_DISCM, maybe_discm
-                    ),
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "discm does not match. Expected: {:?}. Received: {:?}",
+                    INITIALIZE_IX_DISCM, maybe_discm
                 ),
-            );
+            ));
         }
-        Ok(Self)
+        Ok(Self(InitializeIxArgs::deserialize(&mut reader)?))
     }
     pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
-        writer.write_all(&INITIALIZE_IX_DISCM)
+        writer.write_all(&INITIALIZE_IX_DISCM)?;
+        self.0.serialize(&mut writer)
     }
     pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
         let mut data = Vec::new();
@@ -160,42 +189,50 @@ impl InitializeIxData {
 pub fn initialize_ix_with_program_id(
     program_id: Pubkey,
     keys: InitializeKeys,
+    args: InitializeIxArgs,
 ) -> std::io::Result<Instruction> {
     let metas: [AccountMeta; INITIALIZE_IX_ACCOUNTS_LEN] = keys.into();
+    let data: InitializeIxData = args.into();
     Ok(Instruction {
         program_id,
         accounts: Vec::from(metas),
-        data: InitializeIxData.try_to_vec()?,
+        data: data.try_to_vec()?,
     })
 }
-pub fn initialize_ix(keys: InitializeKeys) -> std::io::Result<Instruction> {
-    initialize_ix_with_program_id(crate::ID, keys)
+pub fn initialize_ix(keys: InitializeKeys, args: InitializeIxArgs) -> std::io::Result<Instruction> {
+    initialize_ix_with_program_id(crate::ID, keys, args)
 }
 pub fn initialize_invoke_with_program_id(
     program_id: Pubkey,
     accounts: InitializeAccounts<'_, '_>,
+    args: InitializeIxArgs,
 ) -> ProgramResult {
     let keys: InitializeKeys = accounts.into();
-    let ix = initialize_ix_with_program_id(program_id, keys)?;
+    let ix = initialize_ix_with_program_id(program_id, keys, args)?;
     invoke_instruction(&ix, accounts)
 }
-pub fn initialize_invoke(accounts: InitializeAccounts<'_, '_>) -> ProgramResult {
-    initialize_invoke_with_program_id(crate::ID, accounts)
+pub fn initialize_invoke(
+    accounts: InitializeAccounts<'_, '_>,
+    args: InitializeIxArgs,
+) -> ProgramResult {
+    initialize_invoke_with_program_id(crate::ID, accounts, args)
 }
 pub fn initialize_invoke_signed_with_program_id(
     program_id: Pubkey,
     accounts: InitializeAccounts<'_, '_>,
+    args: InitializeIxArgs,
     seeds: &[&[&[u8]]],
 ) -> ProgramResult {
     let keys: InitializeKeys = accounts.into();
-    let ix = initialize_ix_with_program_id(program_id, keys)?;
+    let ix = initialize_ix_with_program_id(program_id, keys, args)?;
     invoke_instruction_signed(&ix, accounts, seeds)
 }
 pub fn initialize_invoke_signed(
     accounts: InitializeAccounts<'_, '_>,
+    args: InitializeIxArgs,
     seeds: &[&[&[u8]]],
 ) -> ProgramResult {
-    initialize_invoke_signed_with_program_id(crate::ID, accounts, seeds)
+    initialize_invoke_signed_with_program_id(crate::ID, accounts, args, seeds)
 }
 pub fn initialize_verify_account_keys(
     accounts: InitializeAccounts<'_, '_>,
@@ -239,22 +276,28 @@ pub fn initialize_verify_account_privileges<'me, 'info>(
     initialize_verify_signer_privileges(accounts)?;
     Ok(())
 }
-pub const INCREMENT_IX_ACCOUNTS_LEN: usize = 2;
+pub const INCREMENT_IX_ACCOUNTS_LEN: usize = 4;
 #[derive(Copy, Clone, Debug)]
 pub struct IncrementAccounts<'me, 'info> {
     pub counter: &'me AccountInfo<'info>,
     pub user: &'me AccountInfo<'info>,
+    pub user_increments: &'me AccountInfo<'info>,
+    pub system_program: &'me AccountInfo<'info>,
 }
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct IncrementKeys {
     pub counter: Pubkey,
     pub user: Pubkey,
+    pub user_increments: Pubkey,
+    pub system_program: Pubkey,
 }
 impl From<IncrementAccounts<'_, '_>> for IncrementKeys {
     fn from(accounts: IncrementAccounts) -> Self {
         Self {
             counter: *accounts.counter.key,
             user: *accounts.user.key,
+            user_increments: *accounts.user_increments.key,
+            system_program: *accounts.system_program.key,
         }
     }
 }
@@ -271,6 +314,16 @@ impl From<IncrementKeys> for [AccountMeta; INCREMENT_IX_ACCOUNTS_LEN] {
                 is_signer: true,
                 is_writable: true,
             },
+            AccountMeta {
+                pubkey: keys.user_increments,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: keys.system_program,
+                is_signer: false,
+                is_writable: false,
+            },
         ]
     }
 }
@@ -279,21 +332,30 @@ impl From<[Pubkey; INCREMENT_IX_ACCOUNTS_LEN]> for IncrementKeys {
         Self {
             counter: pubkeys[0],
             user: pubkeys[1],
+            user_increments: pubkeys[2],
+            system_program: pubkeys[3],
         }
     }
 }
-impl<'info> From<IncrementAccounts<'_, 'info>>
-for [AccountInfo<'info>; INCREMENT_IX_ACCOUNTS_LEN] {
+impl<'info> From<IncrementAccounts<'_, 'info>> for [AccountInfo<'info>; INCREMENT_IX_ACCOUNTS_LEN] {
     fn from(accounts: IncrementAccounts<'_, 'info>) -> Self {
-        [accounts.counter.clone(), accounts.user.clone()]
+        [
+            accounts.counter.clone(),
+            accounts.user.clone(),
+            accounts.user_increments.clone(),
+            accounts.system_program.clone(),
+        ]
     }
 }
 impl<'me, 'info> From<&'me [AccountInfo<'info>; INCREMENT_IX_ACCOUNTS_LEN]>
-for IncrementAccounts<'me, 'info> {
+    for IncrementAccounts<'me, 'info>
+{
     fn from(arr: &'me [AccountInfo<'info>; INCREMENT_IX_ACCOUNTS_LEN]) -> Self {
         Self {
             counter: &arr[0],
             user: &arr[1],
+            user_increments: &arr[2],
+            system_program: &arr[3],
         }
     }
 }
@@ -306,15 +368,13 @@ impl IncrementIxData {
         let mut maybe_discm = [0u8; 8];
         reader.read_exact(&mut maybe_discm)?;
         if maybe_discm != INCREMENT_IX_DISCM {
-            return Err(
-                std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!(
-                        "discm does not match. Expected: {:?}. Received: {:?}",
-                        INCREMENT_IX_DISCM, maybe_discm
-                    ),
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "discm does not match. Expected: {:?}. Received: {:?}",
+                    INCREMENT_IX_DISCM, maybe_discm
                 ),
-            );
+            ));
         }
         Ok(Self)
     }
@@ -374,6 +434,8 @@ pub fn increment_verify_account_keys(
     for (actual, expected) in [
         (*accounts.counter.key, keys.counter),
         (*accounts.user.key, keys.user),
+        (*accounts.user_increments.key, keys.user_increments),
+        (*accounts.system_program.key, keys.system_program),
     ] {
         if actual != expected {
             return Err((actual, expected));
@@ -384,7 +446,7 @@ pub fn increment_verify_account_keys(
 pub fn increment_verify_writable_privileges<'me, 'info>(
     accounts: IncrementAccounts<'me, 'info>,
 ) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
-    for should_be_writable in [accounts.counter, accounts.user] {
+    for should_be_writable in [accounts.counter, accounts.user, accounts.user_increments] {
         if !should_be_writable.is_writable {
             return Err((should_be_writable, ProgramError::InvalidAccountData));
         }
@@ -408,3 +470,547 @@ pub fn increment_verify_account_privileges<'me, 'info>(
     increment_verify_signer_privileges(accounts)?;
     Ok(())
 }
+pub const SET_COOLDOWN_IX_ACCOUNTS_LEN: usize = 2;
+#[derive(Copy, Clone, Debug)]
+pub struct SetCooldownAccounts<'me, 'info> {
+    pub counter: &'me AccountInfo<'info>,
+    pub authority: &'me AccountInfo<'info>,
+}
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SetCooldownKeys {
+    pub counter: Pubkey,
+    pub authority: Pubkey,
+}
+impl From<SetCooldownAccounts<'_, '_>> for SetCooldownKeys {
+    fn from(accounts: SetCooldownAccounts) -> Self {
+        Self {
+            counter: *accounts.counter.key,
+            authority: *accounts.authority.key,
+        }
+    }
+}
+impl From<SetCooldownKeys> for [AccountMeta; SET_COOLDOWN_IX_ACCOUNTS_LEN] {
+    fn from(keys: SetCooldownKeys) -> Self {
+        [
+            AccountMeta {
+                pubkey: keys.counter,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: keys.authority,
+                is_signer: true,
+                is_writable: false,
+            },
+        ]
+    }
+}
+impl From<[Pubkey; SET_COOLDOWN_IX_ACCOUNTS_LEN]> for SetCooldownKeys {
+    fn from(pubkeys: [Pubkey; SET_COOLDOWN_IX_ACCOUNTS_LEN]) -> Self {
+        Self {
+            counter: pubkeys[0],
+            authority: pubkeys[1],
+        }
+    }
+}
+impl<'info> From<SetCooldownAccounts<'_, 'info>>
+    for [AccountInfo<'info>; SET_COOLDOWN_IX_ACCOUNTS_LEN]
+{
+    fn from(accounts: SetCooldownAccounts<'_, 'info>) -> Self {
+        [accounts.counter.clone(), accounts.authority.clone()]
+    }
+}
+impl<'me, 'info> From<&'me [AccountInfo<'info>; SET_COOLDOWN_IX_ACCOUNTS_LEN]>
+    for SetCooldownAccounts<'me, 'info>
+{
+    fn from(arr: &'me [AccountInfo<'info>; SET_COOLDOWN_IX_ACCOUNTS_LEN]) -> Self {
+        Self {
+            counter: &arr[0],
+            authority: &arr[1],
+        }
+    }
+}
+pub const SET_COOLDOWN_IX_DISCM: [u8; 8] = [57, 78, 91, 178, 112, 152, 211, 87];
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetCooldownIxArgs {
+    pub cooldown_slots: u64,
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetCooldownIxData(pub SetCooldownIxArgs);
+impl From<SetCooldownIxArgs> for SetCooldownIxData {
+    fn from(args: SetCooldownIxArgs) -> Self {
+        Self(args)
+    }
+}
+impl SetCooldownIxData {
+    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
+        let mut reader = buf;
+        let mut maybe_discm = [0u8; 8];
+        reader.read_exact(&mut maybe_discm)?;
+        if maybe_discm != SET_COOLDOWN_IX_DISCM {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "discm does not match. Expected: {:?}. Received: {:?}",
+                    SET_COOLDOWN_IX_DISCM, maybe_discm
+                ),
+            ));
+        }
+        Ok(Self(SetCooldownIxArgs::deserialize(&mut reader)?))
+    }
+    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&SET_COOLDOWN_IX_DISCM)?;
+        self.0.serialize(&mut writer)
+    }
+    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.serialize(&mut data)?;
+        Ok(data)
+    }
+}
+pub fn set_cooldown_ix_with_program_id(
+    program_id: Pubkey,
+    keys: SetCooldownKeys,
+    args: SetCooldownIxArgs,
+) -> std::io::Result<Instruction> {
+    let metas: [AccountMeta; SET_COOLDOWN_IX_ACCOUNTS_LEN] = keys.into();
+    let data: SetCooldownIxData = args.into();
+    Ok(Instruction {
+        program_id,
+        accounts: Vec::from(metas),
+        data: data.try_to_vec()?,
+    })
+}
+pub fn set_cooldown_ix(
+    keys: SetCooldownKeys,
+    args: SetCooldownIxArgs,
+) -> std::io::Result<Instruction> {
+    set_cooldown_ix_with_program_id(crate::ID, keys, args)
+}
+pub fn set_cooldown_invoke_with_program_id(
+    program_id: Pubkey,
+    accounts: SetCooldownAccounts<'_, '_>,
+    args: SetCooldownIxArgs,
+) -> ProgramResult {
+    let keys: SetCooldownKeys = accounts.into();
+    let ix = set_cooldown_ix_with_program_id(program_id, keys, args)?;
+    invoke_instruction(&ix, accounts)
+}
+pub fn set_cooldown_invoke(
+    accounts: SetCooldownAccounts<'_, '_>,
+    args: SetCooldownIxArgs,
+) -> ProgramResult {
+    set_cooldown_invoke_with_program_id(crate::ID, accounts, args)
+}
+pub fn set_cooldown_invoke_signed_with_program_id(
+    program_id: Pubkey,
+    accounts: SetCooldownAccounts<'_, '_>,
+    args: SetCooldownIxArgs,
+    seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let keys: SetCooldownKeys = accounts.into();
+    let ix = set_cooldown_ix_with_program_id(program_id, keys, args)?;
+    invoke_instruction_signed(&ix, accounts, seeds)
+}
+pub fn set_cooldown_invoke_signed(
+    accounts: SetCooldownAccounts<'_, '_>,
+    args: SetCooldownIxArgs,
+    seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    set_cooldown_invoke_signed_with_program_id(crate::ID, accounts, args, seeds)
+}
+pub fn set_cooldown_verify_account_keys(
+    accounts: SetCooldownAccounts<'_, '_>,
+    keys: SetCooldownKeys,
+) -> Result<(), (Pubkey, Pubkey)> {
+    for (actual, expected) in [
+        (*accounts.counter.key, keys.counter),
+        (*accounts.authority.key, keys.authority),
+    ] {
+        if actual != expected {
+            return Err((actual, expected));
+        }
+    }
+    Ok(())
+}
+pub fn set_cooldown_verify_writable_privileges<'me, 'info>(
+    accounts: SetCooldownAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    for should_be_writable in [accounts.counter] {
+        if !should_be_writable.is_writable {
+            return Err((should_be_writable, ProgramError::InvalidAccountData));
+        }
+    }
+    Ok(())
+}
+pub fn set_cooldown_verify_signer_privileges<'me, 'info>(
+    accounts: SetCooldownAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    for should_be_signer in [accounts.authority] {
+        if !should_be_signer.is_signer {
+            return Err((should_be_signer, ProgramError::MissingRequiredSignature));
+        }
+    }
+    Ok(())
+}
+pub fn set_cooldown_verify_account_privileges<'me, 'info>(
+    accounts: SetCooldownAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    set_cooldown_verify_writable_privileges(accounts)?;
+    set_cooldown_verify_signer_privileges(accounts)?;
+    Ok(())
+}
+pub const INITIALIZE_ZERO_COPY_IX_ACCOUNTS_LEN: usize = 3;
+#[derive(Copy, Clone, Debug)]
+pub struct InitializeZeroCopyAccounts<'me, 'info> {
+    pub counter: &'me AccountInfo<'info>,
+    pub user: &'me AccountInfo<'info>,
+    pub system_program: &'me AccountInfo<'info>,
+}
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InitializeZeroCopyKeys {
+    pub counter: Pubkey,
+    pub user: Pubkey,
+    pub system_program: Pubkey,
+}
+impl From<InitializeZeroCopyAccounts<'_, '_>> for InitializeZeroCopyKeys {
+    fn from(accounts: InitializeZeroCopyAccounts) -> Self {
+        Self {
+            counter: *accounts.counter.key,
+            user: *accounts.user.key,
+            system_program: *accounts.system_program.key,
+        }
+    }
+}
+impl From<InitializeZeroCopyKeys> for [AccountMeta; INITIALIZE_ZERO_COPY_IX_ACCOUNTS_LEN] {
+    fn from(keys: InitializeZeroCopyKeys) -> Self {
+        [
+            AccountMeta {
+                pubkey: keys.counter,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: keys.user,
+                is_signer: true,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: keys.system_program,
+                is_signer: false,
+                is_writable: false,
+            },
+        ]
+    }
+}
+impl From<[Pubkey; INITIALIZE_ZERO_COPY_IX_ACCOUNTS_LEN]> for InitializeZeroCopyKeys {
+    fn from(pubkeys: [Pubkey; INITIALIZE_ZERO_COPY_IX_ACCOUNTS_LEN]) -> Self {
+        Self {
+            counter: pubkeys[0],
+            user: pubkeys[1],
+            system_program: pubkeys[2],
+        }
+    }
+}
+impl<'info> From<InitializeZeroCopyAccounts<'_, 'info>>
+    for [AccountInfo<'info>; INITIALIZE_ZERO_COPY_IX_ACCOUNTS_LEN]
+{
+    fn from(accounts: InitializeZeroCopyAccounts<'_, 'info>) -> Self {
+        [
+            accounts.counter.clone(),
+            accounts.user.clone(),
+            accounts.system_program.clone(),
+        ]
+    }
+}
+impl<'me, 'info> From<&'me [AccountInfo<'info>; INITIALIZE_ZERO_COPY_IX_ACCOUNTS_LEN]>
+    for InitializeZeroCopyAccounts<'me, 'info>
+{
+    fn from(arr: &'me [AccountInfo<'info>; INITIALIZE_ZERO_COPY_IX_ACCOUNTS_LEN]) -> Self {
+        Self {
+            counter: &arr[0],
+            user: &arr[1],
+            system_program: &arr[2],
+        }
+    }
+}
+pub const INITIALIZE_ZERO_COPY_IX_DISCM: [u8; 8] = [231, 210, 91, 45, 76, 136, 245, 98];
+#[derive(Clone, Debug, PartialEq)]
+pub struct InitializeZeroCopyIxData;
+impl InitializeZeroCopyIxData {
+    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
+        let mut reader = buf;
+        let mut maybe_discm = [0u8; 8];
+        reader.read_exact(&mut maybe_discm)?;
+        if maybe_discm != INITIALIZE_ZERO_COPY_IX_DISCM {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "discm does not match. Expected: {:?}. Received: {:?}",
+                    INITIALIZE_ZERO_COPY_IX_DISCM, maybe_discm
+                ),
+            ));
+        }
+        Ok(Self)
+    }
+    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&INITIALIZE_ZERO_COPY_IX_DISCM)
+    }
+    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.serialize(&mut data)?;
+        Ok(data)
+    }
+}
+pub fn initialize_zero_copy_ix_with_program_id(
+    program_id: Pubkey,
+    keys: InitializeZeroCopyKeys,
+) -> std::io::Result<Instruction> {
+    let metas: [AccountMeta; INITIALIZE_ZERO_COPY_IX_ACCOUNTS_LEN] = keys.into();
+    Ok(Instruction {
+        program_id,
+        accounts: Vec::from(metas),
+        data: InitializeZeroCopyIxData.try_to_vec()?,
+    })
+}
+pub fn initialize_zero_copy_ix(keys: InitializeZeroCopyKeys) -> std::io::Result<Instruction> {
+    initialize_zero_copy_ix_with_program_id(crate::ID, keys)
+}
+pub fn initialize_zero_copy_invoke_with_program_id(
+    program_id: Pubkey,
+    accounts: InitializeZeroCopyAccounts<'_, '_>,
+) -> ProgramResult {
+    let keys: InitializeZeroCopyKeys = accounts.into();
+    let ix = initialize_zero_copy_ix_with_program_id(program_id, keys)?;
+    invoke_instruction(&ix, accounts)
+}
+pub fn initialize_zero_copy_invoke(accounts: InitializeZeroCopyAccounts<'_, '_>) -> ProgramResult {
+    initialize_zero_copy_invoke_with_program_id(crate::ID, accounts)
+}
+pub fn initialize_zero_copy_invoke_signed_with_program_id(
+    program_id: Pubkey,
+    accounts: InitializeZeroCopyAccounts<'_, '_>,
+    seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let keys: InitializeZeroCopyKeys = accounts.into();
+    let ix = initialize_zero_copy_ix_with_program_id(program_id, keys)?;
+    invoke_instruction_signed(&ix, accounts, seeds)
+}
+pub fn initialize_zero_copy_invoke_signed(
+    accounts: InitializeZeroCopyAccounts<'_, '_>,
+    seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    initialize_zero_copy_invoke_signed_with_program_id(crate::ID, accounts, seeds)
+}
+pub fn initialize_zero_copy_verify_account_keys(
+    accounts: InitializeZeroCopyAccounts<'_, '_>,
+    keys: InitializeZeroCopyKeys,
+) -> Result<(), (Pubkey, Pubkey)> {
+    for (actual, expected) in [
+        (*accounts.counter.key, keys.counter),
+        (*accounts.user.key, keys.user),
+        (*accounts.system_program.key, keys.system_program),
+    ] {
+        if actual != expected {
+            return Err((actual, expected));
+        }
+    }
+    Ok(())
+}
+pub fn initialize_zero_copy_verify_writable_privileges<'me, 'info>(
+    accounts: InitializeZeroCopyAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    for should_be_writable in [accounts.counter, accounts.user] {
+        if !should_be_writable.is_writable {
+            return Err((should_be_writable, ProgramError::InvalidAccountData));
+        }
+    }
+    Ok(())
+}
+pub fn initialize_zero_copy_verify_signer_privileges<'me, 'info>(
+    accounts: InitializeZeroCopyAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    for should_be_signer in [accounts.user] {
+        if !should_be_signer.is_signer {
+            return Err((should_be_signer, ProgramError::MissingRequiredSignature));
+        }
+    }
+    Ok(())
+}
+pub fn initialize_zero_copy_verify_account_privileges<'me, 'info>(
+    accounts: InitializeZeroCopyAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    initialize_zero_copy_verify_writable_privileges(accounts)?;
+    initialize_zero_copy_verify_signer_privileges(accounts)?;
+    Ok(())
+}
+pub const INCREMENT_ZERO_COPY_IX_ACCOUNTS_LEN: usize = 2;
+#[derive(Copy, Clone, Debug)]
+pub struct IncrementZeroCopyAccounts<'me, 'info> {
+    pub counter: &'me AccountInfo<'info>,
+    pub user: &'me AccountInfo<'info>,
+}
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IncrementZeroCopyKeys {
+    pub counter: Pubkey,
+    pub user: Pubkey,
+}
+impl From<IncrementZeroCopyAccounts<'_, '_>> for IncrementZeroCopyKeys {
+    fn from(accounts: IncrementZeroCopyAccounts) -> Self {
+        Self {
+            counter: *accounts.counter.key,
+            user: *accounts.user.key,
+        }
+    }
+}
+impl From<IncrementZeroCopyKeys> for [AccountMeta; INCREMENT_ZERO_COPY_IX_ACCOUNTS_LEN] {
+    fn from(keys: IncrementZeroCopyKeys) -> Self {
+        [
+            AccountMeta {
+                pubkey: keys.counter,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: keys.user,
+                is_signer: true,
+                is_writable: true,
+            },
+        ]
+    }
+}
+impl From<[Pubkey; INCREMENT_ZERO_COPY_IX_ACCOUNTS_LEN]> for IncrementZeroCopyKeys {
+    fn from(pubkeys: [Pubkey; INCREMENT_ZERO_COPY_IX_ACCOUNTS_LEN]) -> Self {
+        Self {
+            counter: pubkeys[0],
+            user: pubkeys[1],
+        }
+    }
+}
+impl<'info> From<IncrementZeroCopyAccounts<'_, 'info>>
+    for [AccountInfo<'info>; INCREMENT_ZERO_COPY_IX_ACCOUNTS_LEN]
+{
+    fn from(accounts: IncrementZeroCopyAccounts<'_, 'info>) -> Self {
+        [accounts.counter.clone(), accounts.user.clone()]
+    }
+}
+impl<'me, 'info> From<&'me [AccountInfo<'info>; INCREMENT_ZERO_COPY_IX_ACCOUNTS_LEN]>
+    for IncrementZeroCopyAccounts<'me, 'info>
+{
+    fn from(arr: &'me [AccountInfo<'info>; INCREMENT_ZERO_COPY_IX_ACCOUNTS_LEN]) -> Self {
+        Self {
+            counter: &arr[0],
+            user: &arr[1],
+        }
+    }
+}
+pub const INCREMENT_ZERO_COPY_IX_DISCM: [u8; 8] = [185, 18, 29, 251, 232, 249, 13, 86];
+#[derive(Clone, Debug, PartialEq)]
+pub struct IncrementZeroCopyIxData;
+impl IncrementZeroCopyIxData {
+    pub fn deserialize(buf: &[u8]) -> std::io::Result<Self> {
+        let mut reader = buf;
+        let mut maybe_discm = [0u8; 8];
+        reader.read_exact(&mut maybe_discm)?;
+        if maybe_discm != INCREMENT_ZERO_COPY_IX_DISCM {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "discm does not match. Expected: {:?}. Received: {:?}",
+                    INCREMENT_ZERO_COPY_IX_DISCM, maybe_discm
+                ),
+            ));
+        }
+        Ok(Self)
+    }
+    pub fn serialize<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&INCREMENT_ZERO_COPY_IX_DISCM)
+    }
+    pub fn try_to_vec(&self) -> std::io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.serialize(&mut data)?;
+        Ok(data)
+    }
+}
+pub fn increment_zero_copy_ix_with_program_id(
+    program_id: Pubkey,
+    keys: IncrementZeroCopyKeys,
+) -> std::io::Result<Instruction> {
+    let metas: [AccountMeta; INCREMENT_ZERO_COPY_IX_ACCOUNTS_LEN] = keys.into();
+    Ok(Instruction {
+        program_id,
+        accounts: Vec::from(metas),
+        data: IncrementZeroCopyIxData.try_to_vec()?,
+    })
+}
+pub fn increment_zero_copy_ix(keys: IncrementZeroCopyKeys) -> std::io::Result<Instruction> {
+    increment_zero_copy_ix_with_program_id(crate::ID, keys)
+}
+pub fn increment_zero_copy_invoke_with_program_id(
+    program_id: Pubkey,
+    accounts: IncrementZeroCopyAccounts<'_, '_>,
+) -> ProgramResult {
+    let keys: IncrementZeroCopyKeys = accounts.into();
+    let ix = increment_zero_copy_ix_with_program_id(program_id, keys)?;
+    invoke_instruction(&ix, accounts)
+}
+pub fn increment_zero_copy_invoke(accounts: IncrementZeroCopyAccounts<'_, '_>) -> ProgramResult {
+    increment_zero_copy_invoke_with_program_id(crate::ID, accounts)
+}
+pub fn increment_zero_copy_invoke_signed_with_program_id(
+    program_id: Pubkey,
+    accounts: IncrementZeroCopyAccounts<'_, '_>,
+    seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let keys: IncrementZeroCopyKeys = accounts.into();
+    let ix = increment_zero_copy_ix_with_program_id(program_id, keys)?;
+    invoke_instruction_signed(&ix, accounts, seeds)
+}
+pub fn increment_zero_copy_invoke_signed(
+    accounts: IncrementZeroCopyAccounts<'_, '_>,
+    seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    increment_zero_copy_invoke_signed_with_program_id(crate::ID, accounts, seeds)
+}
+pub fn increment_zero_copy_verify_account_keys(
+    accounts: IncrementZeroCopyAccounts<'_, '_>,
+    keys: IncrementZeroCopyKeys,
+) -> Result<(), (Pubkey, Pubkey)> {
+    for (actual, expected) in [
+        (*accounts.counter.key, keys.counter),
+        (*accounts.user.key, keys.user),
+    ] {
+        if actual != expected {
+            return Err((actual, expected));
+        }
+    }
+    Ok(())
+}
+pub fn increment_zero_copy_verify_writable_privileges<'me, 'info>(
+    accounts: IncrementZeroCopyAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    for should_be_writable in [accounts.counter, accounts.user] {
+        if !should_be_writable.is_writable {
+            return Err((should_be_writable, ProgramError::InvalidAccountData));
+        }
+    }
+    Ok(())
+}
+pub fn increment_zero_copy_verify_signer_privileges<'me, 'info>(
+    accounts: IncrementZeroCopyAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    for should_be_signer in [accounts.user] {
+        if !should_be_signer.is_signer {
+            return Err((should_be_signer, ProgramError::MissingRequiredSignature));
+        }
+    }
+    Ok(())
+}
+pub fn increment_zero_copy_verify_account_privileges<'me, 'info>(
+    accounts: IncrementZeroCopyAccounts<'me, 'info>,
+) -> Result<(), (&'me AccountInfo<'info>, ProgramError)> {
+    increment_zero_copy_verify_writable_privileges(accounts)?;
+    increment_zero_copy_verify_signer_privileges(accounts)?;
+    Ok(())
+}
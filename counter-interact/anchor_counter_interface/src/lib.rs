@@ -1,5 +1,13 @@
+//the generated code builds io errors with `Error::new(ErrorKind::Other, ..)`, keep to that
+#![allow(clippy::io_other_error)]
 solana_program::declare_id!("TH1S1SNoTAVAL1DPUBKEYDoNoTUSE11111111111111");
 pub mod accounts;
 pub use accounts::*;
+pub mod errors;
+pub use errors::*;
 pub mod instructions;
 pub use instructions::*;
+pub mod pod;
+pub use pod::*;
+pub mod scan;
+pub use scan::*;
@@ -0,0 +1,68 @@
+//! Not generated by solores. `bytemuck` views over the raw account data, so a client can read
+//! counters straight out of `get_account_data` bytes without going through Borsh.
+//! The structs must have the exact `#[repr(C)]` layout the program uses.
+use crate::{COUNTER_ACCOUNT_DISCM, ZERO_COPY_COUNTER_ACCOUNT_DISCM};
+use bytemuck::{Pod, Zeroable};
+
+/// Layout of the program's `#[account(zero_copy)] ZeroCopyCounter` after the 8 byte discriminator
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct ZeroCopyCounterPod {
+    pub count: u64,
+    pub reserved: [u64; 15],
+}
+
+impl ZeroCopyCounterPod {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Reads the counter from the full account data, discriminator included
+    pub fn from_account_data(data: &[u8]) -> std::io::Result<Self> {
+        read_pod(data, ZERO_COPY_COUNTER_ACCOUNT_DISCM)
+    }
+}
+
+/// The Borsh `Counter` starts with its `u64` count, which has a plain old data layout even though
+/// the name after it doesn't. Only reads the count.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct CounterPod {
+    pub count: u64,
+}
+
+impl CounterPod {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    /// Reads the counter from the full account data, discriminator included
+    pub fn from_account_data(data: &[u8]) -> std::io::Result<Self> {
+        read_pod(data, COUNTER_ACCOUNT_DISCM)
+    }
+}
+
+/// Checks the discriminator and copies the bytes after it into `T`.
+/// `Vec<u8>` from the rpc has no alignment guarantee, so this reads unaligned instead of casting
+/// the slice in place.
+fn read_pod<T: Pod>(data: &[u8], discm: [u8; 8]) -> std::io::Result<T> {
+    let len = std::mem::size_of::<T>();
+    if data.len() < 8 + len {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!(
+                "account data is {} bytes, expected at least {}",
+                data.len(),
+                8 + len
+            ),
+        ));
+    }
+    if data[..8] != discm {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!(
+                "discm does not match. Expected: {:?}. Received: {:?}",
+                discm,
+                &data[..8]
+            ),
+        ));
+    }
+    bytemuck::try_pod_read_unaligned(&data[8..8 + len])
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{:?}", e)))
+}
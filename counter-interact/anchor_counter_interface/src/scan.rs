@@ -0,0 +1,104 @@
+//! Not generated by solores. Helpers for finding every `Counter` a deployment owns with
+//! `getProgramAccounts` and decoding what comes back. The offsets have to match the program's
+//! `Counter` layout, Borsh encoded after the 8 byte discriminator.
+use crate::COUNTER_ACCOUNT_DISCM;
+#[cfg(feature = "rpc")]
+use crate::{Counter, CounterAccount};
+#[cfg(feature = "rpc")]
+use solana_account_decoder::UiAccountEncoding;
+#[cfg(feature = "rpc")]
+use solana_program::pubkey::Pubkey;
+#[cfg(feature = "rpc")]
+use solana_rpc_client_api::{
+    config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    filter::{Memcmp, RpcFilterType},
+};
+#[cfg(feature = "rpc")]
+use solana_sdk::account::Account;
+
+/// Same limit as the program's `MAX_NAME_LEN`
+pub const COUNTER_MAX_NAME_LEN: usize = 32;
+
+pub const COUNTER_DISCM_OFFSET: usize = 0;
+/// The `u64` count, little endian
+pub const COUNTER_COUNT_OFFSET: usize = COUNTER_DISCM_OFFSET + COUNTER_ACCOUNT_DISCM.len();
+/// The `Pubkey` allowed to set the cooldown
+pub const COUNTER_AUTHORITY_OFFSET: usize = COUNTER_COUNT_OFFSET + 8;
+/// The `u64` cooldown in slots, little endian
+pub const COUNTER_COOLDOWN_OFFSET: usize = COUNTER_AUTHORITY_OFFSET + 32;
+/// The `u32` length of the name, followed by the name itself
+pub const COUNTER_NAME_OFFSET: usize = COUNTER_COOLDOWN_OFFSET + 8;
+
+/// The program allocates every counter for the longest name, so they're all this size
+pub const COUNTER_ACCOUNT_LEN: usize = COUNTER_NAME_OFFSET + 4 + COUNTER_MAX_NAME_LEN;
+
+/// Only accounts that start with the `Counter` discriminator
+#[cfg(feature = "rpc")]
+pub fn counter_discm_filter() -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        COUNTER_DISCM_OFFSET,
+        &COUNTER_ACCOUNT_DISCM,
+    ))
+}
+
+/// Only accounts exactly the size of a `Counter`
+#[cfg(feature = "rpc")]
+pub fn counter_data_size_filter() -> RpcFilterType {
+    RpcFilterType::DataSize(COUNTER_ACCOUNT_LEN as u64)
+}
+
+/// Only counters that are at exactly `count`
+#[cfg(feature = "rpc")]
+pub fn counter_count_filter(count: u64) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        COUNTER_COUNT_OFFSET,
+        &count.to_le_bytes(),
+    ))
+}
+
+/// Only counters whose cooldown `authority` can set
+#[cfg(feature = "rpc")]
+pub fn counter_authority_filter(authority: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        COUNTER_AUTHORITY_OFFSET,
+        authority.as_ref(),
+    ))
+}
+
+/// `getProgramAccounts` config that returns every `Counter` with base64 data. Only filters on
+/// the discriminator, so an account with the right discriminator but the wrong size still comes
+/// back and shows up as undecodable instead of silently missing.
+#[cfg(feature = "rpc")]
+pub fn counter_program_accounts_config() -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(vec![counter_discm_filter()]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    }
+}
+
+/// What `decode_counters` made of a `getProgramAccounts` response
+#[cfg(feature = "rpc")]
+#[derive(Debug, Default)]
+pub struct DecodedCounters {
+    pub counters: Vec<(Pubkey, Counter)>,
+    /// Accounts that didn't deserialize as a `Counter` and why
+    pub undecodable: Vec<(Pubkey, std::io::Error)>,
+}
+
+/// Decodes every account as a `Counter`, keeping the ones that don't decode separate instead of
+/// failing the whole list. Order is kept.
+#[cfg(feature = "rpc")]
+pub fn decode_counters(accounts: Vec<(Pubkey, Account)>) -> DecodedCounters {
+    let mut decoded = DecodedCounters::default();
+    for (address, account) in accounts {
+        match CounterAccount::deserialize(&account.data) {
+            Ok(CounterAccount(counter)) => decoded.counters.push((address, counter)),
+            Err(e) => decoded.undecodable.push((address, e)),
+        }
+    }
+    decoded
+}
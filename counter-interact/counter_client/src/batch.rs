@@ -0,0 +1,274 @@
+use crate::increment_keys;
+use crate::transaction::{compile_message, send_instructions, transaction_size, TxFormat};
+use anchor_counter_interface::increment_ix_with_program_id;
+use anyhow::anyhow;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+///Most compute units a transaction can ask for
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+///Headroom in percent added to a simulated increment, the cost moves a little with the slot
+pub const SIMULATED_CU_HEADROOM_PCT: u64 = 20;
+
+///How to pack and send a batch of increments
+pub struct BatchConfig {
+    ///Compute units to budget per increment. A user's first increment of a counter also creates
+    ///their `user_increments` account and costs more, so this has to cover that.
+    ///`measure_cu_per_increment` gets it from a simulation.
+    pub cu_per_increment: u32,
+    ///Most compute units to ask for in one transaction
+    pub max_cu_per_tx: u32,
+    ///Most transactions waiting on confirmation at once
+    pub max_in_flight: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            cu_per_increment: 10_000,
+            max_cu_per_tx: MAX_COMPUTE_UNIT_LIMIT,
+            max_in_flight: 4,
+        }
+    }
+}
+
+///The instructions of one transaction and which targets they increment
+pub struct Batch {
+    ///Indexes into the targets
+    pub targets: Vec<usize>,
+    ///The compute unit limit and price first, then one increment per target
+    pub ixs: Vec<Instruction>,
+}
+
+///What happened to one target
+pub struct TargetResult {
+    pub counter: Pubkey,
+    pub batch: usize,
+    pub result: Result<Signature, String>,
+}
+
+///The compute budget instructions and the increments for the batch at `index`.
+///Two batches can increment the same targets, `--times` over a short list does exactly that, and
+///identical transactions under the same blockhash have the same signature, so the cluster would
+///only land one of them. A compute unit price of `index` micro-lamports keeps every batch's
+///transaction different. The price is per compute unit asked for, so even at the 1.4M limit it
+///only adds `index` * 1.4 lamports to the fee.
+fn batch_ixs(index: usize, increments: &[Instruction], config: &BatchConfig) -> Vec<Instruction> {
+    let limit = config.cu_per_increment * increments.len() as u32;
+    let mut ixs = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(limit),
+        ComputeBudgetInstruction::set_compute_unit_price(index as u64),
+    ];
+    ixs.extend_from_slice(increments);
+    ixs
+}
+
+///Packs an increment for every target into as few transactions as fit under the packet size
+///and the compute unit limit. Targets stay in order, the same counter can be listed more than once
+///but only goes in a transaction once: with a cooldown set, the second increment of a counter in
+///the same transaction would fail it.
+pub fn pack(
+    program_id: Pubkey,
+    user: &Pubkey,
+    targets: &[Pubkey],
+    format: &TxFormat,
+    config: &BatchConfig,
+) -> anyhow::Result<Vec<Batch>> {
+    let per_tx_cu = (config.max_cu_per_tx / config.cu_per_increment.max(1)) as usize;
+    if per_tx_cu == 0 {
+        Err(anyhow!(
+            "One increment needs {} compute units, more than the {} allowed per transaction",
+            config.cu_per_increment,
+            config.max_cu_per_tx
+        ))?;
+    }
+
+    let mut batches = vec![];
+    let mut targets_in_batch = vec![];
+    let mut counters_in_batch = HashSet::new();
+    let mut increments = vec![];
+
+    for (i, counter) in targets.iter().enumerate() {
+        let keys = increment_keys(program_id, counter, user);
+        let ix = increment_ix_with_program_id(program_id, keys)?;
+
+        if !counters_in_batch.contains(counter) {
+            increments.push(ix.clone());
+            let size =
+                transaction_size(&batch_ixs(batches.len(), &increments, config), user, format)?;
+            if increments.len() <= per_tx_cu && size <= PACKET_DATA_SIZE {
+                targets_in_batch.push(i);
+                counters_in_batch.insert(*counter);
+                continue;
+            }
+            increments.pop();
+            if increments.is_empty() {
+                Err(anyhow!("A single increment doesn't fit in a transaction"))?;
+            }
+        }
+
+        //doesn't fit or is already in it, so close the batch and start the next one with it
+        batches.push(Batch {
+            targets: std::mem::take(&mut targets_in_batch),
+            ixs: batch_ixs(batches.len(), &increments, config),
+        });
+        increments = vec![ix];
+        targets_in_batch.push(i);
+        counters_in_batch = HashSet::from([*counter]);
+    }
+
+    if !increments.is_empty() {
+        batches.push(Batch {
+            targets: targets_in_batch,
+            ixs: batch_ixs(batches.len(), &increments, config),
+        });
+    }
+
+    Ok(batches)
+}
+
+///Compute units to budget per increment of `targets`, from simulating one increment plus
+///`SIMULATED_CU_HEADROOM_PCT`. A first increment costs the most, since it creates the
+///`user_increments` account, so it simulates a target the user hasn't incremented yet if there is
+///one.
+pub fn measure_cu_per_increment(
+    rpc: &RpcClient,
+    program_id: Pubkey,
+    user: &Keypair,
+    targets: &[Pubkey],
+    format: &TxFormat,
+) -> anyhow::Result<u32> {
+    let mut distinct = vec![];
+    let mut seen = HashSet::new();
+    for counter in targets {
+        if seen.insert(*counter) {
+            distinct.push(increment_keys(program_id, counter, &user.pubkey()));
+        }
+    }
+    let Some(mut keys) = distinct.first().copied() else {
+        Err(anyhow!("No counters to increment"))?
+    };
+
+    //get_multiple_accounts takes at most 100 addresses
+    'chunks: for chunk in distinct.chunks(100) {
+        let addresses: Vec<Pubkey> = chunk.iter().map(|keys| keys.user_increments).collect();
+        for (keys_in_chunk, account) in chunk.iter().zip(rpc.get_multiple_accounts(&addresses)?) {
+            if account.is_none() {
+                keys = *keys_in_chunk;
+                break 'chunks;
+            }
+        }
+    }
+
+    let ix = increment_ix_with_program_id(program_id, keys)?;
+    let message = compile_message(&[ix], &user.pubkey(), format, rpc.get_latest_blockhash()?)?;
+    let tx = VersionedTransaction::try_new(message, &[user])?;
+    let simulation = rpc
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+
+    if let Some(err) = simulation.err {
+        Err(anyhow!(
+            "Simulating an increment of {} failed: {}. Pass --cu-per-increment to skip the simulation.\n{}",
+            keys.counter,
+            err,
+            simulation.logs.unwrap_or_default().join("\n")
+        ))?;
+    }
+    let units = simulation
+        .units_consumed
+        .ok_or_else(|| anyhow!("The simulation didn't say how many compute units it used"))?;
+
+    let budget = units + units * SIMULATED_CU_HEADROOM_PCT / 100;
+    Ok(u32::try_from(budget)?)
+}
+
+///Sends the batches with at most `max_in_flight` of them waiting on confirmation at once.
+///A failed batch fails all of its targets, the others carry on.
+pub fn send_batches(
+    rpc: &RpcClient,
+    user: &Keypair,
+    targets: &[Pubkey],
+    batches: &[Batch],
+    format: &TxFormat,
+    config: &BatchConfig,
+) -> Vec<TargetResult> {
+    let next = AtomicUsize::new(0);
+    let outcomes: Mutex<Vec<Option<Result<Signature, String>>>> =
+        Mutex::new(batches.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..config.max_in_flight.clamp(1, batches.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let Some(batch) = batches.get(i) else {
+                    break;
+                };
+                let outcome = send_instructions(rpc, &batch.ixs, user, format)
+                    .map_err(|e| format!("{:?}", e));
+                let n = batches.len();
+                match &outcome {
+                    Ok(sig) => println!("Batch {} of {} landed: {:?}", i + 1, n, sig),
+                    Err(e) => println!("Batch {} of {} failed: {}", i + 1, n, e),
+                }
+                outcomes.lock().unwrap()[i] = Some(outcome);
+            });
+        }
+    });
+
+    let outcomes = outcomes.into_inner().unwrap();
+    let mut results = vec![];
+    for (i, batch) in batches.iter().enumerate() {
+        for target in &batch.targets {
+            results.push(TargetResult {
+                counter: targets[*target],
+                batch: i,
+                result: outcomes[i]
+                    .clone()
+                    .unwrap_or_else(|| Err("not sent".to_string())),
+            });
+        }
+    }
+    results
+}
+
+///Displays how each target did and how many went through
+pub fn display_results(results: &[TargetResult]) {
+    for result in results {
+        match &result.result {
+            Ok(sig) => println!(
+                "{:?}: incremented in batch {} ({:?})",
+                result.counter,
+                result.batch + 1,
+                sig
+            ),
+            Err(e) => println!(
+                "{:?}: failed in batch {}: {}",
+                result.counter,
+                result.batch + 1,
+                e
+            ),
+        }
+    }
+
+    let succeeded = results.iter().filter(|r| r.result.is_ok()).count();
+    println!("{} of {} increments succeeded", succeeded, results.len());
+}
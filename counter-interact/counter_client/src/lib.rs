@@ -0,0 +1,21 @@
+pub mod batch;
+pub mod lookup_table;
+pub mod transaction;
+
+use anchor_counter_interface::IncrementKeys;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+///The keys to increment `counter`, including the PDA that tracks when `user` last incremented it
+pub fn increment_keys(program_id: Pubkey, counter: &Pubkey, user: &Pubkey) -> IncrementKeys {
+    let (user_increments, _) = Pubkey::find_program_address(
+        &[b"user_increments", counter.as_ref(), user.as_ref()],
+        &program_id,
+    );
+    IncrementKeys {
+        counter: *counter,
+        user: *user,
+        user_increments,
+        system_program: system_program::ID,
+    }
+}
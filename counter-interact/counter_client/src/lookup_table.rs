@@ -0,0 +1,172 @@
+use crate::transaction::{send_instructions, TxFormat};
+use anyhow::anyhow;
+use solana_client::rpc_client::RpcClient;
+use solana_program::address_lookup_table::instruction::{
+    close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table,
+};
+use solana_program::address_lookup_table::state::AddressLookupTable;
+use solana_program::address_lookup_table_account::AddressLookupTableAccount;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program::slot_hashes::MAX_ENTRIES;
+use solana_program::system_program;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+
+///The accounts the counter instructions use that are worth putting in a lookup table,
+///including the PDA that tracks the user's increments of this counter
+pub fn counter_addresses(program_id: Pubkey, counter_pda: Pubkey, user: &Pubkey) -> [Pubkey; 4] {
+    let keys = crate::increment_keys(program_id, &counter_pda, user);
+    [
+        program_id,
+        counter_pda,
+        keys.user_increments,
+        system_program::ID,
+    ]
+}
+
+///Reads the account data of a lookup table in the form `v0::Message::try_compile` takes
+pub fn parse_lookup_table(
+    address: &Pubkey,
+    data: &[u8],
+) -> anyhow::Result<AddressLookupTableAccount> {
+    let table = AddressLookupTable::deserialize(data)
+        .map_err(|e| anyhow!("{} is not a lookup table: {:?}", address, e))?;
+
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+///Fetches a lookup table in the form `v0::Message::try_compile` takes
+pub fn load_lookup_table(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> anyhow::Result<AddressLookupTableAccount> {
+    let data = rpc.get_account_data(address)?;
+    parse_lookup_table(address, &data)
+}
+
+///The instruction that creates an empty lookup table with the user as its authority, and the
+///table's address. `recent_slot` has to be in the cluster's slot hashes.
+pub fn create_ix(user: &Pubkey, recent_slot: u64) -> (Instruction, Pubkey) {
+    create_lookup_table(*user, *user, recent_slot)
+}
+
+///The instruction that adds whichever of the addresses `current` doesn't have yet.
+///`None` if it has them all.
+pub fn extend_ix(
+    user: &Pubkey,
+    current: &AddressLookupTableAccount,
+    addresses: &[Pubkey],
+) -> Option<Instruction> {
+    let missing: Vec<Pubkey> = addresses
+        .iter()
+        .filter(|address| !current.addresses.contains(address))
+        .copied()
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+
+    Some(extend_lookup_table(
+        current.key,
+        *user,
+        Some(*user),
+        missing,
+    ))
+}
+
+///The instruction that closes a deactivated table and sends its rent back to the user.
+///Fails if the table is still active or cooling down at `current_slot`.
+pub fn close_ix(
+    user: &Pubkey,
+    table: &Pubkey,
+    data: &[u8],
+    current_slot: u64,
+) -> anyhow::Result<Instruction> {
+    let state = AddressLookupTable::deserialize(data)
+        .map_err(|e| anyhow!("{} is not a lookup table: {:?}", table, e))?;
+
+    let deactivation_slot = state.meta.deactivation_slot;
+    if deactivation_slot == u64::MAX {
+        Err(anyhow!(
+            "Lookup table is still active, run alt_deactivate first"
+        ))?;
+    }
+    //a deactivated table stays usable until its deactivation slot drops out of the slot hashes
+    let closable_at = deactivation_slot + MAX_ENTRIES as u64 + 1;
+    if current_slot < closable_at {
+        Err(anyhow!(
+            "Lookup table is cooling down, it can be closed in about {} slots",
+            closable_at - current_slot
+        ))?;
+    }
+
+    Ok(close_lookup_table(*table, *user, *user))
+}
+
+///Creates an empty lookup table with the user as its authority
+pub fn create(rpc: &RpcClient, user: &Keypair) -> anyhow::Result<(Pubkey, Signature)> {
+    //the table address is derived from a recent slot, which the runtime checks against its
+    //slot hashes, so it has to be one the cluster has definitely seen
+    let recent_slot = rpc.get_slot_with_commitment(CommitmentConfig::finalized())?;
+    let (ix, table) = create_ix(&user.pubkey(), recent_slot);
+
+    let sig = send_instructions(rpc, &[ix], user, &TxFormat::Legacy)?;
+    Ok((table, sig))
+}
+
+///Adds whichever of the addresses the table doesn't have yet. `None` if it has them all.
+pub fn extend(
+    rpc: &RpcClient,
+    user: &Keypair,
+    table: &Pubkey,
+    addresses: &[Pubkey],
+) -> anyhow::Result<Option<Signature>> {
+    let current = load_lookup_table(rpc, table)?;
+    let Some(ix) = extend_ix(&user.pubkey(), &current, addresses) else {
+        return Ok(None);
+    };
+
+    let sig = send_instructions(rpc, &[ix], user, &TxFormat::Legacy)?;
+    Ok(Some(sig))
+}
+
+///Starts the cooldown after which the table can be closed. It can't be extended or used anymore.
+pub fn deactivate(rpc: &RpcClient, user: &Keypair, table: &Pubkey) -> anyhow::Result<Signature> {
+    let ix = deactivate_lookup_table(*table, user.pubkey());
+    send_instructions(rpc, &[ix], user, &TxFormat::Legacy)
+}
+
+///Closes a deactivated table and sends its rent back to the user
+pub fn close(rpc: &RpcClient, user: &Keypair, table: &Pubkey) -> anyhow::Result<Signature> {
+    let data = rpc.get_account_data(table)?;
+    let ix = close_ix(&user.pubkey(), table, &data, rpc.get_slot()?)?;
+    send_instructions(rpc, &[ix], user, &TxFormat::Legacy)
+}
+
+///Displays the table's authority, status and addresses
+pub fn display_lookup_table(rpc: &RpcClient, table: &Pubkey) -> anyhow::Result<()> {
+    let data = rpc.get_account_data(table)?;
+    let state = AddressLookupTable::deserialize(&data)
+        .map_err(|e| anyhow!("{} is not a lookup table: {:?}", table, e))?;
+
+    println!("Lookup Table @ Address: {:?}", table);
+    println!("Authority: {:?}", state.meta.authority);
+    if state.meta.deactivation_slot == u64::MAX {
+        println!("Status: active");
+    } else {
+        println!(
+            "Status: deactivated at slot {}",
+            state.meta.deactivation_slot
+        );
+    }
+    for (i, address) in state.addresses.iter().enumerate() {
+        println!("  {}: {:?}", i, address);
+    }
+
+    Ok(())
+}
@@ -1,17 +1,26 @@
 use anchor_counter_interface::{
-    increment_ix_with_program_id, initialize_ix_with_program_id, IncrementKeys, InitializeKeys,
+    counter_program_accounts_config, decode_counters, increment_ix_with_program_id,
+    initialize_ix_with_program_id, set_cooldown_ix_with_program_id, CounterAccount,
+    InitializeIxArgs, InitializeKeys, SetCooldownIxArgs, SetCooldownKeys, COUNTER_MAX_NAME_LEN,
 };
 use anyhow::anyhow;
+use counter_interact::batch::{self, BatchConfig};
+use counter_interact::increment_keys;
+use counter_interact::lookup_table::{self, counter_addresses, display_lookup_table};
+use counter_interact::transaction::{send_instructions, TxFormat};
 use solana_client::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
 use solana_program::system_program;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::signature::{Keypair, Signature};
 use solana_sdk::signer::{EncodableKey, Signer};
-use solana_sdk::transaction::Transaction;
 use std::path::{self, Path};
 use std::str::FromStr;
 
+const USAGE: &str = "Please specify an action: init, incr, init_incr, incr_batch, set_cooldown, list, alt_create, alt_extend, alt_deactivate, alt_close";
+//the counter actions use when there's no --name, the program doesn't allow an empty one
+const DEFAULT_NAME: &str = "default";
+
 fn main() -> anyhow::Result<()> {
     /*SET THESE TO YOUR VALUES OR READ FROM ENV*/
     let path_to_keypair = path::Path::new("/home/derked/.config/solana/id.json");
@@ -22,21 +31,43 @@ fn main() -> anyhow::Result<()> {
         rpc,
         user,
         program_id,
-    } = SetUpClient::new(rpc_url, &path_to_keypair, program_id)?;
+    } = SetUpClient::new(rpc_url, path_to_keypair, program_id)?;
+
+    let mut args: Vec<String> = std::env::args().collect();
 
-    let args: Vec<String> = std::env::args().collect();
+    //every action that touches a counter takes --name, without it they use the "default" counter
+    let name = take_name(&mut args)?;
 
     if args.len() < 2 {
-        Err(anyhow!("Please specify an action: init, incr, init_incr"))?;
+        Err(anyhow!(USAGE))?;
     }
 
-    let (counter_pda, _) = Pubkey::find_program_address(&[b"counter"], &program_id);
+    let (counter_pda, _) =
+        Pubkey::find_program_address(&[b"counter", name.as_bytes()], &program_id);
+
+    if args[1] == "list" {
+        return list_counters(&rpc, &program_id);
+    }
+    if args[1].starts_with("alt_") {
+        return lookup_table_action(&rpc, &user, program_id, &counter_pda, &args[1..]);
+    }
+    if args[1] == "set_cooldown" {
+        return set_cooldown(&rpc, &user, program_id, &counter_pda, &args[2..]);
+    }
+    if args[1] == "incr_batch" {
+        return batch_increment(&rpc, &user, program_id, &counter_pda, &args[2..]);
+    }
+
+    //--v0 sends a v0 transaction, --alt <ADDRESS> also loads accounts from that lookup table
+    let format = TxFormat::from_args(&rpc, &args[2..])?;
 
     let sig = match args[1].as_str() {
-        "init" => initialize(&rpc, &counter_pda, &user, program_id),
-        "incr" => increment(&rpc, &counter_pda, &user, program_id),
-        "init_incr" => initialize_and_increment(&rpc, &counter_pda, &user, program_id),
-        _ => Err(anyhow!("Please specify an action: init, incr, init_incr"))?,
+        "init" => initialize(&rpc, &counter_pda, &name, &user, program_id, &format),
+        "incr" => increment(&rpc, &counter_pda, &user, program_id, &format),
+        "init_incr" => {
+            initialize_and_increment(&rpc, &counter_pda, &name, &user, program_id, &format)
+        }
+        _ => Err(anyhow!(USAGE))?,
     };
 
     match (sig, args[1].as_str()) {
@@ -48,7 +79,13 @@ fn main() -> anyhow::Result<()> {
                 Err(anyhow!("Something went wrong with initializing: {:?}", e))?;
             }
         }
-        (Err(e), "incr") => Err(anyhow!("Something went wrong with incrementing: {:?}", e))?,
+        (Err(e), "incr") => {
+            if e.to_string().contains("custom program error: 0x1771") {
+                println!("Incremented too recently! Wait for the counter's cooldown to pass.");
+            } else {
+                Err(anyhow!("Something went wrong with incrementing: {:?}", e))?;
+            }
+        }
         (Err(e), "init_incr") => {
             if e.to_string().contains("custom program error: 0x0") {
                 println!("Counter Account Already Initialized! Cannot increment afterwards!");
@@ -88,33 +125,241 @@ impl SetUpClient {
     }
 }
 
-///Initialize the Counter Account
+///Removes `--name <NAME>` from the args and returns the name, `DEFAULT_NAME` if there wasn't one
+fn take_name(args: &mut Vec<String>) -> anyhow::Result<String> {
+    let Some(i) = args.iter().position(|arg| arg == "--name") else {
+        return Ok(DEFAULT_NAME.to_string());
+    };
+    if i + 1 >= args.len() {
+        Err(anyhow!("--name needs a value"))?;
+    }
+
+    let name = args.drain(i..i + 2).nth(1).unwrap();
+    //the program rejects an empty name, it would give the PDA of the old single counter
+    if name.is_empty() {
+        Err(anyhow!("Counter names can't be empty"))?;
+    }
+    //a longer name can't be a PDA seed
+    if name.len() > COUNTER_MAX_NAME_LEN {
+        Err(anyhow!(
+            "Counter names can be at most {} bytes, {:?} is {}",
+            COUNTER_MAX_NAME_LEN,
+            name,
+            name.len()
+        ))?;
+    }
+    Ok(name)
+}
+
+///Increment many counters, packed into as few transactions as fit.
+///Takes counter addresses, defaulting to the counter PDA, and these options:
+///`--times N` to go through the list N times, `--in-flight N`, `--cu-per-increment N` instead of
+///simulating an increment to find out, plus `--v0` and `--alt <ADDRESS>` like the other actions.
+fn batch_increment(
+    rpc: &RpcClient,
+    user: &Keypair,
+    program_id: Pubkey,
+    counter_pda: &Pubkey,
+    args: &[String],
+) -> anyhow::Result<()> {
+    let mut counters = vec![];
+    let mut times = 1;
+    let mut config = BatchConfig::default();
+    let mut cu_per_increment = None;
+    let mut format_args = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("{} needs a value", arg))
+                .cloned()
+        };
+        match arg.as_str() {
+            "--times" => times = value()?.parse()?,
+            "--in-flight" => config.max_in_flight = value()?.parse()?,
+            "--cu-per-increment" => cu_per_increment = Some(value()?.parse()?),
+            "--v0" => format_args.push(arg.clone()),
+            "--alt" => format_args.extend([arg.clone(), value()?]),
+            counter => counters.push(Pubkey::from_str(counter)?),
+        }
+    }
+    if counters.is_empty() {
+        counters.push(*counter_pda);
+    }
+    let targets: Vec<Pubkey> = counters.repeat(times);
+
+    let format = TxFormat::from_args(rpc, &format_args)?;
+    config.cu_per_increment = match cu_per_increment {
+        Some(cu) => cu,
+        None => {
+            let cu = batch::measure_cu_per_increment(rpc, program_id, user, &targets, &format)?;
+            println!("Budgeting {} compute units per increment", cu);
+            cu
+        }
+    };
+    let batches = batch::pack(program_id, &user.pubkey(), &targets, &format, &config)?;
+    println!(
+        "Packed {} increments into {} transaction(s)",
+        targets.len(),
+        batches.len()
+    );
+
+    let results = batch::send_batches(rpc, user, &targets, &batches, &format, &config);
+    batch::display_results(&results);
+    if results.iter().any(|result| result.result.is_err()) {
+        Err(anyhow!("Some increments failed"))?;
+    }
+
+    Ok(())
+}
+
+///Set how many slots a user has to wait between increments, 0 turns the cooldown off.
+///Only the counter's authority, whoever initialized it, can do this.
+fn set_cooldown(
+    rpc: &RpcClient,
+    user: &Keypair,
+    program_id: Pubkey,
+    counter_pda: &Pubkey,
+    args: &[String],
+) -> anyhow::Result<()> {
+    let cooldown_slots: u64 = args
+        .first()
+        .ok_or_else(|| anyhow!("set_cooldown needs the number of slots"))?
+        .parse()?;
+    let format = TxFormat::from_args(rpc, &args[1..])?;
+
+    let keys = SetCooldownKeys {
+        counter: *counter_pda,
+        authority: user.pubkey(),
+    };
+    let ix =
+        set_cooldown_ix_with_program_id(program_id, keys, SetCooldownIxArgs { cooldown_slots })?;
+
+    match send_instructions(rpc, &[ix], user, &format) {
+        Ok(sig) => println!("Tx Successful with Signature: {:?}", sig),
+        //anchor's ConstraintHasOne
+        Err(e) if e.to_string().contains("custom program error: 0x7d1") => {
+            Err(anyhow!("Only the counter's authority can set its cooldown"))?
+        }
+        Err(e) => Err(anyhow!(
+            "Something went wrong with setting the cooldown: {:?}",
+            e
+        ))?,
+    }
+
+    display_counter_info(rpc, counter_pda)?;
+    Ok(())
+}
+
+///Create, extend, deactivate or close a lookup table with the counter's accounts
+fn lookup_table_action(
+    rpc: &RpcClient,
+    user: &Keypair,
+    program_id: Pubkey,
+    counter_pda: &Pubkey,
+    args: &[String],
+) -> anyhow::Result<()> {
+    let table = || -> anyhow::Result<Pubkey> {
+        let address = args
+            .get(1)
+            .ok_or_else(|| anyhow!("{} needs the lookup table address", args[0]))?;
+        Ok(Pubkey::from_str(address)?)
+    };
+
+    match args[0].as_str() {
+        "alt_create" => {
+            let (table, sig) = lookup_table::create(rpc, user)?;
+            println!("Tx Successful with Signature: {:?}", sig);
+            println!("Run alt_extend {} to add the counter accounts", table);
+            display_lookup_table(rpc, &table)?;
+        }
+        "alt_extend" => {
+            let table = table()?;
+            let addresses = counter_addresses(program_id, *counter_pda, &user.pubkey());
+            match lookup_table::extend(rpc, user, &table, &addresses)? {
+                Some(sig) => println!("Tx Successful with Signature: {:?}", sig),
+                None => println!("Lookup table already has the counter accounts"),
+            }
+            display_lookup_table(rpc, &table)?;
+        }
+        "alt_deactivate" => {
+            let table = table()?;
+            let sig = lookup_table::deactivate(rpc, user, &table)?;
+            println!("Tx Successful with Signature: {:?}", sig);
+            display_lookup_table(rpc, &table)?;
+        }
+        "alt_close" => {
+            let sig = lookup_table::close(rpc, user, &table()?)?;
+            println!("Tx Successful with Signature: {:?}", sig);
+        }
+        _ => Err(anyhow!(USAGE))?,
+    }
+
+    Ok(())
+}
+
+///Initialize the Counter Account with the given name
 fn initialize(
     rpc: &RpcClient,
     counter_pda: &Pubkey,
+    name: &str,
     user: &Keypair,
     program_id: Pubkey,
+    format: &TxFormat,
 ) -> anyhow::Result<Signature> {
     let init_keys = InitializeKeys::from([*counter_pda, user.pubkey(), system_program::ID]);
+    let init_args = InitializeIxArgs {
+        name: name.to_string(),
+    };
 
-    let ix = initialize_ix_with_program_id(program_id, init_keys)?;
-    let recent_blockhash = rpc.get_latest_blockhash()?;
-
-    let tx =
-        Transaction::new_signed_with_payer(&[ix], Some(&user.pubkey()), &[&user], recent_blockhash);
+    let ix = initialize_ix_with_program_id(program_id, init_keys, init_args)?;
 
-    let sig = rpc.send_and_confirm_transaction(&tx)?;
+    let sig = send_instructions(rpc, &[ix], user, format)?;
 
     Ok(sig)
 }
 
-///Displays the count in the Counter PDA
+///Displays the name, count, authority and cooldown in the Counter PDA
 fn display_counter_info(rpc: &RpcClient, counter_pda: &Pubkey) -> anyhow::Result<()> {
     println!("Counter Account @ Address: {:?}", counter_pda);
-    let counter_acct_data = rpc.get_account_data(&counter_pda)?;
-    let counter_acct = anchor_counter_interface::CounterAccount::deserialize(&counter_acct_data)?.0;
+    let counter_acct_data = rpc.get_account_data(counter_pda)?;
+    let counter_acct = CounterAccount::deserialize(&counter_acct_data)?.0;
 
+    println!("Counter name: {:?}", counter_acct.name);
     println!("Counter count: {:?}", counter_acct.count);
+    println!("Counter authority: {:?}", counter_acct.authority);
+    println!("Counter cooldown: {} slot(s)", counter_acct.cooldown_slots);
+
+    Ok(())
+}
+
+///Finds every Counter Account the program owns and displays them by name,
+///then any account with the Counter discriminator that doesn't decode
+fn list_counters(rpc: &RpcClient, program_id: &Pubkey) -> anyhow::Result<()> {
+    //the config filters on the Counter discriminator, so no zero copy counters
+    let accounts =
+        rpc.get_program_accounts_with_config(program_id, counter_program_accounts_config())?;
+    let mut decoded = decode_counters(accounts);
+    decoded.counters.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+
+    println!("Found {} counter(s)", decoded.counters.len());
+    for (address, counter_acct) in &decoded.counters {
+        println!(
+            "{:?} @ {:?}: {}",
+            counter_acct.name, address, counter_acct.count
+        );
+    }
+
+    if !decoded.undecodable.is_empty() {
+        println!(
+            "{} account(s) look like counters but don't decode:",
+            decoded.undecodable.len()
+        );
+        for (address, e) in &decoded.undecodable {
+            println!("  {:?}: {}", address, e);
+        }
+    }
 
     Ok(())
 }
@@ -125,16 +370,13 @@ fn increment(
     counter_pda: &Pubkey,
     user: &Keypair,
     program_id: Pubkey,
+    format: &TxFormat,
 ) -> anyhow::Result<Signature> {
-    let increment_keys = IncrementKeys::from([*counter_pda, user.pubkey()]);
+    let increment_keys = increment_keys(program_id, counter_pda, &user.pubkey());
 
     let ix = increment_ix_with_program_id(program_id, increment_keys)?;
-    let recent_blockhash = rpc.get_latest_blockhash()?;
-
-    let tx =
-        Transaction::new_signed_with_payer(&[ix], Some(&user.pubkey()), &[&user], recent_blockhash);
 
-    let sig = rpc.send_and_confirm_transaction(&tx)?;
+    let sig = send_instructions(rpc, &[ix], user, format)?;
 
     Ok(sig)
 }
@@ -144,23 +386,20 @@ fn increment(
 fn initialize_and_increment(
     rpc: &RpcClient,
     counter_pda: &Pubkey,
+    name: &str,
     user: &Keypair,
     program_id: Pubkey,
+    format: &TxFormat,
 ) -> anyhow::Result<Signature> {
     let init_keys = InitializeKeys::from([*counter_pda, user.pubkey(), system_program::ID]);
-    let init_ix = initialize_ix_with_program_id(program_id, init_keys)?;
+    let init_args = InitializeIxArgs {
+        name: name.to_string(),
+    };
+    let init_ix = initialize_ix_with_program_id(program_id, init_keys, init_args)?;
 
-    let increment_keys = IncrementKeys::from([*counter_pda, user.pubkey()]);
+    let increment_keys = increment_keys(program_id, counter_pda, &user.pubkey());
     let increment_ix = increment_ix_with_program_id(program_id, increment_keys)?;
 
-    let recent_blockhash = rpc.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &[init_ix, increment_ix],
-        Some(&user.pubkey()),
-        &[&user],
-        recent_blockhash,
-    );
-
-    let sig = rpc.send_and_confirm_transaction(&tx)?;
+    let sig = send_instructions(rpc, &[init_ix, increment_ix], user, format)?;
     Ok(sig)
 }
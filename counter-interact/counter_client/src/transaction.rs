@@ -0,0 +1,126 @@
+use crate::lookup_table::load_lookup_table;
+use anyhow::anyhow;
+use solana_client::rpc_client::RpcClient;
+use solana_program::address_lookup_table_account::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+use std::str::FromStr;
+
+///Which kind of transaction to send the instructions in
+pub enum TxFormat {
+    Legacy,
+    ///A v0 transaction, which can load accounts from these lookup tables instead of listing them
+    V0(Vec<AddressLookupTableAccount>),
+}
+
+impl TxFormat {
+    ///Reads `--v0` and `--alt <ADDRESS>` from the args after the action.
+    ///`--alt` can be repeated and implies `--v0`.
+    pub fn from_args(rpc: &RpcClient, args: &[String]) -> anyhow::Result<Self> {
+        let mut v0 = false;
+        let mut tables = vec![];
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--v0" => v0 = true,
+                "--alt" => {
+                    let address = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--alt needs a lookup table address"))?;
+                    tables.push(load_lookup_table(rpc, &Pubkey::from_str(address)?)?);
+                }
+                other => Err(anyhow!("Unknown option: {}", other))?,
+            }
+        }
+
+        if v0 || !tables.is_empty() {
+            Ok(Self::V0(tables))
+        } else {
+            Ok(Self::Legacy)
+        }
+    }
+}
+
+///Compiles the instructions into a message of the given format, with `payer` paying the fees
+pub fn compile_message(
+    ixs: &[Instruction],
+    payer: &Pubkey,
+    format: &TxFormat,
+    recent_blockhash: Hash,
+) -> anyhow::Result<VersionedMessage> {
+    let message = match format {
+        TxFormat::Legacy => VersionedMessage::Legacy(Message::new_with_blockhash(
+            ixs,
+            Some(payer),
+            &recent_blockhash,
+        )),
+        //try_compile moves every account it finds in the tables out of the message, except
+        //the programs being called, which always have to be listed in the message itself
+        TxFormat::V0(tables) => VersionedMessage::V0(v0::Message::try_compile(
+            payer,
+            ixs,
+            tables,
+            recent_blockhash,
+        )?),
+    };
+
+    Ok(message)
+}
+
+///How many accounts the message loads from lookup tables instead of listing them
+pub fn loaded_accounts(message: &v0::Message) -> usize {
+    message
+        .address_table_lookups
+        .iter()
+        .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+        .sum()
+}
+
+///Signs the instructions into a transaction of the given format and sends it
+pub fn send_instructions(
+    rpc: &RpcClient,
+    ixs: &[Instruction],
+    user: &Keypair,
+    format: &TxFormat,
+) -> anyhow::Result<Signature> {
+    let recent_blockhash = rpc.get_latest_blockhash()?;
+    let message = compile_message(ixs, &user.pubkey(), format, recent_blockhash)?;
+
+    if let VersionedMessage::V0(message) = &message {
+        println!(
+            "Sending v0 transaction: {} accounts in the message, {} loaded from {} lookup table(s)",
+            message.account_keys.len(),
+            loaded_accounts(message),
+            message.address_table_lookups.len()
+        );
+    }
+
+    let tx = VersionedTransaction::try_new(message, &[user])?;
+    let sig = rpc.send_and_confirm_transaction(&tx)?;
+
+    Ok(sig)
+}
+
+///Size in bytes of the transaction the instructions would make, signatures included. This is
+///what has to fit in a packet.
+pub fn transaction_size(
+    ixs: &[Instruction],
+    payer: &Pubkey,
+    format: &TxFormat,
+) -> anyhow::Result<usize> {
+    //the blockhash is the same size whatever its value
+    let message = compile_message(ixs, payer, format, Hash::default())?;
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::default(); message.header().num_required_signatures as usize],
+        message,
+    };
+
+    Ok(bincode::serialized_size(&tx)? as usize)
+}
@@ -0,0 +1,140 @@
+use anchor_counter_interface::increment_ix_with_program_id;
+use counter_interact::batch::{pack, BatchConfig};
+use counter_interact::increment_keys;
+use counter_interact::transaction::{transaction_size, TxFormat};
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+
+///Compute budget instructions at the front of every batch
+const BUDGET_IXS: usize = 2;
+
+#[test]
+fn test_pack_splits_on_packet_size() {
+    let program_id = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let targets: Vec<Pubkey> = (0..40).map(|_| Pubkey::new_unique()).collect();
+    //enough compute units for all of them, so only the size can split them
+    let config = BatchConfig {
+        cu_per_increment: 1,
+        ..BatchConfig::default()
+    };
+
+    let batches = pack(program_id, &user, &targets, &TxFormat::Legacy, &config).unwrap();
+    assert!(batches.len() > 1);
+
+    //every target once, in order
+    let packed: Vec<usize> = batches.iter().flat_map(|b| b.targets.clone()).collect();
+    assert_eq!(packed, (0..targets.len()).collect::<Vec<_>>());
+
+    for (i, batch) in batches.iter().enumerate() {
+        let size = transaction_size(&batch.ixs, &user, &TxFormat::Legacy).unwrap();
+        assert!(size <= PACKET_DATA_SIZE);
+        assert_eq!(batch.ixs.len(), BUDGET_IXS + batch.targets.len());
+
+        //a batch only closes when the next increment wouldn't have fit
+        if let Some(next) = batches.get(i + 1) {
+            let keys = increment_keys(program_id, &targets[next.targets[0]], &user);
+            let mut ixs = batch.ixs.clone();
+            ixs.push(increment_ix_with_program_id(program_id, keys).unwrap());
+            assert!(transaction_size(&ixs, &user, &TxFormat::Legacy).unwrap() > PACKET_DATA_SIZE);
+        }
+    }
+}
+
+#[test]
+fn test_pack_splits_on_compute_units() {
+    let program_id = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let targets: Vec<Pubkey> = (0..7).map(|_| Pubkey::new_unique()).collect();
+    let config = BatchConfig {
+        cu_per_increment: 10_000,
+        max_cu_per_tx: 30_000,
+        ..BatchConfig::default()
+    };
+
+    let batches = pack(program_id, &user, &targets, &TxFormat::Legacy, &config).unwrap();
+    let sizes: Vec<usize> = batches.iter().map(|b| b.targets.len()).collect();
+    assert_eq!(sizes, vec![3, 3, 1]);
+
+    //the limit covers just the increments in the batch
+    assert_eq!(
+        batches[0].ixs[0],
+        ComputeBudgetInstruction::set_compute_unit_limit(30_000)
+    );
+    assert_eq!(
+        batches[2].ixs[0],
+        ComputeBudgetInstruction::set_compute_unit_limit(10_000)
+    );
+}
+
+#[test]
+fn test_pack_repeated_targets() {
+    let program_id = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let counter = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let targets = vec![counter, other, counter, counter, other];
+
+    //with a cooldown the second increment of a counter in a transaction fails it, so a counter
+    //only goes in once and a repeat starts the next transaction
+    let batches = pack(
+        program_id,
+        &user,
+        &targets,
+        &TxFormat::Legacy,
+        &BatchConfig::default(),
+    )
+    .unwrap();
+    let packed: Vec<Vec<usize>> = batches.iter().map(|b| b.targets.clone()).collect();
+    assert_eq!(packed, vec![vec![0, 1], vec![2], vec![3, 4]]);
+    for batch in &batches {
+        assert_eq!(batch.ixs.len(), BUDGET_IXS + batch.targets.len());
+    }
+}
+
+#[test]
+fn test_pack_increment_over_compute_limit() {
+    let config = BatchConfig {
+        cu_per_increment: 2_000_000,
+        ..BatchConfig::default()
+    };
+
+    let res = pack(
+        Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &[Pubkey::new_unique()],
+        &TxFormat::Legacy,
+        &config,
+    );
+    let e = res.err().unwrap().to_string();
+    assert!(e.contains("more than the 1400000 allowed"), "{}", e);
+}
+
+#[test]
+fn test_pack_batches_with_same_targets_differ() {
+    let program_id = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    //what --times 2 over three counters makes with three increments per transaction
+    let counters: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let targets = counters.repeat(2);
+    let config = BatchConfig {
+        cu_per_increment: 10_000,
+        max_cu_per_tx: 30_000,
+        ..BatchConfig::default()
+    };
+
+    let batches = pack(program_id, &user, &targets, &TxFormat::Legacy, &config).unwrap();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].ixs[BUDGET_IXS..], batches[1].ixs[BUDGET_IXS..]);
+
+    //same increments, but the messages and so the signatures have to differ or only one lands
+    let first = Message::new(&batches[0].ixs, Some(&user));
+    let second = Message::new(&batches[1].ixs, Some(&user));
+    assert_ne!(first, second);
+    assert_eq!(
+        batches[1].ixs[1],
+        ComputeBudgetInstruction::set_compute_unit_price(1)
+    );
+}
@@ -0,0 +1,183 @@
+//! Runs the lookup table commands and a v0 increment against a local bank with the real
+//! anchor_counter program. Build the program first with `anchor build` in `anchor-counter`,
+//! the test loads it from that project's `target/deploy`.
+use anchor_counter_interface::{
+    increment_ix_with_program_id, initialize_ix_with_program_id, CounterAccount, InitializeIxArgs,
+    InitializeKeys,
+};
+use counter_interact::increment_keys;
+use counter_interact::lookup_table::{
+    close_ix, counter_addresses, create_ix, extend_ix, parse_lookup_table,
+};
+use counter_interact::transaction::{compile_message, loaded_accounts, transaction_size, TxFormat};
+use solana_program::address_lookup_table::instruction::deactivate_lookup_table;
+use solana_program::address_lookup_table::state::AddressLookupTable;
+use solana_program::address_lookup_table_account::AddressLookupTableAccount;
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+use solana_program::slot_hashes::{SlotHashes, MAX_ENTRIES};
+use solana_program::system_program;
+use solana_program_test::{tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+
+///The id the program declares, anchor refuses to run under any other
+const PROGRAM_ID: Pubkey = pubkey!("CQ2VvuR8Du2WQq1XWmzBKxmK4arc7BVReWMxUMW3nJs5");
+
+///Where `anchor build` puts `anchor_counter.so`
+const PROGRAM_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../anchor-counter/target/deploy"
+);
+
+///Signs the instructions into a transaction of the given format on a fresh blockhash, so the
+///same instructions twice don't make the same signature, and processes it
+async fn send(
+    context: &mut ProgramTestContext,
+    ixs: &[Instruction],
+    user: &Keypair,
+    format: &TxFormat,
+) -> anyhow::Result<VersionedTransaction> {
+    let blockhash = context.get_new_latest_blockhash().await?;
+    let message = compile_message(ixs, &user.pubkey(), format, blockhash)?;
+    let tx = VersionedTransaction::try_new(message, &[user])?;
+    context.banks_client.process_transaction(tx.clone()).await?;
+    Ok(tx)
+}
+
+async fn account_data(context: &mut ProgramTestContext, address: &Pubkey) -> Vec<u8> {
+    context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+///Replaces the slot hashes with just `slot`. Warping only adds one slot hash at a time, so this
+///is how the lookup table program gets a recent slot to create from and forgets a deactivation.
+fn set_slot_hashes(context: &ProgramTestContext, slot: u64) {
+    context.set_sysvar(&SlotHashes::new(&[(slot, Hash::new_unique())]));
+}
+
+#[tokio::test]
+async fn test_lookup_table_flow() -> anyhow::Result<()> {
+    if std::env::var("BPF_OUT_DIR").is_err() && std::env::var("SBF_OUT_DIR").is_err() {
+        std::env::set_var("SBF_OUT_DIR", PROGRAM_DIR);
+    }
+    let mut validator = ProgramTest::new("anchor_counter", PROGRAM_ID, None);
+    let user = Keypair::new();
+    validator.add_account(
+        user.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut context = validator.start_with_context().await;
+    context.warp_to_slot(100)?;
+
+    let (counter, _) = Pubkey::find_program_address(&[b"counter", b"deploys"], &PROGRAM_ID);
+    let init_ix = initialize_ix_with_program_id(
+        PROGRAM_ID,
+        InitializeKeys::from([counter, user.pubkey(), system_program::ID]),
+        InitializeIxArgs {
+            name: "deploys".to_string(),
+        },
+    )?;
+    send(&mut context, &[init_ix], &user, &TxFormat::Legacy).await?;
+
+    //alt_create
+    set_slot_hashes(&context, 99);
+    let (create, table) = create_ix(&user.pubkey(), 99);
+    send(&mut context, &[create], &user, &TxFormat::Legacy).await?;
+    let created = parse_lookup_table(&table, &account_data(&mut context, &table).await)?;
+    assert!(created.addresses.is_empty());
+
+    //alt_extend, and running it again has nothing left to add
+    let addresses = counter_addresses(PROGRAM_ID, counter, &user.pubkey());
+    let extend = extend_ix(&user.pubkey(), &created, &addresses).unwrap();
+    send(&mut context, &[extend], &user, &TxFormat::Legacy).await?;
+    let extended = parse_lookup_table(&table, &account_data(&mut context, &table).await)?;
+    assert_eq!(extended.addresses, addresses.to_vec());
+    assert!(extend_ix(&user.pubkey(), &extended, &addresses).is_none());
+
+    //addresses added in a slot can only be looked up from the next one
+    context.warp_to_slot(101)?;
+
+    let keys = increment_keys(PROGRAM_ID, &counter, &user.pubkey());
+    let increment = increment_ix_with_program_id(PROGRAM_ID, keys)?;
+    let format = TxFormat::V0(vec![AddressLookupTableAccount {
+        key: table,
+        addresses: extended.addresses.clone(),
+    }]);
+
+    //the counter and user_increments PDAs and the system program come from the table, the
+    //signer and the program being called can't
+    let message = match compile_message(
+        std::slice::from_ref(&increment),
+        &user.pubkey(),
+        &format,
+        Hash::default(),
+    )? {
+        VersionedMessage::V0(message) => message,
+        VersionedMessage::Legacy(_) => unreachable!(),
+    };
+    assert_eq!(loaded_accounts(&message), 3);
+    assert_eq!(message.account_keys, vec![user.pubkey(), PROGRAM_ID]);
+    let lookup = &message.address_table_lookups[0];
+    assert_eq!(lookup.account_key, table);
+    let mut writable: Vec<Pubkey> = lookup
+        .writable_indexes
+        .iter()
+        .map(|i| extended.addresses[*i as usize])
+        .collect();
+    //try_compile goes through the keys in address order, not table order
+    writable.sort();
+    let mut expected = vec![counter, keys.user_increments];
+    expected.sort();
+    assert_eq!(writable, expected);
+    assert_eq!(
+        extended.addresses[lookup.readonly_indexes[0] as usize],
+        system_program::ID
+    );
+
+    let v0_size = transaction_size(std::slice::from_ref(&increment), &user.pubkey(), &format)?;
+    let legacy_size = transaction_size(
+        std::slice::from_ref(&increment),
+        &user.pubkey(),
+        &TxFormat::Legacy,
+    )?;
+    assert!(v0_size < legacy_size, "{} >= {}", v0_size, legacy_size);
+
+    //sent through the table, and the size matches what actually went out
+    let tx = send(&mut context, &[increment], &user, &format).await?;
+    assert_eq!(v0_size, bincode::serialized_size(&tx)? as usize);
+    let counter_acct = CounterAccount::deserialize(&account_data(&mut context, &counter).await)?.0;
+    assert_eq!(counter_acct.count, 1);
+
+    //alt_deactivate, after which alt_close has to wait out the cooldown
+    let deactivate = deactivate_lookup_table(table, user.pubkey());
+    send(&mut context, &[deactivate], &user, &TxFormat::Legacy).await?;
+    let data = account_data(&mut context, &table).await;
+    let state = AddressLookupTable::deserialize(&data).unwrap();
+    assert_eq!(state.meta.deactivation_slot, 101);
+    let e = close_ix(&user.pubkey(), &table, &data, 101).unwrap_err();
+    assert!(e.to_string().contains("cooling down"), "{}", e);
+
+    //alt_close once the deactivation slot is out of the slot hashes
+    let closable_at = 101 + MAX_ENTRIES as u64 + 1;
+    context.warp_to_slot(closable_at)?;
+    set_slot_hashes(&context, closable_at - 1);
+    let close = close_ix(&user.pubkey(), &table, &data, closable_at)?;
+    send(&mut context, &[close], &user, &TxFormat::Legacy).await?;
+    assert!(context.banks_client.get_account(table).await?.is_none());
+
+    Ok(())
+}
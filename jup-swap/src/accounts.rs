@@ -0,0 +1,245 @@
+use crate::simulate::{balance_sources, BalanceSource};
+use crate::swap::ResolvedPair;
+use crate::tokens::{raw_to_ui, TokenRegistry, TOKEN_2022_PROGRAM_ID};
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+use solana_account_decoder::UiAccountData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Size of an SPL Token account
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+/// Size of a Token-2022 ATA, which always gets the immutable owner extension. Mints with other
+/// extensions can make their token accounts bigger still.
+pub const TOKEN_2022_ATA_LEN: usize = 170;
+
+/// A token account the swap needs that doesn't exist yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingAccount {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    /// Lamports it takes to make the account rent exempt
+    pub rent: u64,
+}
+
+/// Checks that the token accounts the swap reads from and writes to exist. The input account has
+/// to exist or there's nothing to sell. A missing output ATA gets created by Jupiter in the swap
+/// transaction, which costs rent, so it's returned for the caller to report or create up front.
+pub async fn ata_preflight(
+    rpc: &RpcClient,
+    wallet: &Pubkey,
+    pair: &ResolvedPair,
+    wrap_and_unwrap_sol: bool,
+    destination_token_account: Option<Pubkey>,
+) -> anyhow::Result<Vec<MissingAccount>> {
+    let (input, output) = balance_sources(
+        rpc,
+        wallet,
+        pair,
+        wrap_and_unwrap_sol,
+        destination_token_account,
+    )
+    .await?;
+
+    let mints = rpc
+        .get_multiple_accounts(&[pair.input_mint, pair.output_mint])
+        .await
+        .context("fetching mints")?;
+    let programs: Vec<Pubkey> = mints
+        .iter()
+        .map(|mint| mint.as_ref().map_or(spl_token::ID, |mint| mint.owner))
+        .collect();
+
+    let existing = rpc
+        .get_multiple_accounts(&[input.address(), output.address()])
+        .await
+        .context("fetching token accounts")?;
+
+    if let (BalanceSource::TokenAccount(address), None) = (input, &existing[0]) {
+        return Err(anyhow!(
+            "input token account {} doesn't exist, there's nothing to swap from{}",
+            address,
+            if pair.input_mint == spl_token::native_mint::ID {
+                " (wrap your SOL first or leave --wrap-unwrap-sol on)"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    let mut missing = vec![];
+    if let (BalanceSource::TokenAccount(address), None) = (output, &existing[1]) {
+        if destination_token_account.is_some() {
+            return Err(anyhow!(
+                "destination token account {} doesn't exist, Jupiter only creates ATAs",
+                address
+            ));
+        }
+
+        let len = if programs[1] == TOKEN_2022_PROGRAM_ID {
+            TOKEN_2022_ATA_LEN
+        } else {
+            TOKEN_ACCOUNT_LEN
+        };
+        missing.push(MissingAccount {
+            address,
+            mint: pair.output_mint,
+            token_program: programs[1],
+            rent: rpc.get_minimum_balance_for_rent_exemption(len).await?,
+        });
+    }
+
+    Ok(missing)
+}
+
+/// Creates the missing ATAs in one transaction before the swap. Idempotent, so it's harmless if
+/// someone else creates one in the meantime.
+pub async fn create_missing_atas(
+    rpc: &RpcClient,
+    wallet: &Keypair,
+    missing: &[MissingAccount],
+) -> anyhow::Result<Signature> {
+    let instructions: Vec<_> = missing
+        .iter()
+        .map(|account| {
+            create_associated_token_account_idempotent(
+                &wallet.pubkey(),
+                &wallet.pubkey(),
+                &account.mint,
+                &account.token_program,
+            )
+        })
+        .collect();
+
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&wallet.pubkey()),
+        &[wallet],
+        blockhash,
+    );
+    Ok(rpc.send_and_confirm_transaction(&tx).await?)
+}
+
+/// One token account of the wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenHolding {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenHolding {
+    /// Reads a token account from the `jsonParsed` encoding the rpc uses for
+    /// `getTokenAccountsByOwner`, which conveniently includes the mint's decimals
+    pub fn from_parsed(
+        account: Pubkey,
+        token_program: Pubkey,
+        parsed: &Value,
+    ) -> anyhow::Result<Self> {
+        let info = &parsed["info"];
+        let mint = info["mint"]
+            .as_str()
+            .ok_or_else(|| anyhow!("token account {} has no mint", account))?;
+        let amount = info["tokenAmount"]["amount"]
+            .as_str()
+            .ok_or_else(|| anyhow!("token account {} has no amount", account))?;
+        let decimals = info["tokenAmount"]["decimals"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("token account {} has no decimals", account))?;
+
+        Ok(Self {
+            account,
+            mint: Pubkey::from_str(mint)?,
+            token_program,
+            amount: amount.parse()?,
+            decimals: decimals as u8,
+        })
+    }
+}
+
+/// Every SPL Token and Token-2022 account the wallet owns
+pub async fn token_holdings(rpc: &RpcClient, wallet: &Pubkey) -> anyhow::Result<Vec<TokenHolding>> {
+    let mut holdings = vec![];
+    for program in [spl_token::ID, TOKEN_2022_PROGRAM_ID] {
+        let accounts = rpc
+            .get_token_accounts_by_owner(wallet, TokenAccountsFilter::ProgramId(program))
+            .await
+            .context("fetching wallet token accounts")?;
+
+        for keyed in accounts {
+            let UiAccountData::Json(parsed) = &keyed.account.data else {
+                return Err(anyhow!("token account {} wasn't json parsed", keyed.pubkey));
+            };
+            holdings.push(TokenHolding::from_parsed(
+                Pubkey::from_str(&keyed.pubkey)?,
+                program,
+                &parsed.parsed,
+            )?);
+        }
+    }
+    Ok(holdings)
+}
+
+/// SOL first, then every token account sorted by symbol. wSOL is called out separately since it
+/// doesn't count towards the SOL balance.
+pub fn render_balances(
+    lamports: u64,
+    holdings: &[TokenHolding],
+    registry: &TokenRegistry,
+) -> String {
+    let mut rows: Vec<(String, &TokenHolding)> = holdings
+        .iter()
+        .map(|holding| {
+            let symbol = if holding.mint == spl_token::native_mint::ID {
+                "wSOL".to_string()
+            } else {
+                registry.symbol(&holding.mint)
+            };
+            (symbol, holding)
+        })
+        .collect();
+    rows.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.account.cmp(&b.1.account)));
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{:<12} {:>24} {:<12} account",
+        "token", "balance", "program"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{:<12} {:>24} {:<12} ",
+        "SOL",
+        raw_to_ui(lamports, 9),
+        "native"
+    )
+    .unwrap();
+    for (symbol, holding) in rows {
+        writeln!(
+            out,
+            "{:<12} {:>24} {:<12} {}",
+            symbol,
+            raw_to_ui(holding.amount, holding.decimals),
+            if holding.token_program == TOKEN_2022_PROGRAM_ID {
+                "token-2022"
+            } else {
+                "token"
+            },
+            holding.account
+        )
+        .unwrap();
+    }
+    out
+}
@@ -0,0 +1,374 @@
+use anyhow::anyhow;
+use clap::{ArgAction, Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "jup-swap",
+    about = "Quote and swap tokens through the Jupiter swap API"
+)]
+pub struct Cli {
+    /// JSON file mapping token symbols to mint addresses
+    #[arg(long, global = true, default_value = "tokens.json")]
+    pub tokens: PathBuf,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Get a quote without swapping
+    Quote(PairArgs),
+    /// Get a quote, sign the swap transaction and send it
+    Swap(SwapArgs),
+    /// List the wallet's SOL, wSOL and token balances
+    Balances,
+    /// Buy the output token in fixed size orders on a schedule until the budget is spent
+    Dca(DcaArgs),
+    /// Sell the input token once the quote reaches a price, in chunks, until filled or expired
+    Limit(LimitArgs),
+    /// Swap the wallet back to the target allocations in a config file
+    Rebalance(RebalanceArgs),
+    /// Sell a large amount in timed slices, requoting each one, and report the average price
+    Twap(TwapArgs),
+    /// Quote the same swap with different routing settings side by side and recommend one
+    Compare(CompareArgs),
+}
+
+/// The pair, amount and slippage shared by every command that asks Jupiter for a quote
+#[derive(Args, Debug, Clone)]
+pub struct PairArgs {
+    /// Token to sell, a symbol from the token registry or a mint address
+    #[arg(long = "in")]
+    pub input: String,
+
+    /// Token to buy, a symbol from the token registry or a mint address
+    #[arg(long = "out")]
+    pub output: String,
+
+    /// Amount in UI units (e.g. 0.01 for 0.01 SOL). The input token amount unless --exact-out
+    #[arg(long)]
+    pub amount: String,
+
+    /// Max slippage in basis points
+    #[arg(long, default_value_t = 50)]
+    pub slippage_bps: u16,
+
+    /// Treat --amount as the exact amount of the output token to receive
+    #[arg(long)]
+    pub exact_out: bool,
+}
+
+/// How Jupiter should build the swap transaction, mapped onto its `TransactionConfig`
+#[derive(Args, Debug, Clone)]
+pub struct TxArgs {
+    /// Wrap SOL into a temporary wSOL account for the swap and unwrap it after. Set to false to
+    /// trade out of and into the wallet's wSOL ATA instead.
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub wrap_unwrap_sol: bool,
+
+    /// Route through Jupiter's shared program token accounts instead of creating intermediate
+    /// token accounts for the wallet
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub use_shared_accounts: bool,
+
+    /// Token account to receive the output in, instead of the wallet's ATA. It has to exist.
+    #[arg(long)]
+    pub destination_token_account: Option<String>,
+
+    /// Create a missing output ATA in its own transaction before swapping, instead of only
+    /// reporting the rent Jupiter will charge for creating it in the swap
+    #[arg(long)]
+    pub create_atas: bool,
+
+    /// Priority fee: total lamports (e.g. 10000), `auto`, or a percentile of recent fees paid on
+    /// the route's accounts (e.g. p90). Left to Jupiter when not set.
+    #[arg(long)]
+    pub priority_fee: Option<String>,
+
+    /// Have Jupiter simulate the swap and set the compute unit limit to what it uses, instead of
+    /// the 1.4M maximum
+    #[arg(long)]
+    pub dynamic_compute_unit_limit: bool,
+
+    /// Most the swap may cost in transaction fees (base plus priority), in lamports. With
+    /// --jito-url the bundle tip and the tip transaction's base fee count toward it too.
+    #[arg(long)]
+    pub max_fee_lamports: Option<u64>,
+
+    /// Extra program the swap transaction may invoke, on top of Jupiter, token, ATA, compute
+    /// budget and system. Can be repeated.
+    #[arg(long = "allow-program")]
+    pub allow_programs: Vec<String>,
+
+    /// Send the swap as a bundle with a tip to this block engine, like
+    /// https://mainnet.block-engine.jito.wtf, falling back to the rpc if it doesn't land
+    #[arg(long)]
+    pub jito_url: Option<String>,
+
+    /// Tip for the bundle, in lamports. Counts toward --max-fee-lamports.
+    #[arg(long, default_value_t = 10_000)]
+    pub jito_tip_lamports: u64,
+
+    /// Tip account to pay, one of the block engine's. Defaults to a well known mainnet one.
+    #[arg(long)]
+    pub jito_tip_account: Option<String>,
+}
+
+/// When and how to retry a swap that didn't land
+#[derive(Args, Debug, Clone)]
+pub struct RetryArgs {
+    /// Total attempts. Failed or expired swaps get requoted and sent again, never while the
+    /// previous attempt could still land.
+    #[arg(long, default_value_t = 3)]
+    pub max_attempts: u32,
+
+    /// Raise the slippage by this much after each slippage failure
+    #[arg(long, default_value_t = 0)]
+    pub slippage_step_bps: u16,
+
+    /// Never raise the slippage past this. Defaults to --slippage-bps, so no escalation.
+    #[arg(long)]
+    pub max_slippage_bps: Option<u16>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SwapArgs {
+    #[command(flatten)]
+    pub pair: PairArgs,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    /// Don't ask for confirmation before signing. The guardrails below still apply.
+    #[arg(long, short)]
+    pub yes: bool,
+
+    /// Abort before signing if the quote's price impact is above this percent
+    #[arg(long)]
+    pub max_price_impact_pct: Option<f64>,
+
+    /// Abort before signing if the minimum out after slippage is below this amount (UI units)
+    #[arg(long)]
+    pub min_out: Option<String>,
+
+    /// Append the swap receipt to this trade journal, CSV if it ends in .csv and JSON lines
+    /// otherwise
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DcaArgs {
+    //--amount is the size of each order, in the input token
+    #[command(flatten)]
+    pub pair: PairArgs,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    /// Time between orders, like 30m, 4h or 1d
+    #[arg(long, value_parser = parse_duration)]
+    pub interval: Duration,
+
+    /// Total amount of the input token to spend across all orders (UI units)
+    #[arg(long)]
+    pub budget: String,
+
+    /// Skip an order if the quote prices the output token above this, in input tokens per
+    /// output token
+    #[arg(long)]
+    pub max_price: Option<f64>,
+
+    /// Skip an order if the quote's price impact is above this percent
+    #[arg(long)]
+    pub max_price_impact_pct: Option<f64>,
+
+    /// Where progress is kept, so a restarted `dca` picks up where it left off
+    #[arg(long, default_value = "dca-state.json")]
+    pub state: PathBuf,
+
+    /// Append every swap receipt to this trade journal
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+
+    /// Start without asking for confirmation
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LimitArgs {
+    //--amount is the whole order, in the input token
+    #[command(flatten)]
+    pub pair: PairArgs,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    /// Only swap while the quote gives at least this many output tokens per input token
+    #[arg(long)]
+    pub min_price: f64,
+
+    /// Fill the order in chunks of this much of the input token (UI units) instead of all at once
+    #[arg(long)]
+    pub chunk: Option<String>,
+
+    /// How often to ask for a new quote
+    #[arg(long, value_parser = parse_duration, default_value = "30s")]
+    pub poll_interval: Duration,
+
+    /// Give up on the order after this long, like 12h or 7d. Unfilled chunks are left unfilled.
+    #[arg(long, value_parser = parse_duration)]
+    pub expires_in: Option<Duration>,
+
+    /// Don't fill a chunk while the quote's price impact is above this percent
+    #[arg(long)]
+    pub max_price_impact_pct: Option<f64>,
+
+    /// Where progress is kept, so a restarted `limit` picks up where it left off
+    #[arg(long, default_value = "limit-state.json")]
+    pub state: PathBuf,
+
+    /// Append every swap receipt to this trade journal
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+
+    /// Start without asking for confirmation
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RebalanceArgs {
+    /// JSON file with the target allocations and limits
+    #[arg(long, default_value = "rebalance.json")]
+    pub config: PathBuf,
+
+    /// Max slippage in basis points for legs trading tokens without their own in the config
+    #[arg(long, default_value_t = 50)]
+    pub slippage_bps: u16,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    /// Abort a leg if its quote's price impact is above this percent
+    #[arg(long)]
+    pub max_price_impact_pct: Option<f64>,
+
+    /// Print the plan and stop
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Don't ask for confirmation before executing the plan
+    #[arg(long, short)]
+    pub yes: bool,
+
+    /// Append every swap receipt to this trade journal
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TwapArgs {
+    //--amount is the total, split evenly across the slices
+    #[command(flatten)]
+    pub pair: PairArgs,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    /// How many swaps to split the amount into
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub slices: u32,
+
+    /// How long to spread the slices over, like 30m or 4h
+    #[arg(long, value_parser = parse_duration)]
+    pub duration: Duration,
+
+    /// Move each slice up to this percent of the time between slices off its even spacing, at
+    /// random, so the swaps don't land on a predictable grid
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u8).range(0..=50))]
+    pub jitter_pct: u8,
+
+    /// Stop once the price impact of the slices added up would go above this percent
+    #[arg(long)]
+    pub max_cumulative_impact_pct: Option<f64>,
+
+    /// Stop once the average price would be more than this percent worse than the first slice's
+    /// quote
+    #[arg(long)]
+    pub max_price_degradation_pct: Option<f64>,
+
+    /// Append every swap receipt to this trade journal
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+
+    /// Start without asking for confirmation
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CompareArgs {
+    #[command(flatten)]
+    pub pair: PairArgs,
+
+    /// Try a variant capped at this many accounts. Can be repeated.
+    #[arg(long = "max-accounts", default_values_t = [32, 64])]
+    pub max_accounts: Vec<usize>,
+
+    /// Also try routing only through these DEXes, like `--dex Orca --dex Raydium`
+    #[arg(long = "dex")]
+    pub dexes: Vec<String>,
+
+    /// Also try routing around these DEXes. Can be repeated.
+    #[arg(long = "exclude-dex")]
+    pub excluded_dexes: Vec<String>,
+
+    /// Treat quotes within this many bps of the best as just as good, and recommend the one with
+    /// the smallest transaction among them
+    #[arg(long, default_value_t = 5)]
+    pub within_bps: u16,
+}
+
+/// Parses a duration written as a number and a unit: s, m, h or d
+pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("duration {} needs a unit: s, m, h or d", s))?;
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value.parse().map_err(|_| anyhow!("bad duration {}", s))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(anyhow!("bad duration unit {}, use s, m, h or d", unit)),
+    };
+    let secs = value
+        .checked_mul(unit_secs)
+        .ok_or_else(|| anyhow!("duration {} is too long", s))?;
+    if secs == 0 {
+        return Err(anyhow!("duration has to be longer than 0"));
+    }
+    Ok(Duration::from_secs(secs))
+}
@@ -0,0 +1,327 @@
+use crate::quote::price_impact_pct;
+use crate::swap::{build_swap_transaction, ResolvedPair, SwapConfig};
+use crate::tokens::{raw_to_ui, TokenRegistry};
+use anyhow::anyhow;
+use futures::future::join_all;
+use jupiter_swap_api_client::quote::{QuoteRequest, QuoteResponse};
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt::Write;
+
+/// One set of routing settings to ask Jupiter for a quote with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteVariant {
+    pub name: String,
+    pub only_direct_routes: bool,
+    pub restrict_intermediate_tokens: bool,
+    /// Only route through these DEXes, all of them if empty
+    pub dexes: Vec<String>,
+    pub excluded_dexes: Vec<String>,
+    pub max_accounts: Option<usize>,
+}
+
+impl RouteVariant {
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// The pair's quote request with this variant's routing settings on top. `QuoteRequest` has
+    /// no field for `restrictIntermediateTokens`, `quote_query` adds it.
+    pub fn quote_request(&self, pair: &ResolvedPair) -> QuoteRequest {
+        let list = |dexes: &Vec<String>| (!dexes.is_empty()).then(|| dexes.clone());
+        QuoteRequest {
+            only_direct_routes: self.only_direct_routes.then_some(true),
+            dexes: list(&self.dexes),
+            excluded_dexes: list(&self.excluded_dexes),
+            max_accounts: self.max_accounts,
+            ..pair.quote_request()
+        }
+    }
+
+    /// The `/quote` query string for this variant, encoded the way `JupiterSwapApiClient::quote`
+    /// does it
+    pub fn quote_query(&self, pair: &ResolvedPair) -> anyhow::Result<String> {
+        let mut query = serde_qs::to_string(&self.quote_request(pair))?;
+        if self.restrict_intermediate_tokens {
+            query.push_str("&restrictIntermediateTokens=true");
+        }
+        Ok(query)
+    }
+
+    /// The settings that differ from the default, for the table
+    pub fn describe(&self) -> String {
+        let mut settings = vec![];
+        if self.only_direct_routes {
+            settings.push("onlyDirectRoutes".to_string());
+        }
+        if self.restrict_intermediate_tokens {
+            settings.push("restrictIntermediateTokens".to_string());
+        }
+        if !self.dexes.is_empty() {
+            settings.push(format!("dexes={}", self.dexes.join(",")));
+        }
+        if !self.excluded_dexes.is_empty() {
+            settings.push(format!("excludeDexes={}", self.excluded_dexes.join(",")));
+        }
+        if let Some(max) = self.max_accounts {
+            settings.push(format!("maxAccounts={}", max));
+        }
+        if settings.is_empty() {
+            return "-".to_string();
+        }
+        settings.join(" ")
+    }
+}
+
+/// The variants `compare` tries: the defaults, direct routes only, restricted intermediate tokens,
+/// one per `max_accounts` and, when given, the DEX include and exclude lists
+pub fn default_variants(
+    max_accounts: &[usize],
+    dexes: &[String],
+    excluded_dexes: &[String],
+) -> Vec<RouteVariant> {
+    let mut variants = vec![
+        RouteVariant::named("default"),
+        RouteVariant {
+            only_direct_routes: true,
+            ..RouteVariant::named("direct")
+        },
+        RouteVariant {
+            restrict_intermediate_tokens: true,
+            ..RouteVariant::named("restricted")
+        },
+    ];
+    for max in max_accounts {
+        variants.push(RouteVariant {
+            max_accounts: Some(*max),
+            ..RouteVariant::named(&format!("max-accounts-{}", max))
+        });
+    }
+    if !dexes.is_empty() {
+        variants.push(RouteVariant {
+            dexes: dexes.to_vec(),
+            ..RouteVariant::named("only-dexes")
+        });
+    }
+    if !excluded_dexes.is_empty() {
+        variants.push(RouteVariant {
+            excluded_dexes: excluded_dexes.to_vec(),
+            ..RouteVariant::named("exclude-dexes")
+        });
+    }
+    variants
+}
+
+/// What one variant's quote came back with
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteStats {
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub hops: usize,
+    pub price_impact_pct: f64,
+    /// Accounts the swap transaction references, `None` when it wasn't built
+    pub accounts: Option<usize>,
+    /// The AMMs the route goes through, in order
+    pub amms: Vec<String>,
+}
+
+impl RouteStats {
+    pub fn from_quote(quote: &QuoteResponse, accounts: Option<usize>) -> anyhow::Result<Self> {
+        Ok(Self {
+            in_amount: quote.in_amount,
+            out_amount: quote.out_amount,
+            hops: quote.route_plan.len(),
+            price_impact_pct: price_impact_pct(quote)?,
+            accounts,
+            amms: quote
+                .route_plan
+                .iter()
+                .map(|step| step.swap_info.label.clone())
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub variant: RouteVariant,
+    /// The quote's stats, or why the variant got no quote
+    pub result: Result<RouteStats, String>,
+}
+
+/// Every account a message references, counting the ones it loads from lookup tables. This is
+/// what `max_accounts` limits, and what has to fit alongside anything else in the transaction.
+pub fn account_count(message: &VersionedMessage) -> usize {
+    match message {
+        VersionedMessage::Legacy(message) => message.account_keys.len(),
+        VersionedMessage::V0(message) => {
+            message.account_keys.len()
+                + message
+                    .address_table_lookups
+                    .iter()
+                    .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+                    .sum::<usize>()
+        }
+    }
+}
+
+/// Quotes every variant at the same time. With a `user`, each quote's swap transaction is built
+/// too so its accounts can be counted, nothing gets signed.
+pub async fn compare_routes(
+    jup: &JupiterSwapApiClient,
+    pair: &ResolvedPair,
+    variants: &[RouteVariant],
+    user: Option<Pubkey>,
+) -> Vec<Comparison> {
+    let quotes = variants.iter().map(|variant| async move {
+        let result = quote_variant(jup, pair, variant, user)
+            .await
+            .map_err(|e| format!("{:#}", e));
+        Comparison {
+            variant: variant.clone(),
+            result,
+        }
+    });
+    join_all(quotes).await
+}
+
+async fn quote_variant(
+    jup: &JupiterSwapApiClient,
+    pair: &ResolvedPair,
+    variant: &RouteVariant,
+    user: Option<Pubkey>,
+) -> anyhow::Result<RouteStats> {
+    let response = reqwest::Client::new()
+        .get(format!(
+            "{}/quote?{}",
+            jup.base_path,
+            variant.quote_query(pair)?
+        ))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "quote request failed with {}: {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        ));
+    }
+    let quote: QuoteResponse = response.json().await?;
+    let accounts = match user {
+        Some(user) => {
+            let tx =
+                build_swap_transaction(jup, user, quote.clone(), SwapConfig::default()).await?;
+            Some(account_count(&tx.message))
+        }
+        None => None,
+    };
+    RouteStats::from_quote(&quote, accounts)
+}
+
+/// Picks the best variant: the most out for exact in, the least in for exact out. Anything within
+/// `within_bps` of that counts as just as good, and of those the one with the fewest accounts,
+/// then hops, wins, since smaller transactions land more easily.
+pub fn recommend(comparisons: &[Comparison], exact_out: bool, within_bps: u16) -> Option<usize> {
+    let quoted: Vec<(usize, &RouteStats)> = comparisons
+        .iter()
+        .enumerate()
+        .filter_map(|(i, comparison)| comparison.result.as_ref().ok().map(|stats| (i, stats)))
+        .collect();
+
+    let margin = |amount: u64| (amount as u128 * within_bps as u128 / 10_000) as u64;
+    let good_enough: Vec<(usize, &RouteStats)> = if exact_out {
+        let best = quoted.iter().map(|(_, stats)| stats.in_amount).min()?;
+        quoted
+            .into_iter()
+            .filter(|(_, stats)| stats.in_amount <= best + margin(best))
+            .collect()
+    } else {
+        let best = quoted.iter().map(|(_, stats)| stats.out_amount).max()?;
+        quoted
+            .into_iter()
+            .filter(|(_, stats)| stats.out_amount >= best - margin(best))
+            .collect()
+    };
+
+    //min_by_key keeps the first of equals, so ties go to the earlier, simpler variant
+    good_enough
+        .into_iter()
+        .min_by_key(|(_, stats)| (stats.accounts.unwrap_or(0), stats.hops))
+        .map(|(i, _)| i)
+}
+
+/// The comparison table with the recommendation underneath
+pub fn render_comparison(
+    comparisons: &[Comparison],
+    recommended: Option<usize>,
+    pair: &ResolvedPair,
+    registry: &TokenRegistry,
+) -> String {
+    let mut out = String::new();
+    let amount_header = if pair.exact_out {
+        format!("in ({})", registry.symbol(&pair.input_mint))
+    } else {
+        format!("out ({})", registry.symbol(&pair.output_mint))
+    };
+
+    writeln!(
+        out,
+        "  {:<16} {:>20} {:>5} {:>10} {:>9}  route",
+        "variant", amount_header, "hops", "impact", "accounts"
+    )
+    .unwrap();
+    for (i, comparison) in comparisons.iter().enumerate() {
+        let marker = if Some(i) == recommended { "*" } else { " " };
+        match &comparison.result {
+            Ok(stats) => {
+                let amount = if pair.exact_out {
+                    raw_to_ui(stats.in_amount, pair.input_decimals)
+                } else {
+                    raw_to_ui(stats.out_amount, pair.output_decimals)
+                };
+                let accounts = stats
+                    .accounts
+                    .map_or_else(|| "-".to_string(), |accounts| accounts.to_string());
+                writeln!(
+                    out,
+                    "{} {:<16} {:>20} {:>5} {:>9.4}% {:>9}  {}",
+                    marker,
+                    comparison.variant.name,
+                    amount,
+                    stats.hops,
+                    stats.price_impact_pct,
+                    accounts,
+                    stats.amms.join(" -> ")
+                )
+                .unwrap();
+            }
+            Err(e) => {
+                writeln!(
+                    out,
+                    "{} {:<16} no quote: {}",
+                    marker, comparison.variant.name, e
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    match recommended {
+        Some(i) => {
+            let variant = &comparisons[i].variant;
+            writeln!(
+                out,
+                "recommended: {} ({})",
+                variant.name,
+                variant.describe()
+            )
+            .unwrap();
+        }
+        None => writeln!(out, "no variant got a quote").unwrap(),
+    }
+    out
+}
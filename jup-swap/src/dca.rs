@@ -0,0 +1,368 @@
+use crate::executor::{Executor, Fill};
+use crate::quote::{price_impact_pct, Guardrails};
+use crate::retry::{PendingSwap, Unsettled};
+use crate::schedule::{ensure_same_pair, load_state, save_state, Clock, MAX_CONSECUTIVE_FAILURES};
+use crate::swap::ResolvedPair;
+use crate::tokens::raw_to_ui;
+use anyhow::anyhow;
+use jupiter_swap_api_client::quote::QuoteResponse;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// What to buy, how much at a time and when not to
+#[derive(Debug, Clone)]
+pub struct DcaPlan {
+    /// Raw input amount of one order. The last order is smaller if that's all the budget has left.
+    pub order_amount: u64,
+    /// Raw input amount to spend across all orders
+    pub budget: u64,
+    pub interval: Duration,
+    /// Most one output token may cost, in input tokens (UI units)
+    pub max_price: Option<f64>,
+    pub max_price_impact_pct: Option<f64>,
+}
+
+impl DcaPlan {
+    /// Why the order shouldn't buy at this quote, `None` if it should
+    pub fn skip_reason(
+        &self,
+        quote: &QuoteResponse,
+        pair: &ResolvedPair,
+    ) -> anyhow::Result<Option<String>> {
+        if let Some(max) = self.max_price_impact_pct {
+            let impact = price_impact_pct(quote)?;
+            if impact > max {
+                return Ok(Some(format!(
+                    "price impact {:.4}% is above {}%",
+                    impact, max
+                )));
+            }
+        }
+
+        if let Some(max) = self.max_price {
+            let price = quoted_price(quote, pair);
+            if price > max {
+                return Ok(Some(format!("price {} is above {}", price, max)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// What the quote charges for one output token, in input tokens (UI units)
+pub fn quoted_price(quote: &QuoteResponse, pair: &ResolvedPair) -> f64 {
+    if quote.out_amount == 0 {
+        return f64::INFINITY;
+    }
+    let ui_in = quote.in_amount as f64 / 10f64.powi(pair.input_decimals as i32);
+    let ui_out = quote.out_amount as f64 / 10f64.powi(pair.output_decimals as i32);
+    ui_in / ui_out
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    Filled,
+    /// The quote broke `--max-price` or `--max-price-impact-pct`, nothing was sent
+    Skipped,
+    Failed,
+}
+
+/// One scheduled order and how it went
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DcaOrder {
+    /// Unix seconds the order was placed at
+    pub at: u64,
+    pub status: OrderStatus,
+    /// Raw amounts the swap moved, 0 unless it filled
+    pub in_amount: u64,
+    pub out_amount: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Why the order was skipped or failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl DcaOrder {
+    fn filled(&mut self, fill: &Fill) {
+        self.status = OrderStatus::Filled;
+        self.in_amount = fill.in_amount;
+        self.out_amount = fill.out_amount;
+        self.signature = Some(fill.signature.to_string());
+        self.reason = None;
+    }
+}
+
+/// An order whose swap was sent, saved before sending so a restart can find out how it went
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingOrder {
+    /// Unix seconds the order was placed at
+    pub at: u64,
+    #[serde(flatten)]
+    pub swap: PendingSwap,
+}
+
+/// Progress of a `dca` run, saved after every order so a restart picks up where it left off
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DcaState {
+    pub input_mint: String,
+    pub output_mint: String,
+    /// Raw input spent and output received by the filled orders
+    pub spent: u64,
+    pub received: u64,
+    /// Unix seconds the next order is due
+    pub next_order_at: u64,
+    pub orders: Vec<DcaOrder>,
+    /// Set while an order's swap is out, a run that stopped then settles it before anything else
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<PendingOrder>,
+}
+
+impl DcaState {
+    /// A fresh state with the first order due right away
+    pub fn new(pair: &ResolvedPair, now: u64) -> Self {
+        Self {
+            input_mint: pair.input_mint.to_string(),
+            output_mint: pair.output_mint.to_string(),
+            spent: 0,
+            received: 0,
+            next_order_at: now,
+            orders: vec![],
+            pending: None,
+        }
+    }
+
+    /// Loads the state at `path`, or starts a new one if there's none. A state for a different
+    /// pair is refused rather than overwritten.
+    pub fn load_or_new(path: &Path, pair: &ResolvedPair, now: u64) -> anyhow::Result<Self> {
+        let Some(state) = load_state::<Self>(path)? else {
+            return Ok(Self::new(pair, now));
+        };
+        ensure_same_pair(path, "dca", &state.input_mint, &state.output_mint, pair)?;
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        save_state(path, self)
+    }
+
+    pub fn remaining(&self, budget: u64) -> u64 {
+        budget.saturating_sub(self.spent)
+    }
+
+    pub fn consecutive_failures(&self) -> usize {
+        self.orders
+            .iter()
+            .rev()
+            .take_while(|order| order.status == OrderStatus::Failed)
+            .count()
+    }
+}
+
+/// Places the orders of a plan one interval apart, persisting progress to `state_path`
+pub struct Dca<'a> {
+    pub jup: &'a JupiterSwapApiClient,
+    pub executor: &'a dyn Executor,
+    pub clock: &'a dyn Clock,
+    /// The pair to buy. Its amount is replaced by each order's.
+    pub pair: ResolvedPair,
+    pub plan: DcaPlan,
+    pub state_path: PathBuf,
+}
+
+impl Dca<'_> {
+    /// Waits for the next order to come due, places it and saves the state. `None` once the
+    /// budget is spent. An order left pending by a run that stopped is settled first, in place
+    /// of a new one. If an order's swap was sent but whether it landed can't be found out, the
+    /// `Unsettled` error comes back and the order stays pending for the next run to settle.
+    pub async fn step(&self, state: &mut DcaState) -> anyhow::Result<Option<DcaOrder>> {
+        if let Some(pending) = state.pending.clone() {
+            let next_order_at = self.next_order_at(pending.at)?;
+            let order = self.settle(&pending).await?;
+            self.record(state, &order, next_order_at)?;
+            return Ok(Some(order));
+        }
+
+        let amount = self
+            .plan
+            .order_amount
+            .min(state.remaining(self.plan.budget));
+        if amount == 0 {
+            return Ok(None);
+        }
+
+        self.clock.sleep_until(state.next_order_at).await;
+        let now = self.clock.now();
+        //orders missed while we weren't running aren't made up, the next one is an interval out
+        let next_order_at = self.next_order_at(now)?;
+
+        let pair = ResolvedPair {
+            amount,
+            ..self.pair.clone()
+        };
+        let order = match self.place(&pair, now, state).await {
+            Ok(order) => order,
+            Err(e) => {
+                //the state file already has it pending, keep it that way here too
+                if let Some(unsettled) = e.downcast_ref::<Unsettled>() {
+                    state.pending = Some(PendingOrder {
+                        at: now,
+                        swap: unsettled.pending.clone(),
+                    });
+                }
+                return Err(e);
+            }
+        };
+        self.record(state, &order, next_order_at)?;
+
+        Ok(Some(order))
+    }
+
+    fn next_order_at(&self, placed_at: u64) -> anyhow::Result<u64> {
+        placed_at
+            .checked_add(self.plan.interval.as_secs())
+            .ok_or_else(|| anyhow!("--interval {:?} is too long", self.plan.interval))
+    }
+
+    fn record(
+        &self,
+        state: &mut DcaState,
+        order: &DcaOrder,
+        next_order_at: u64,
+    ) -> anyhow::Result<()> {
+        if order.status == OrderStatus::Filled {
+            state.spent += order.in_amount;
+            state.received += order.out_amount;
+        }
+        state.orders.push(order.clone());
+        state.pending = None;
+        state.next_order_at = next_order_at;
+        state.save(&self.state_path)
+    }
+
+    /// Finds out whether a pending order's swap landed
+    async fn settle(&self, pending: &PendingOrder) -> anyhow::Result<DcaOrder> {
+        let mut order = DcaOrder {
+            at: pending.at,
+            status: OrderStatus::Failed,
+            in_amount: 0,
+            out_amount: 0,
+            signature: Some(pending.swap.signature.clone()),
+            reason: None,
+        };
+        match self.executor.settle(&self.pair, &pending.swap).await? {
+            Some(fill) => order.filled(&fill),
+            None => order.reason = Some("the swap sent before the restart didn't land".to_string()),
+        }
+        Ok(order)
+    }
+
+    /// The order as placed, failed if the swap didn't land. Only an `Unsettled` swap is an error.
+    async fn place(
+        &self,
+        pair: &ResolvedPair,
+        now: u64,
+        state: &DcaState,
+    ) -> anyhow::Result<DcaOrder> {
+        let mut order = DcaOrder {
+            at: now,
+            status: OrderStatus::Failed,
+            in_amount: 0,
+            out_amount: 0,
+            signature: None,
+            reason: None,
+        };
+
+        let quote = match self.jup.quote(&pair.quote_request()).await {
+            Ok(quote) => quote,
+            Err(e) => {
+                order.reason = Some(format!("quote failed: {}", e));
+                return Ok(order);
+            }
+        };
+
+        match self.plan.skip_reason(&quote, pair) {
+            Ok(None) => {}
+            Ok(Some(reason)) => {
+                order.status = OrderStatus::Skipped;
+                order.reason = Some(reason);
+                return Ok(order);
+            }
+            Err(e) => {
+                order.reason = Some(format!("{:#}", e));
+                return Ok(order);
+            }
+        }
+
+        let guardrails = Guardrails {
+            max_price_impact_pct: self.plan.max_price_impact_pct,
+            min_out: None,
+        };
+        //a crash after sending leaves the swap in the state file, so it isn't lost or sent again
+        let save_pending = |swap: &PendingSwap| {
+            let mut saved = state.clone();
+            saved.pending = Some(PendingOrder {
+                at: now,
+                swap: swap.clone(),
+            });
+            saved.save(&self.state_path)
+        };
+        match self
+            .executor
+            .execute_tracked(pair, quote, &guardrails, &save_pending)
+            .await
+        {
+            Ok(fill) => order.filled(&fill),
+            Err(e) if e.is::<Unsettled>() => return Err(e),
+            Err(e) => order.reason = Some(format!("{:#}", e)),
+        }
+        Ok(order)
+    }
+
+    /// Places orders until the budget is spent, or fails after `MAX_CONSECUTIVE_FAILURES`
+    /// failed orders in a row. Everything done so far is in the state either way.
+    pub async fn run(&self, state: &mut DcaState) -> anyhow::Result<()> {
+        while let Some(order) = self.step(state).await? {
+            println!("{}", self.render_order(&order, state));
+
+            if state.consecutive_failures() >= MAX_CONSECUTIVE_FAILURES {
+                return Err(anyhow!(
+                    "{} orders in a row failed, stopping. Progress is saved in {}",
+                    MAX_CONSECUTIVE_FAILURES,
+                    self.state_path.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn render_order(&self, order: &DcaOrder, state: &DcaState) -> String {
+        let progress = format!(
+            "{} of {} spent",
+            raw_to_ui(state.spent, self.pair.input_decimals),
+            raw_to_ui(self.plan.budget, self.pair.input_decimals)
+        );
+        match order.status {
+            OrderStatus::Filled => format!(
+                "order filled: {} -> {} ({})",
+                raw_to_ui(order.in_amount, self.pair.input_decimals),
+                raw_to_ui(order.out_amount, self.pair.output_decimals),
+                progress
+            ),
+            OrderStatus::Skipped | OrderStatus::Failed => format!(
+                "order {}: {} ({})",
+                if order.status == OrderStatus::Skipped {
+                    "skipped"
+                } else {
+                    "failed"
+                },
+                order.reason.as_deref().unwrap_or("no reason"),
+                progress
+            ),
+        }
+    }
+}
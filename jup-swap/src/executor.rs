@@ -0,0 +1,259 @@
+use crate::cli::{RetryArgs, TxArgs};
+use crate::jito::{bundle_fee_lamports, BundleSubmitter};
+use crate::priority::{
+    compute_unit_price, priority_fee_lamports, FeeCapCheck, PriorityFee, DEFAULT_SWAP_COMPUTE_UNITS,
+};
+use crate::quote::Guardrails;
+use crate::receipt::{append_journal, fetch_balances, Receipt};
+use crate::retry::{
+    swap_with_retries, wait_until_final, BeforeSend, Landing, PendingSwap, RetryPolicy,
+};
+use crate::simulate::SimulationCheck;
+use crate::submit::Submitter;
+use crate::swap::{transaction_config, ResolvedPair, SwapCheck};
+use crate::tokens::TokenRegistry;
+use crate::verify::{Allowlist, VerifyCheck};
+use async_trait::async_trait;
+use jupiter_swap_api_client::quote::QuoteResponse;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The checks every swap goes through before it's signed: verification, the fee cap when
+/// `--max-fee-lamports` is set, and simulation
+pub struct StandardChecks<'a> {
+    pub verify: VerifyCheck<'a>,
+    pub fee_cap: Option<FeeCapCheck>,
+    pub simulation: SimulationCheck<'a>,
+}
+
+impl<'a> StandardChecks<'a> {
+    pub fn new(rpc: &'a RpcClient, tx: &TxArgs) -> anyhow::Result<Self> {
+        let mut allowlist = Allowlist::default();
+        for program in &tx.allow_programs {
+            allowlist.allow(Pubkey::from_str(program)?);
+        }
+        let config = transaction_config(tx, None)?;
+
+        Ok(Self {
+            verify: VerifyCheck { rpc, allowlist },
+            fee_cap: FeeCapCheck::new(tx.max_fee_lamports, bundle_fee_lamports(tx))?,
+            simulation: SimulationCheck {
+                rpc,
+                wrap_and_unwrap_sol: config.transaction.wrap_and_unwrap_sol,
+                destination_token_account: config.transaction.destination_token_account,
+            },
+        })
+    }
+
+    /// In the order they run, cheapest first
+    pub fn list(&self) -> Vec<&dyn SwapCheck> {
+        let mut checks: Vec<&dyn SwapCheck> = vec![&self.verify];
+        if let Some(fee_cap) = &self.fee_cap {
+            checks.push(fee_cap);
+        }
+        checks.push(&self.simulation);
+        checks
+    }
+}
+
+/// A swap that landed and what it moved. The amounts come from the receipt, or from the quote if
+/// the receipt couldn't be built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub signature: Signature,
+    pub in_amount: u64,
+    pub out_amount: u64,
+}
+
+/// Takes a quote all the way to a landed swap. Modes that trade on their own, like `dca`, go
+/// through this so tests can swap in a fake.
+#[async_trait]
+pub trait Executor: Send + Sync {
+    /// Swaps `quote` for `pair`. Retries requote, and every new quote has to pass `guardrails`.
+    async fn execute(
+        &self,
+        pair: &ResolvedPair,
+        quote: QuoteResponse,
+        guardrails: &Guardrails,
+    ) -> anyhow::Result<Fill> {
+        self.execute_tracked(pair, quote, guardrails, &|_| Ok(()))
+            .await
+    }
+
+    /// `execute`, handing every attempt to `before_send` before it goes out
+    async fn execute_tracked(
+        &self,
+        pair: &ResolvedPair,
+        quote: QuoteResponse,
+        guardrails: &Guardrails,
+        before_send: &BeforeSend<'_>,
+    ) -> anyhow::Result<Fill>;
+
+    /// Waits until a swap that was sent but never reported back on can't land anymore. Its fill
+    /// if it landed, `None` if it didn't.
+    async fn settle(
+        &self,
+        pair: &ResolvedPair,
+        pending: &PendingSwap,
+    ) -> anyhow::Result<Option<Fill>>;
+}
+
+/// The real thing: priority fee, checks, retries, then the receipt and journal
+pub struct SwapExecutor<'a> {
+    pub rpc: &'a RpcClient,
+    /// Set with `--jito-url`, otherwise swaps go straight to `rpc`
+    pub bundles: Option<BundleSubmitter<'a>>,
+    pub jup: &'a JupiterSwapApiClient,
+    pub wallet: &'a Keypair,
+    pub registry: &'a TokenRegistry,
+    pub tx: TxArgs,
+    pub retry: RetryArgs,
+    pub checks: StandardChecks<'a>,
+    pub journal: Option<PathBuf>,
+}
+
+impl<'a> SwapExecutor<'a> {
+    /// The standard checks and, with `--jito-url`, bundles, all set up from the command's args
+    pub fn new(
+        rpc: &'a RpcClient,
+        jup: &'a JupiterSwapApiClient,
+        wallet: &'a Keypair,
+        registry: &'a TokenRegistry,
+        tx: &TxArgs,
+        retry: &RetryArgs,
+        journal: Option<PathBuf>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            rpc,
+            bundles: BundleSubmitter::from_args(wallet, tx, rpc)?,
+            jup,
+            wallet,
+            registry,
+            tx: tx.clone(),
+            retry: retry.clone(),
+            checks: StandardChecks::new(rpc, tx)?,
+            journal,
+        })
+    }
+
+    fn submitter(&self) -> &dyn Submitter {
+        match &self.bundles {
+            Some(bundles) => bundles,
+            None => self.rpc,
+        }
+    }
+
+    /// Builds the receipt of a swap that landed and adds it to the journal. The fill keeps the
+    /// quoted amounts if the receipt can't be built.
+    async fn landed(&self, sig: Signature, pair: &ResolvedPair, quoted: (u64, u64)) -> Fill {
+        println!("tx successful with signature: {:?}", sig);
+
+        let mut fill = Fill {
+            signature: sig,
+            in_amount: quoted.0,
+            out_amount: quoted.1,
+        };
+
+        //the swap has landed at this point, so a failed receipt is only a warning
+        match fetch_balances(self.rpc, &sig).await {
+            Ok(balances) => {
+                let receipt = Receipt::compute_quoted(
+                    sig,
+                    &balances,
+                    &self.wallet.pubkey(),
+                    pair,
+                    quoted,
+                    self.tx.wrap_unwrap_sol,
+                );
+                println!("{}", receipt.render(self.registry));
+                fill.in_amount = receipt.in_amount;
+                fill.out_amount = receipt.out_amount;
+
+                if let Some(journal) = &self.journal {
+                    if let Err(e) = append_journal(journal, &receipt) {
+                        println!("couldn't write the trade journal: {:#}", e);
+                    }
+                }
+            }
+            Err(e) => println!("couldn't build the receipt: {:#}", e),
+        }
+
+        fill
+    }
+}
+
+#[async_trait]
+impl Executor for SwapExecutor<'_> {
+    async fn execute_tracked(
+        &self,
+        pair: &ResolvedPair,
+        quote: QuoteResponse,
+        guardrails: &Guardrails,
+        before_send: &BeforeSend<'_>,
+    ) -> anyhow::Result<Fill> {
+        let price = match &self.tx.priority_fee {
+            Some(fee) => {
+                let price = compute_unit_price(
+                    self.rpc,
+                    fee.parse::<PriorityFee>()?,
+                    &quote,
+                    DEFAULT_SWAP_COMPUTE_UNITS,
+                    //the bundle's share of the cap isn't left for the priority fee
+                    self.tx
+                        .max_fee_lamports
+                        .map(|max| max.saturating_sub(bundle_fee_lamports(&self.tx))),
+                )
+                .await?;
+                println!(
+                    "priority fee: {} micro lamports per compute unit, up to {} lamports",
+                    price,
+                    priority_fee_lamports(price, DEFAULT_SWAP_COMPUTE_UNITS)
+                );
+                Some(price)
+            }
+            None => None,
+        };
+
+        let outcome = swap_with_retries(
+            self.submitter(),
+            self.jup,
+            self.wallet,
+            pair,
+            quote,
+            guardrails,
+            &|| transaction_config(&self.tx, price),
+            &self.checks.list(),
+            &RetryPolicy::from_args(&self.retry, pair.slippage_bps),
+            before_send,
+        )
+        .await?;
+
+        let quoted = (outcome.quote.in_amount, outcome.quote.out_amount);
+        Ok(self.landed(outcome.signature, pair, quoted).await)
+    }
+
+    async fn settle(
+        &self,
+        pair: &ResolvedPair,
+        pending: &PendingSwap,
+    ) -> anyhow::Result<Option<Fill>> {
+        let sig = Signature::from_str(&pending.signature)?;
+        let blockhash = Hash::from_str(&pending.blockhash)?;
+        println!("waiting to see if tx {} landed ...", sig);
+
+        let poll_interval = RetryPolicy::default().poll_interval;
+        match wait_until_final(self.submitter(), &sig, &blockhash, poll_interval).await? {
+            Landing::Landed => {
+                let quoted = (pending.quoted_in_amount, pending.quoted_out_amount);
+                Ok(Some(self.landed(sig, pair, quoted).await))
+            }
+            Landing::Failed(_) | Landing::Expired => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::cli::TxArgs;
+use crate::receipt::LAMPORTS_PER_SIGNATURE;
+use crate::submit::Submitter;
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{self, VersionedTransaction};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// One of the block engine's tip accounts. Any of them works, they're only spread out to avoid
+/// write lock contention.
+pub const DEFAULT_TIP_ACCOUNT: Pubkey = pubkey!("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5");
+
+/// What sending a swap as a bundle costs on top of the swap's own fee: the tip, and the base fee
+/// of the tip transaction. Nothing without `--jito-url`. If the bundle doesn't land, neither is
+/// paid.
+pub fn bundle_fee_lamports(tx: &TxArgs) -> u64 {
+    match tx.jito_url {
+        Some(_) => tx.jito_tip_lamports + LAMPORTS_PER_SIGNATURE,
+        None => 0,
+    }
+}
+
+/// What the block engine says about a bundle it accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleStatus {
+    Pending,
+    Landed,
+    /// Dropped by the block engine. Nothing in it landed.
+    Failed,
+}
+
+/// Sends the signed swap as a bundle together with a tip to the block engine, so it can't be
+/// sandwiched on the way in. If the block engine won't take the bundle or it doesn't land in
+/// time, the swap goes out through `fallback` instead.
+pub struct BundleSubmitter<'a> {
+    pub http: reqwest::Client,
+    /// Block engine base url, like https://mainnet.block-engine.jito.wtf
+    pub url: String,
+    /// Pays the tip. Has to be the wallet, the tip transaction is signed on the spot.
+    pub payer: &'a Keypair,
+    pub tip_account: Pubkey,
+    pub tip_lamports: u64,
+    /// How long to wait for the bundle to land before falling back
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+    /// Used for the fallback, and for everything the block engine can't answer: signature
+    /// statuses and blockhash validity
+    pub fallback: &'a dyn Submitter,
+}
+
+impl<'a> BundleSubmitter<'a> {
+    /// With `--jito-url`, a submitter that sends swaps as bundles paid from `wallet` and falls
+    /// back to `fallback`
+    pub fn from_args(
+        wallet: &'a Keypair,
+        tx: &TxArgs,
+        fallback: &'a dyn Submitter,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(url) = &tx.jito_url else {
+            return Ok(None);
+        };
+        let tip_account = tx
+            .jito_tip_account
+            .as_deref()
+            .map(Pubkey::from_str)
+            .transpose()
+            .map_err(|e| anyhow!("bad --jito-tip-account: {}", e))?
+            .unwrap_or(DEFAULT_TIP_ACCOUNT);
+
+        Ok(Some(Self {
+            http: reqwest::Client::new(),
+            url: url.clone(),
+            payer: wallet,
+            tip_account,
+            tip_lamports: tx.jito_tip_lamports,
+            timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_secs(2),
+            fallback,
+        }))
+    }
+
+    /// The tip transfer that rides along with the swap. It uses the swap's blockhash, so the
+    /// bundle expires as a whole.
+    pub fn tip_transaction(&self, blockhash: Hash) -> anyhow::Result<VersionedTransaction> {
+        let ix = system_instruction::transfer(
+            &self.payer.pubkey(),
+            &self.tip_account,
+            self.tip_lamports,
+        );
+        let message = v0::Message::try_compile(&self.payer.pubkey(), &[ix], &[], blockhash)?;
+        Ok(VersionedTransaction::try_new(
+            VersionedMessage::V0(message),
+            &[self.payer],
+        )?)
+    }
+
+    /// Posts the bundle and returns its id. The tip goes last, so it's only paid if the swap
+    /// lands: a bundle lands whole or not at all.
+    pub async fn send_bundle(&self, txs: &[&VersionedTransaction]) -> anyhow::Result<String> {
+        let mut encoded = vec![];
+        for tx in txs {
+            encoded.push(base64::engine::general_purpose::STANDARD.encode(bincode::serialize(tx)?));
+        }
+        let result = self
+            .call("sendBundle", json!([encoded, { "encoding": "base64" }]))
+            .await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("sendBundle returned {} instead of a bundle id", result))
+    }
+
+    pub async fn bundle_status(&self, bundle_id: &str) -> anyhow::Result<BundleStatus> {
+        let result = self
+            .call("getInflightBundleStatuses", json!([[bundle_id]]))
+            .await?;
+        let statuses: InflightStatuses =
+            serde_json::from_value(result).context("bad getInflightBundleStatuses response")?;
+        //"Invalid" means the block engine doesn't know the id, which is also what it says right
+        //after sendBundle before it has indexed the bundle. Falling back then would send the swap
+        //through the public rpc while the bundle may still land, so it's pending until the
+        //timeout like any other unknown status.
+        Ok(match statuses.value.first().map(|s| s.status.as_str()) {
+            Some("Landed") => BundleStatus::Landed,
+            Some("Failed") => BundleStatus::Failed,
+            _ => BundleStatus::Pending,
+        })
+    }
+
+    /// Polls until the bundle lands, fails or runs out of time, which counts as failed
+    pub async fn wait_for_bundle(&self, bundle_id: &str) -> anyhow::Result<BundleStatus> {
+        let started = Instant::now();
+        loop {
+            let status = self.bundle_status(bundle_id).await?;
+            if status != BundleStatus::Pending {
+                return Ok(status);
+            }
+            if started.elapsed() >= self.timeout {
+                return Ok(BundleStatus::Failed);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Sends the bundle and waits for it. `Ok(false)` if it didn't land.
+    async fn submit_bundle(&self, tx: &VersionedTransaction) -> anyhow::Result<bool> {
+        let tip = self.tip_transaction(*tx.message.recent_blockhash())?;
+        let bundle_id = self.send_bundle(&[tx, &tip]).await?;
+        println!(
+            "sent bundle {} with a {} lamport tip",
+            bundle_id, self.tip_lamports
+        );
+        Ok(self.wait_for_bundle(&bundle_id).await? == BundleStatus::Landed)
+    }
+
+    async fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let url = format!("{}/api/v1/bundles", self.url.trim_end_matches('/'));
+        let response: RpcResponse = self
+            .http
+            .post(&url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .with_context(|| format!("{} to {}", method, url))?
+            .error_for_status()
+            .with_context(|| format!("{} to {}", method, url))?
+            .json()
+            .await
+            .with_context(|| format!("bad {} response", method))?;
+
+        if let Some(error) = response.error {
+            return Err(anyhow!("{} rejected: {}", method, error));
+        }
+        response
+            .result
+            .ok_or_else(|| anyhow!("{} returned no result", method))
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct InflightStatuses {
+    value: Vec<InflightStatus>,
+}
+
+#[derive(Deserialize)]
+struct InflightStatus {
+    status: String,
+}
+
+#[async_trait]
+impl Submitter for BundleSubmitter<'_> {
+    async fn submit(&self, tx: &VersionedTransaction) -> anyhow::Result<Signature> {
+        match self.submit_bundle(tx).await {
+            Ok(true) => return Ok(tx.signatures[0]),
+            Ok(false) => println!("bundle didn't land, sending through rpc instead"),
+            Err(e) => println!("bundle failed ({:#}), sending through rpc instead", e),
+        }
+        //the bundle and this carry the same signed swap, which can only land once. If a late
+        //bundle wins, this send fails and the retry loop finds the swap landed.
+        self.fallback.submit(tx).await
+    }
+
+    async fn signature_status(
+        &self,
+        signature: &Signature,
+    ) -> anyhow::Result<Option<transaction::Result<()>>> {
+        self.fallback.signature_status(signature).await
+    }
+
+    async fn is_blockhash_valid(&self, blockhash: &Hash) -> anyhow::Result<bool> {
+        self.fallback.is_blockhash_valid(blockhash).await
+    }
+}
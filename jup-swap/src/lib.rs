@@ -0,0 +1,20 @@
+pub mod accounts;
+pub mod cli;
+pub mod compare;
+pub mod dca;
+pub mod executor;
+pub mod jito;
+pub mod limit;
+pub mod message;
+pub mod priority;
+pub mod quote;
+pub mod rebalance;
+pub mod receipt;
+pub mod retry;
+pub mod schedule;
+pub mod simulate;
+pub mod submit;
+pub mod swap;
+pub mod tokens;
+pub mod twap;
+pub mod verify;
@@ -0,0 +1,343 @@
+use crate::executor::Executor;
+use crate::quote::{min_out, Guardrails};
+use crate::retry::{PendingSwap, Unsettled};
+use crate::schedule::{ensure_same_pair, load_state, save_state, Clock, MAX_CONSECUTIVE_FAILURES};
+use crate::swap::ResolvedPair;
+use crate::tokens::raw_to_ui;
+use jupiter_swap_api_client::quote::QuoteResponse;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// When the order fires and how it's sliced
+#[derive(Debug, Clone)]
+pub struct LimitPlan {
+    /// Raw input amount to sell in total
+    pub amount: u64,
+    /// Raw input amount of one chunk. Each chunk is quoted and filled on its own, so a big order
+    /// can fill bit by bit as the price allows.
+    pub chunk: u64,
+    /// Least the quote has to give for one input token, in output tokens (UI units)
+    pub min_price: f64,
+    pub poll_interval: Duration,
+    pub max_price_impact_pct: Option<f64>,
+}
+
+impl LimitPlan {
+    /// Least raw output `amount` of raw input may be sold for at `min_price`, rounded up so a
+    /// fill at exactly this is never below the limit
+    pub fn min_out(&self, amount: u64, pair: &ResolvedPair) -> u64 {
+        let ui_in = amount as f64 / 10f64.powi(pair.input_decimals as i32);
+        let exact = self.min_price * ui_in * 10f64.powi(pair.output_decimals as i32);
+        //don't let float noise like 120000000.00000001 round up a whole unit
+        let rounded = exact.round();
+        if (exact - rounded).abs() < 1e-6 {
+            rounded as u64
+        } else {
+            exact.ceil() as u64
+        }
+    }
+}
+
+/// What the quote gives for one input token, in output tokens (UI units)
+pub fn quoted_price(quote: &QuoteResponse, pair: &ResolvedPair) -> f64 {
+    if quote.in_amount == 0 {
+        return 0.0;
+    }
+    let ui_in = quote.in_amount as f64 / 10f64.powi(pair.input_decimals as i32);
+    let ui_out = quote.out_amount as f64 / 10f64.powi(pair.output_decimals as i32);
+    ui_out / ui_in
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitStatus {
+    Open,
+    Filled,
+    /// Ran out of time, possibly with some chunks filled
+    Expired,
+}
+
+/// One chunk that filled
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LimitFill {
+    /// Unix seconds
+    pub at: u64,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub signature: String,
+}
+
+/// A chunk whose swap was sent, saved before sending so a restart can find out how it went
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingChunk {
+    /// Unix seconds the chunk was sent at
+    pub at: u64,
+    #[serde(flatten)]
+    pub swap: PendingSwap,
+}
+
+/// Progress of a limit order, saved after every change so a restart picks up where it left off
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LimitState {
+    pub input_mint: String,
+    pub output_mint: String,
+    pub status: LimitStatus,
+    /// Unix seconds the order stops waiting for its price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// Raw input sold and output received by the filled chunks
+    pub filled: u64,
+    pub received: u64,
+    pub fills: Vec<LimitFill>,
+    /// Set while a chunk's swap is out, a run that stopped then settles it before quoting again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<PendingChunk>,
+}
+
+impl LimitState {
+    pub fn new(pair: &ResolvedPair, expires_at: Option<u64>) -> Self {
+        Self {
+            input_mint: pair.input_mint.to_string(),
+            output_mint: pair.output_mint.to_string(),
+            status: LimitStatus::Open,
+            expires_at,
+            filled: 0,
+            received: 0,
+            fills: vec![],
+            pending: None,
+        }
+    }
+
+    /// Loads the state at `path`, or opens a new order if there's none. The expiry of an order
+    /// that's already open is kept, a restart doesn't extend it.
+    pub fn load_or_new(
+        path: &Path,
+        pair: &ResolvedPair,
+        expires_at: Option<u64>,
+    ) -> anyhow::Result<Self> {
+        let Some(state) = load_state::<Self>(path)? else {
+            return Ok(Self::new(pair, expires_at));
+        };
+        ensure_same_pair(
+            path,
+            "limit order",
+            &state.input_mint,
+            &state.output_mint,
+            pair,
+        )?;
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        save_state(path, self)
+    }
+
+    pub fn remaining(&self, amount: u64) -> u64 {
+        amount.saturating_sub(self.filled)
+    }
+}
+
+/// What one poll of the order did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Poll {
+    /// The quote isn't good enough yet, and why
+    Waiting(String),
+    Filled(LimitFill),
+    /// The whole amount has filled, nothing left to do
+    Done,
+    Expired,
+}
+
+/// Polls quotes for a limit order and fills it chunk by chunk whenever the price is there
+pub struct LimitOrder<'a> {
+    pub jup: &'a JupiterSwapApiClient,
+    pub executor: &'a dyn Executor,
+    pub clock: &'a dyn Clock,
+    /// The pair to sell. Its amount is replaced by each chunk's.
+    pub pair: ResolvedPair,
+    pub plan: LimitPlan,
+    pub state_path: PathBuf,
+}
+
+impl LimitOrder<'_> {
+    /// Quotes the next chunk and fills it if the price is at the limit or better. The state is
+    /// saved whenever it changes. A chunk left pending by a run that stopped, or by a poll that
+    /// couldn't find out whether its swap landed, is settled first, and if it landed that's the
+    /// fill of this poll.
+    pub async fn poll(&self, state: &mut LimitState) -> anyhow::Result<Poll> {
+        match state.status {
+            LimitStatus::Filled => return Ok(Poll::Done),
+            LimitStatus::Expired => return Ok(Poll::Expired),
+            LimitStatus::Open => {}
+        }
+
+        if let Some(pending) = state.pending.clone() {
+            match self.executor.settle(&self.pair, &pending.swap).await? {
+                Some(fill) => {
+                    let fill = LimitFill {
+                        at: pending.at,
+                        in_amount: fill.in_amount,
+                        out_amount: fill.out_amount,
+                        signature: fill.signature.to_string(),
+                    };
+                    self.record(state, &fill)?;
+                    return Ok(Poll::Filled(fill));
+                }
+                None => {
+                    println!("the chunk sent before the restart didn't land");
+                    state.pending = None;
+                    state.save(&self.state_path)?;
+                }
+            }
+        }
+
+        let now = self.clock.now();
+        if state.expires_at.is_some_and(|expires_at| now >= expires_at) {
+            state.status = LimitStatus::Expired;
+            state.save(&self.state_path)?;
+            return Ok(Poll::Expired);
+        }
+
+        let amount = self.plan.chunk.min(state.remaining(self.plan.amount));
+        if amount == 0 {
+            state.status = LimitStatus::Filled;
+            state.save(&self.state_path)?;
+            return Ok(Poll::Done);
+        }
+
+        let pair = ResolvedPair {
+            amount,
+            ..self.pair.clone()
+        };
+        let quote = self.jup.quote(&pair.quote_request()).await?;
+        let price = quoted_price(&quote, &pair);
+        if price < self.plan.min_price {
+            return Ok(Poll::Waiting(format!(
+                "price {} is below {}",
+                price, self.plan.min_price
+            )));
+        }
+
+        //the quoted price can be over the limit while the worst case after slippage is under it,
+        //so the swap is held to the limit too, requotes included
+        let limit_out = self.plan.min_out(amount, &pair);
+        let worst_case = min_out(&quote, &pair);
+        if worst_case < limit_out {
+            return Ok(Poll::Waiting(format!(
+                "minimum out {} after slippage is below the {} the limit needs",
+                raw_to_ui(worst_case, pair.output_decimals),
+                raw_to_ui(limit_out, pair.output_decimals)
+            )));
+        }
+        let guardrails = Guardrails {
+            max_price_impact_pct: self.plan.max_price_impact_pct,
+            min_out: Some(limit_out),
+        };
+        if let Err(e) = guardrails.check(&quote, &pair) {
+            return Ok(Poll::Waiting(e.to_string()));
+        }
+        //a crash after sending leaves the swap in the state file, so the chunk isn't sold twice
+        let save_pending = |swap: &PendingSwap| {
+            let mut saved = state.clone();
+            saved.pending = Some(PendingChunk {
+                at: now,
+                swap: swap.clone(),
+            });
+            saved.save(&self.state_path)
+        };
+        let fill = self
+            .executor
+            .execute_tracked(&pair, quote, &guardrails, &save_pending)
+            .await;
+        let fill = match fill {
+            Ok(fill) => fill,
+            Err(e) => {
+                match e.downcast_ref::<Unsettled>() {
+                    //it may still land, so it stays pending and the next poll settles it
+                    Some(unsettled) => {
+                        state.pending = Some(PendingChunk {
+                            at: now,
+                            swap: unsettled.pending.clone(),
+                        })
+                    }
+                    //the swap didn't land, so the saved pending chunk is stale
+                    None => state.save(&self.state_path)?,
+                }
+                return Err(e);
+            }
+        };
+
+        let fill = LimitFill {
+            at: now,
+            in_amount: fill.in_amount,
+            out_amount: fill.out_amount,
+            signature: fill.signature.to_string(),
+        };
+        self.record(state, &fill)?;
+
+        Ok(Poll::Filled(fill))
+    }
+
+    fn record(&self, state: &mut LimitState, fill: &LimitFill) -> anyhow::Result<()> {
+        state.filled += fill.in_amount;
+        state.received += fill.out_amount;
+        state.fills.push(fill.clone());
+        state.pending = None;
+        if state.remaining(self.plan.amount) == 0 {
+            state.status = LimitStatus::Filled;
+        }
+        state.save(&self.state_path)
+    }
+
+    /// Polls until the order has filled or expired, which is what it returns. Fails after
+    /// `MAX_CONSECUTIVE_FAILURES` failed polls in a row.
+    pub async fn run(&self, state: &mut LimitState) -> anyhow::Result<LimitStatus> {
+        let mut failures = 0;
+        loop {
+            match self.poll(state).await {
+                Ok(poll) => {
+                    failures = 0;
+                    println!("{}", self.render_poll(&poll, state));
+                    if state.status != LimitStatus::Open {
+                        return Ok(state.status);
+                    }
+                }
+                Err(e) => {
+                    failures += 1;
+                    println!("limit order poll failed: {:#}", e);
+                    if failures >= MAX_CONSECUTIVE_FAILURES {
+                        return Err(e.context(format!(
+                            "{} polls in a row failed, stopping. Progress is saved in {}",
+                            MAX_CONSECUTIVE_FAILURES,
+                            self.state_path.display()
+                        )));
+                    }
+                }
+            }
+
+            let next = self.clock.now() + self.plan.poll_interval.as_secs();
+            self.clock.sleep_until(next).await;
+        }
+    }
+
+    pub fn render_poll(&self, poll: &Poll, state: &LimitState) -> String {
+        let progress = format!(
+            "{} of {} filled",
+            raw_to_ui(state.filled, self.pair.input_decimals),
+            raw_to_ui(self.plan.amount, self.pair.input_decimals)
+        );
+        match poll {
+            Poll::Waiting(reason) => format!("{}, waiting ({})", reason, progress),
+            Poll::Filled(fill) => format!(
+                "chunk filled: {} -> {} ({})",
+                raw_to_ui(fill.in_amount, self.pair.input_decimals),
+                raw_to_ui(fill.out_amount, self.pair.output_decimals),
+                progress
+            ),
+            Poll::Done => format!("order filled ({})", progress),
+            Poll::Expired => format!("order expired ({})", progress),
+        }
+    }
+}
@@ -1,60 +1,442 @@
+use anyhow::anyhow;
+use clap::Parser;
 use dotenv::dotenv;
-use jupiter_swap_api_client::quote::QuoteRequest;
-use jupiter_swap_api_client::swap::SwapRequest;
-use jupiter_swap_api_client::transaction_config::TransactionConfig;
+use jup_swap::accounts::{
+    ata_preflight, create_missing_atas, render_balances, token_holdings, MissingAccount,
+};
+use jup_swap::cli::{Cli, Command, TxArgs};
+use jup_swap::compare::{compare_routes, default_variants, recommend, render_comparison};
+use jup_swap::dca::{Dca, DcaPlan, DcaState};
+use jup_swap::executor::{Executor, SwapExecutor};
+use jup_swap::limit::{LimitOrder, LimitPlan, LimitState};
+use jup_swap::quote::{confirm, render_quote, Guardrails};
+use jup_swap::rebalance::{
+    execute_plan, plan_rebalance, render_plan, value_portfolio, RebalanceConfig,
+};
+use jup_swap::schedule::{Clock, SystemClock};
+use jup_swap::swap::{transaction_config, ResolvedPair};
+use jup_swap::tokens::{raw_to_ui, ui_to_raw, TokenRegistry};
+use jup_swap::twap::{time_seeded_random, Twap, TwapPlan};
 use jupiter_swap_api_client::JupiterSwapApiClient;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
-use solana_sdk::pubkey;
-use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::VersionedTransaction;
 use std::env;
-use tokio;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
+    let cli = Cli::parse();
+
     let rpc_url = env::var("RPC_URL").expect("No RPC_URL provided");
     let api_base_url = env::var("API_BASE_URL").expect("NO API_BASE_URL");
-    let pk_base58 = env::var("PRIVATE_KEY").expect("No PRIVATE_KEY provided");
     let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
     let jup_swap_client = JupiterSwapApiClient::new(api_base_url);
+    let registry = TokenRegistry::load(&cli.tokens)?;
+
+    match cli.command {
+        Command::Quote(args) => {
+            let pair = ResolvedPair::resolve(&args, &registry, &rpc_client).await?;
+            let quote_response = jup_swap_client.quote(&pair.quote_request()).await?;
+            println!("{}", render_quote(&quote_response, &pair, &registry));
+        }
+        Command::Compare(args) => {
+            let pair = ResolvedPair::resolve(&args.pair, &registry, &rpc_client).await?;
+            let variants = default_variants(&args.max_accounts, &args.dexes, &args.excluded_dexes);
+            //counting accounts means building the swap, which needs a wallet to build it for
+            let user = env::var("PRIVATE_KEY").ok().map(|_| load_wallet().pubkey());
+
+            let comparisons = compare_routes(&jup_swap_client, &pair, &variants, user).await;
+            let recommended = recommend(&comparisons, pair.exact_out, args.within_bps);
+            print!(
+                "{}",
+                render_comparison(&comparisons, recommended, &pair, &registry)
+            );
+        }
+        Command::Swap(args) => {
+            let wallet = load_wallet();
+            println!("Wallet: {}", wallet.pubkey());
+
+            let pair = ResolvedPair::resolve(&args.pair, &registry, &rpc_client).await?;
+            let quote_response = jup_swap_client.quote(&pair.quote_request()).await?;
+            println!("{}", render_quote(&quote_response, &pair, &registry));
+
+            let guardrails = Guardrails {
+                max_price_impact_pct: args.max_price_impact_pct,
+                min_out: args
+                    .min_out
+                    .as_deref()
+                    .map(|min_out| ui_to_raw(min_out, pair.output_decimals))
+                    .transpose()?,
+            };
+            guardrails.check(&quote_response, &pair)?;
+
+            if !confirm_and_create_atas(
+                "Sign and send this swap?",
+                &rpc_client,
+                &wallet,
+                &pair,
+                &args.tx,
+                args.yes,
+            )
+            .await?
+            {
+                println!("swap cancelled, nothing was signed");
+                return Ok(());
+            }
+
+            println!("getting swap response ... ");
+
+            let executor = SwapExecutor::new(
+                &rpc_client,
+                &jup_swap_client,
+                &wallet,
+                &registry,
+                &args.tx,
+                &args.retry,
+                args.journal.clone(),
+            )?;
+            executor.execute(&pair, quote_response, &guardrails).await?;
+        }
+        Command::Balances => {
+            let wallet = load_wallet();
+            println!("Wallet: {}", wallet.pubkey());
+
+            let lamports = rpc_client.get_balance(&wallet.pubkey()).await?;
+            let holdings = token_holdings(&rpc_client, &wallet.pubkey()).await?;
+            println!("{}", render_balances(lamports, &holdings, &registry));
+        }
+        Command::Dca(args) => {
+            if args.pair.exact_out {
+                return Err(anyhow!(
+                    "dca spends a fixed amount of the input token per order, --exact-out isn't supported"
+                ));
+            }
+
+            let wallet = load_wallet();
+            println!("Wallet: {}", wallet.pubkey());
+
+            let pair = ResolvedPair::resolve(&args.pair, &registry, &rpc_client).await?;
+            let plan = DcaPlan {
+                order_amount: pair.amount,
+                budget: ui_to_raw(&args.budget, pair.input_decimals)?,
+                interval: args.interval,
+                max_price: args.max_price,
+                max_price_impact_pct: args.max_price_impact_pct,
+            };
+
+            let clock = SystemClock;
+            let mut state = DcaState::load_or_new(&args.state, &pair, clock.now())?;
+            println!(
+                "dca {} -> {}: {} per order every {}s, {} of {} spent so far",
+                registry.symbol(&pair.input_mint),
+                registry.symbol(&pair.output_mint),
+                raw_to_ui(plan.order_amount, pair.input_decimals),
+                plan.interval.as_secs(),
+                raw_to_ui(state.spent, pair.input_decimals),
+                raw_to_ui(plan.budget, pair.input_decimals)
+            );
+
+            if !confirm_and_create_atas(
+                "Start placing orders?",
+                &rpc_client,
+                &wallet,
+                &pair,
+                &args.tx,
+                args.yes,
+            )
+            .await?
+            {
+                println!("dca cancelled, nothing was signed");
+                return Ok(());
+            }
 
-    let wallet = Keypair::from_base58_string(&pk_base58);
-    println!("Wallet: {}", wallet.pubkey());
+            let executor = SwapExecutor::new(
+                &rpc_client,
+                &jup_swap_client,
+                &wallet,
+                &registry,
+                &args.tx,
+                &args.retry,
+                args.journal.clone(),
+            )?;
+            let dca = Dca {
+                jup: &jup_swap_client,
+                executor: &executor,
+                clock: &clock,
+                pair,
+                plan,
+                state_path: args.state.clone(),
+            };
+            dca.run(&mut state).await?;
+            println!(
+                "dca done: spent {} for {}",
+                raw_to_ui(state.spent, dca.pair.input_decimals),
+                raw_to_ui(state.received, dca.pair.output_decimals)
+            );
+        }
+        Command::Limit(args) => {
+            if args.pair.exact_out {
+                return Err(anyhow!(
+                    "limit orders sell a fixed amount of the input token, --exact-out isn't supported"
+                ));
+            }
 
-    const BONK: Pubkey = pubkey!("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263");
-    const SOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+            let wallet = load_wallet();
+            println!("Wallet: {}", wallet.pubkey());
 
-    let quote_request = QuoteRequest {
-        amount: 10_000_000,
-        input_mint: SOL,
-        output_mint: BONK,
-        slippage_bps: 50,
-        ..QuoteRequest::default()
-    };
+            let pair = ResolvedPair::resolve(&args.pair, &registry, &rpc_client).await?;
+            let plan = LimitPlan {
+                amount: pair.amount,
+                chunk: args
+                    .chunk
+                    .as_deref()
+                    .map(|chunk| ui_to_raw(chunk, pair.input_decimals))
+                    .transpose()?
+                    .unwrap_or(pair.amount),
+                min_price: args.min_price,
+                poll_interval: args.poll_interval,
+                max_price_impact_pct: args.max_price_impact_pct,
+            };
+            if plan.chunk == 0 {
+                return Err(anyhow!("--chunk has to be more than 0"));
+            }
 
-    let quote_response = jup_swap_client.quote(&quote_request).await?;
-    println!("got quote response, getting swap response ... ");
+            let clock = SystemClock;
+            let expires_at = args
+                .expires_in
+                .map(|expires_in| clock.now() + expires_in.as_secs());
+            let mut state = LimitState::load_or_new(&args.state, &pair, expires_at)?;
+            println!(
+                "limit order: sell {} {} for at least {} {} each, in chunks of {}, {} filled so far",
+                raw_to_ui(plan.amount, pair.input_decimals),
+                registry.symbol(&pair.input_mint),
+                plan.min_price,
+                registry.symbol(&pair.output_mint),
+                raw_to_ui(plan.chunk, pair.input_decimals),
+                raw_to_ui(state.filled, pair.input_decimals)
+            );
 
-    let swap_reponse = jup_swap_client
-        .swap(&SwapRequest {
-            user_public_key: wallet.pubkey(),
-            quote_response: quote_response.clone(),
-            config: TransactionConfig::default(),
-        })
-        .await?;
+            if !confirm_and_create_atas(
+                "Start watching the price?",
+                &rpc_client,
+                &wallet,
+                &pair,
+                &args.tx,
+                args.yes,
+            )
+            .await?
+            {
+                println!("limit order cancelled, nothing was signed");
+                return Ok(());
+            }
 
-    println!("got swap response, sending tx ...");
+            let executor = SwapExecutor::new(
+                &rpc_client,
+                &jup_swap_client,
+                &wallet,
+                &registry,
+                &args.tx,
+                &args.retry,
+                args.journal.clone(),
+            )?;
+            let order = LimitOrder {
+                jup: &jup_swap_client,
+                executor: &executor,
+                clock: &clock,
+                pair,
+                plan,
+                state_path: args.state.clone(),
+            };
+            let status = order.run(&mut state).await?;
+            println!(
+                "limit order {:?}: sold {} for {}",
+                status,
+                raw_to_ui(state.filled, order.pair.input_decimals),
+                raw_to_ui(state.received, order.pair.output_decimals)
+            );
+        }
+        Command::Rebalance(args) => {
+            let wallet = load_wallet();
+            println!("Wallet: {}", wallet.pubkey());
 
-    let versioned_tx: VersionedTransaction = bincode::deserialize(&swap_reponse.swap_transaction)?;
-    let signed_tx = VersionedTransaction::try_new(versioned_tx.message, &[&wallet])?;
+            let config = RebalanceConfig::load(&args.config)?;
+            let (reference, assets) = value_portfolio(
+                &rpc_client,
+                &jup_swap_client,
+                &registry,
+                &wallet.pubkey(),
+                &config,
+                args.slippage_bps,
+            )
+            .await?;
+            let legs = plan_rebalance(&assets, config.tolerance_pct, config.min_trade_value);
+            println!("{}", render_plan(&assets, &legs, &reference, &registry));
 
-    let sig = rpc_client.send_and_confirm_transaction(&signed_tx).await?;
+            if legs.is_empty() || args.dry_run {
+                return Ok(());
+            }
+            if !args.yes && !confirm("Execute these swaps?")? {
+                println!("rebalance cancelled, nothing was signed");
+                return Ok(());
+            }
 
-    println!("tx successful with signature: {:?}", sig);
+            let executor = SwapExecutor::new(
+                &rpc_client,
+                &jup_swap_client,
+                &wallet,
+                &registry,
+                &args.tx,
+                &args.retry,
+                args.journal.clone(),
+            )?;
+            let fills = execute_plan(
+                &jup_swap_client,
+                &executor,
+                &assets,
+                &legs,
+                args.max_price_impact_pct,
+            )
+            .await?;
+            println!("rebalance done, {} swaps", fills.len());
+        }
+        Command::Twap(args) => {
+            if args.pair.exact_out {
+                return Err(anyhow!(
+                    "twap sells a fixed amount of the input token, --exact-out isn't supported"
+                ));
+            }
+
+            let wallet = load_wallet();
+            println!("Wallet: {}", wallet.pubkey());
+
+            let pair = ResolvedPair::resolve(&args.pair, &registry, &rpc_client).await?;
+            let plan = TwapPlan {
+                total: pair.amount,
+                slices: args.slices,
+                duration: args.duration,
+                jitter_pct: args.jitter_pct,
+                max_cumulative_impact_pct: args.max_cumulative_impact_pct,
+                max_price_degradation_pct: args.max_price_degradation_pct,
+            };
+            let amounts = plan.amounts();
+            if amounts.contains(&0) {
+                return Err(anyhow!(
+                    "{} slices of {} leaves some of them empty",
+                    plan.slices,
+                    raw_to_ui(plan.total, pair.input_decimals)
+                ));
+            }
+            println!(
+                "twap: sell {} {} for {} in {} slices over {}s",
+                raw_to_ui(plan.total, pair.input_decimals),
+                registry.symbol(&pair.input_mint),
+                registry.symbol(&pair.output_mint),
+                plan.slices,
+                plan.duration.as_secs()
+            );
+
+            if !confirm_and_create_atas(
+                "Start the twap?",
+                &rpc_client,
+                &wallet,
+                &pair,
+                &args.tx,
+                args.yes,
+            )
+            .await?
+            {
+                println!("twap cancelled, nothing was signed");
+                return Ok(());
+            }
+
+            let clock = SystemClock;
+            let times = plan.times(clock.now(), &mut time_seeded_random());
+            let schedule: Vec<(u64, u64)> = times.into_iter().zip(amounts).collect();
+
+            let executor = SwapExecutor::new(
+                &rpc_client,
+                &jup_swap_client,
+                &wallet,
+                &registry,
+                &args.tx,
+                &args.retry,
+                args.journal.clone(),
+            )?;
+            let twap = Twap {
+                jup: &jup_swap_client,
+                executor: &executor,
+                clock: &clock,
+                pair,
+                plan,
+            };
+            let summary = twap.run(&schedule).await;
+            print!("{}", summary.render(&twap.pair, twap.plan.total, &registry));
+            if let Some(reason) = summary.aborted {
+                return Err(anyhow!("twap stopped early: {}", reason));
+            }
+        }
+    }
 
     Ok(())
 }
+
+/// Reports the output token accounts the swap needs, asks `prompt` unless `yes`, and creates the
+/// accounts with --create-atas. False if the user said no, nothing was signed then.
+async fn confirm_and_create_atas(
+    prompt: &str,
+    rpc_client: &RpcClient,
+    wallet: &Keypair,
+    pair: &ResolvedPair,
+    tx: &TxArgs,
+    yes: bool,
+) -> anyhow::Result<bool> {
+    let missing = report_missing_atas(rpc_client, wallet, pair, tx).await?;
+
+    if !yes && !confirm(prompt)? {
+        return Ok(false);
+    }
+
+    if tx.create_atas && !missing.is_empty() {
+        let sig = create_missing_atas(rpc_client, wallet, &missing).await?;
+        println!("created token accounts: {}", sig);
+    }
+    Ok(true)
+}
+
+/// Prints the output token accounts the swap will need created and what that costs in rent
+async fn report_missing_atas(
+    rpc_client: &RpcClient,
+    wallet: &Keypair,
+    pair: &ResolvedPair,
+    tx: &TxArgs,
+) -> anyhow::Result<Vec<MissingAccount>> {
+    let config = transaction_config(tx, None)?;
+    let missing = ata_preflight(
+        rpc_client,
+        &wallet.pubkey(),
+        pair,
+        config.transaction.wrap_and_unwrap_sol,
+        config.transaction.destination_token_account,
+    )
+    .await?;
+    for account in &missing {
+        println!(
+            "output token account {} doesn't exist, creating it costs {} SOL rent{}",
+            account.address,
+            raw_to_ui(account.rent, 9),
+            if tx.create_atas {
+                ""
+            } else {
+                " (Jupiter creates it in the swap)"
+            }
+        );
+    }
+    Ok(missing)
+}
+
+fn load_wallet() -> Keypair {
+    let pk_base58 = env::var("PRIVATE_KEY").expect("No PRIVATE_KEY provided");
+    Keypair::from_base58_string(&pk_base58)
+}
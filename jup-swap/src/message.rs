@@ -0,0 +1,94 @@
+use anyhow::{anyhow, Context};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::message::v0::{LoadedAddresses, LoadedMessage, MessageAddressTableLookup};
+use solana_sdk::message::{legacy, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+
+/// One account a message references, in the order instructions index them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageAccount {
+    pub pubkey: Pubkey,
+    pub signer: bool,
+    pub writable: bool,
+    /// Loaded from an address lookup table rather than listed in the message itself
+    pub from_lookup_table: bool,
+}
+
+/// Every account a message uses. Jupiter transactions are v0 and most of their accounts come from
+/// address lookup tables, so for those we have to fetch the tables to see what's actually touched.
+pub async fn resolve_accounts(
+    rpc: &RpcClient,
+    message: &VersionedMessage,
+) -> anyhow::Result<Vec<MessageAccount>> {
+    match message {
+        VersionedMessage::Legacy(message) => Ok(legacy_accounts(message)),
+        VersionedMessage::V0(message) => {
+            let loaded_addresses = load_addresses(rpc, &message.address_table_lookups).await?;
+            let static_len = message.account_keys.len();
+            let loaded = LoadedMessage::new(message.clone(), loaded_addresses);
+
+            Ok(loaded
+                .account_keys()
+                .iter()
+                .enumerate()
+                .map(|(i, pubkey)| MessageAccount {
+                    pubkey: *pubkey,
+                    signer: loaded.is_signer(i),
+                    writable: loaded.is_writable(i),
+                    from_lookup_table: i >= static_len,
+                })
+                .collect())
+        }
+    }
+}
+
+/// The accounts of a legacy message, which are all listed in the message itself
+pub fn legacy_accounts(message: &legacy::Message) -> Vec<MessageAccount> {
+    message
+        .account_keys
+        .iter()
+        .enumerate()
+        .map(|(i, pubkey)| MessageAccount {
+            pubkey: *pubkey,
+            signer: message.is_signer(i),
+            writable: message.is_writable(i),
+            from_lookup_table: false,
+        })
+        .collect()
+}
+
+/// Looks up the writable and readonly addresses a v0 message pulls out of its lookup tables
+pub async fn load_addresses(
+    rpc: &RpcClient,
+    lookups: &[MessageAddressTableLookup],
+) -> anyhow::Result<LoadedAddresses> {
+    let table_keys: Vec<Pubkey> = lookups.iter().map(|lookup| lookup.account_key).collect();
+    let tables = rpc
+        .get_multiple_accounts(&table_keys)
+        .await
+        .context("fetching address lookup tables")?;
+
+    let mut loaded = LoadedAddresses::default();
+    for (lookup, table) in lookups.iter().zip(tables) {
+        let table =
+            table.ok_or_else(|| anyhow!("lookup table {} not found", lookup.account_key))?;
+        let table = AddressLookupTable::deserialize(&table.data)
+            .map_err(|e| anyhow!("bad lookup table {}: {}", lookup.account_key, e))?;
+
+        let select = |indexes: &[u8]| -> anyhow::Result<Vec<Pubkey>> {
+            indexes
+                .iter()
+                .map(|i| {
+                    table.addresses.get(*i as usize).copied().ok_or_else(|| {
+                        anyhow!("index {} out of range in table {}", i, lookup.account_key)
+                    })
+                })
+                .collect()
+        };
+        loaded.writable.extend(select(&lookup.writable_indexes)?);
+        loaded.readonly.extend(select(&lookup.readonly_indexes)?);
+    }
+
+    Ok(loaded)
+}
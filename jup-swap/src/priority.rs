@@ -0,0 +1,226 @@
+use crate::receipt::LAMPORTS_PER_SIGNATURE;
+use crate::swap::{ResolvedPair, SwapCheck};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use jupiter_swap_api_client::quote::QuoteResponse;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::compute_budget;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use std::str::FromStr;
+
+/// Compute unit limit Jupiter sets on a swap unless `--dynamic-compute-unit-limit` is on, used to
+/// turn a priority fee in lamports into a price per compute unit
+pub const DEFAULT_SWAP_COMPUTE_UNITS: u32 = 1_400_000;
+/// What the runtime gives an instruction when there's no compute unit limit instruction
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u32 = 200_000;
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+/// `auto` picks this percentile of recent fees, enough to land without overpaying much
+pub const AUTO_PERCENTILE: u8 = 75;
+
+/// How to pick the priority fee, from `--priority-fee`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityFee {
+    /// Total priority fee in lamports
+    Lamports(u64),
+    /// `AUTO_PERCENTILE` of recent fees paid on the route's accounts
+    Auto,
+    /// This percentile of recent fees paid on the route's accounts, written like `p90`
+    Percentile(u8),
+}
+
+impl FromStr for PriorityFee {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim().to_lowercase();
+        if s == "auto" {
+            return Ok(PriorityFee::Auto);
+        }
+        if let Some(pct) = s.strip_prefix('p') {
+            let pct: u8 = pct
+                .parse()
+                .map_err(|_| anyhow!("bad priority fee percentile {}", s))?;
+            if pct > 100 {
+                return Err(anyhow!("priority fee percentile {} is above 100", pct));
+            }
+            return Ok(PriorityFee::Percentile(pct));
+        }
+        s.parse().map(PriorityFee::Lamports).map_err(|_| {
+            anyhow!(
+                "bad priority fee {}, use lamports, auto or a percentile like p90",
+                s
+            )
+        })
+    }
+}
+
+/// Nearest rank percentile of the fees, 0 when there aren't any
+pub fn percentile(fees: &[u64], pct: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    let mut fees = fees.to_vec();
+    fees.sort_unstable();
+
+    let rank = (pct as usize * fees.len()).div_ceil(100);
+    fees[rank.saturating_sub(1).min(fees.len() - 1)]
+}
+
+/// Total priority fee in lamports for a compute unit price in micro lamports, rounded up like the
+/// runtime does
+pub fn priority_fee_lamports(micro_lamports_per_cu: u64, compute_units: u32) -> u64 {
+    (micro_lamports_per_cu as u128 * compute_units as u128).div_ceil(1_000_000) as u64
+}
+
+/// The compute unit price that spends `lamports` of priority fee over `compute_units`
+pub fn price_for_lamports(lamports: u64, compute_units: u32) -> u64 {
+    if compute_units == 0 {
+        return 0;
+    }
+    (lamports as u128 * 1_000_000 / compute_units as u128) as u64
+}
+
+/// The AMM accounts the quote routes through. Fees paid to write lock these are what we compete
+/// with, so they're what we ask `getRecentPrioritizationFees` about.
+pub fn route_accounts(quote: &QuoteResponse) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = quote
+        .route_plan
+        .iter()
+        .map(|step| step.swap_info.amm_key)
+        .collect();
+    accounts.sort();
+    accounts.dedup();
+    accounts
+}
+
+/// Works out the compute unit price in micro lamports. With `max_fee_lamports` the price is
+/// capped so the whole fee stays under it at `compute_units`.
+pub async fn compute_unit_price(
+    rpc: &RpcClient,
+    fee: PriorityFee,
+    quote: &QuoteResponse,
+    compute_units: u32,
+    max_fee_lamports: Option<u64>,
+) -> anyhow::Result<u64> {
+    let price = match fee {
+        PriorityFee::Lamports(lamports) => price_for_lamports(lamports, compute_units),
+        PriorityFee::Auto | PriorityFee::Percentile(_) => {
+            let pct = match fee {
+                PriorityFee::Percentile(pct) => pct,
+                _ => AUTO_PERCENTILE,
+            };
+            let recent: Vec<u64> = rpc
+                .get_recent_prioritization_fees(&route_accounts(quote))
+                .await
+                .context("fetching recent prioritization fees")?
+                .iter()
+                .map(|fee| fee.prioritization_fee)
+                .collect();
+            percentile(&recent, pct)
+        }
+    };
+
+    Ok(match max_fee_lamports {
+        Some(max) => {
+            let max_priority = max.saturating_sub(LAMPORTS_PER_SIGNATURE);
+            price.min(price_for_lamports(max_priority, compute_units))
+        }
+        None => price,
+    })
+}
+
+/// The compute unit limit and price the message asks for. Without a limit instruction every
+/// other instruction gets the default 200k, capped at 1.4M for the transaction.
+pub fn compute_budget(message: &VersionedMessage) -> (u32, u64) {
+    let keys = message.static_account_keys();
+    let mut limit = None;
+    let mut price = 0;
+    let mut other_instructions = 0u32;
+
+    for ix in message.instructions() {
+        if keys.get(ix.program_id_index as usize) != Some(&compute_budget::ID) {
+            other_instructions += 1;
+            continue;
+        }
+        match ix.data.split_first() {
+            Some((2, data)) if data.len() >= 4 => {
+                limit = Some(u32::from_le_bytes(data[..4].try_into().unwrap()));
+            }
+            Some((3, data)) if data.len() >= 8 => {
+                price = u64::from_le_bytes(data[..8].try_into().unwrap());
+            }
+            _ => {}
+        }
+    }
+
+    let limit = limit
+        .unwrap_or(other_instructions.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNITS))
+        .min(MAX_COMPUTE_UNITS);
+    (limit, price)
+}
+
+/// Most the transaction can cost in lamports: the base fee plus the priority fee at its compute
+/// unit limit
+pub fn max_fee(message: &VersionedMessage) -> u64 {
+    let (limit, price) = compute_budget(message);
+    LAMPORTS_PER_SIGNATURE * message.header().num_required_signatures as u64
+        + priority_fee_lamports(price, limit)
+}
+
+/// Refuses to sign if what Jupiter built could cost more than `--max-fee-lamports`
+pub struct FeeCapCheck {
+    pub max_fee_lamports: u64,
+    /// Paid on top of the swap's own fee when it goes out as a bundle, see `bundle_fee_lamports`
+    pub bundle_fee_lamports: u64,
+}
+
+impl FeeCapCheck {
+    /// The check for `--max-fee-lamports`, if it's set. Fails if the bundle alone would leave
+    /// less than a signature's base fee for the swap, since nothing could pass then.
+    pub fn new(
+        max_fee_lamports: Option<u64>,
+        bundle_fee_lamports: u64,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(max_fee_lamports) = max_fee_lamports else {
+            return Ok(None);
+        };
+        if bundle_fee_lamports + LAMPORTS_PER_SIGNATURE > max_fee_lamports {
+            return Err(anyhow!(
+                "the bundle tip and its transaction fee come to {} lamports, which leaves nothing of --max-fee-lamports {} for the swap",
+                bundle_fee_lamports,
+                max_fee_lamports
+            ));
+        }
+        Ok(Some(Self {
+            max_fee_lamports,
+            bundle_fee_lamports,
+        }))
+    }
+}
+
+#[async_trait]
+impl SwapCheck for FeeCapCheck {
+    async fn check(
+        &self,
+        _wallet: &Pubkey,
+        _pair: &ResolvedPair,
+        _quote: &QuoteResponse,
+        tx: &VersionedTransaction,
+    ) -> anyhow::Result<()> {
+        let fee = max_fee(&tx.message);
+        if fee + self.bundle_fee_lamports > self.max_fee_lamports {
+            let (limit, price) = compute_budget(&tx.message);
+            return Err(anyhow!(
+                "swap could cost up to {} lamports in fees ({} compute units at {} micro lamports) plus {} for the bundle, above --max-fee-lamports {}, not signing",
+                fee,
+                limit,
+                price,
+                self.bundle_fee_lamports,
+                self.max_fee_lamports
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use crate::swap::ResolvedPair;
+use crate::tokens::{raw_to_ui, TokenRegistry};
+use anyhow::anyhow;
+use jupiter_swap_api_client::quote::QuoteResponse;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt::Write;
+use std::io::BufRead;
+
+/// One line summary of a quote in UI units
+pub fn quote_summary(
+    quote: &QuoteResponse,
+    pair: &ResolvedPair,
+    registry: &TokenRegistry,
+) -> String {
+    let in_symbol = registry.symbol(&pair.input_mint);
+    let out_symbol = registry.symbol(&pair.output_mint);
+
+    //other_amount_threshold is the min out for exact in and the max in for exact out
+    let threshold = if pair.exact_out {
+        format!(
+            "max in {} {}",
+            raw_to_ui(quote.other_amount_threshold, pair.input_decimals),
+            in_symbol
+        )
+    } else {
+        format!(
+            "min out {} {}",
+            raw_to_ui(quote.other_amount_threshold, pair.output_decimals),
+            out_symbol
+        )
+    };
+
+    format!(
+        "{} {} -> {} {} ({})",
+        raw_to_ui(quote.in_amount, pair.input_decimals),
+        in_symbol,
+        raw_to_ui(quote.out_amount, pair.output_decimals),
+        out_symbol,
+        threshold
+    )
+}
+
+/// The worst amount of the output token we can receive. For exact in that's the
+/// `other_amount_threshold` (out amount minus slippage), for exact out the out amount is fixed.
+pub fn min_out(quote: &QuoteResponse, pair: &ResolvedPair) -> u64 {
+    if pair.exact_out {
+        quote.out_amount
+    } else {
+        quote.other_amount_threshold
+    }
+}
+
+/// Price impact in percent. Despite the name, Jupiter returns `price_impact_pct` as a fraction
+/// (0.01 is 1%), which is why this multiplies by 100.
+pub fn price_impact_pct(quote: &QuoteResponse) -> anyhow::Result<f64> {
+    let fraction: f64 = quote
+        .price_impact_pct
+        .to_string()
+        .parse()
+        .map_err(|e| anyhow!("bad price impact {}: {}", quote.price_impact_pct, e))?;
+    Ok(fraction * 100.0)
+}
+
+/// Renders everything we want a human to look at before signing: amounts, slippage, price
+/// impact, fees and every hop in the route plan
+pub fn render_quote(
+    quote: &QuoteResponse,
+    pair: &ResolvedPair,
+    registry: &TokenRegistry,
+) -> String {
+    let fmt = |amount: u64, mint: &Pubkey| format_amount(amount, mint, pair, registry);
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "{:<16} {}",
+        "in",
+        fmt(quote.in_amount, &pair.input_mint)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "{:<16} {}",
+        "out",
+        fmt(quote.out_amount, &pair.output_mint)
+    )
+    .unwrap();
+    if pair.exact_out {
+        writeln!(
+            out,
+            "{:<16} {} ({} bps slippage)",
+            "max in",
+            fmt(quote.other_amount_threshold, &pair.input_mint),
+            quote.slippage_bps
+        )
+        .unwrap();
+    } else {
+        writeln!(
+            out,
+            "{:<16} {} ({} bps slippage)",
+            "min out",
+            fmt(quote.other_amount_threshold, &pair.output_mint),
+            quote.slippage_bps
+        )
+        .unwrap();
+    }
+
+    let impact = price_impact_pct(quote)
+        .map(|pct| format!("{:.4}%", pct))
+        .unwrap_or_else(|e| e.to_string());
+    writeln!(out, "{:<16} {}", "price impact", impact).unwrap();
+
+    let platform_fee = match &quote.platform_fee {
+        Some(fee) if fee.amount > 0 => format!("{} ({} bps)", fee.amount, fee.fee_bps),
+        _ => "none".to_string(),
+    };
+    writeln!(out, "{:<16} {}", "platform fee", platform_fee).unwrap();
+
+    writeln!(out, "route ({} hops)", quote.route_plan.len()).unwrap();
+    writeln!(
+        out,
+        "  {:<3} {:<20} {:>8} {:<30} {:<30} fee",
+        "#", "amm", "percent", "in", "out"
+    )
+    .unwrap();
+    for (i, step) in quote.route_plan.iter().enumerate() {
+        let info = &step.swap_info;
+        writeln!(
+            out,
+            "  {:<3} {:<20} {:>7}% {:<30} {:<30} {}",
+            i + 1,
+            info.label,
+            step.percent,
+            fmt(info.in_amount, &info.input_mint),
+            fmt(info.out_amount, &info.output_mint),
+            fmt(info.fee_amount, &info.fee_mint),
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+/// UI amount with symbol for the pair's own mints. Intermediate route mints we don't know the
+/// decimals of without another rpc call, so those are shown raw.
+fn format_amount(
+    amount: u64,
+    mint: &Pubkey,
+    pair: &ResolvedPair,
+    registry: &TokenRegistry,
+) -> String {
+    if *mint == pair.input_mint {
+        format!(
+            "{} {}",
+            raw_to_ui(amount, pair.input_decimals),
+            registry.symbol(mint)
+        )
+    } else if *mint == pair.output_mint {
+        format!(
+            "{} {}",
+            raw_to_ui(amount, pair.output_decimals),
+            registry.symbol(mint)
+        )
+    } else {
+        format!("{} raw {}", amount, registry.symbol(mint))
+    }
+}
+
+/// Hard limits checked against the quote before anything gets signed, even with `--yes`
+#[derive(Debug, Clone, Default)]
+pub struct Guardrails {
+    /// Abort if the price impact is above this percent
+    pub max_price_impact_pct: Option<f64>,
+    /// Abort if the worst case amount out (after slippage) is below this raw amount
+    pub min_out: Option<u64>,
+}
+
+impl Guardrails {
+    pub fn check(&self, quote: &QuoteResponse, pair: &ResolvedPair) -> anyhow::Result<()> {
+        if let Some(max) = self.max_price_impact_pct {
+            let impact = price_impact_pct(quote)?;
+            if impact > max {
+                return Err(anyhow!(
+                    "price impact {:.4}% is above --max-price-impact-pct {}%, not signing",
+                    impact,
+                    max
+                ));
+            }
+        }
+
+        if let Some(min) = self.min_out {
+            let worst_case = min_out(quote, pair);
+            if worst_case < min {
+                return Err(anyhow!(
+                    "minimum out {} is below --min-out {}, not signing",
+                    raw_to_ui(worst_case, pair.output_decimals),
+                    raw_to_ui(min, pair.output_decimals)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Asks a yes/no question on stdin, anything other than y/yes is a no
+pub fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    print!("{} [y/N] ", prompt);
+    std::io::Write::flush(&mut std::io::stdout())?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,390 @@
+use crate::accounts::token_holdings;
+use crate::executor::{Executor, Fill};
+use crate::quote::Guardrails;
+use crate::swap::ResolvedPair;
+use crate::tokens::{mint_decimals, raw_to_ui, ui_to_raw, TokenRegistry};
+use anyhow::{anyhow, Context};
+use jupiter_swap_api_client::quote::QuoteRequest;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+fn default_reference() -> String {
+    "USDC".to_string()
+}
+
+fn default_tolerance_pct() -> f64 {
+    1.0
+}
+
+fn default_min_trade_value() -> f64 {
+    1.0
+}
+
+fn default_sol_reserve() -> String {
+    "0.05".to_string()
+}
+
+/// Target allocations and limits for `rebalance`, read from a JSON file like
+///
+/// ```json
+/// {
+///   "reference": "USDC",
+///   "targets": { "SOL": 50, "USDC": 30, "BONK": 20 },
+///   "tolerance_pct": 2,
+///   "slippage_bps": { "BONK": 150 }
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct RebalanceConfig {
+    /// Token holdings are valued in, a symbol or mint
+    #[serde(default = "default_reference")]
+    pub reference: String,
+    /// Percent of the portfolio's value each token should make up, adding up to 100
+    pub targets: BTreeMap<String, f64>,
+    /// How many percentage points a token may drift from its target before anything is traded
+    #[serde(default = "default_tolerance_pct")]
+    pub tolerance_pct: f64,
+    /// Legs worth less than this, in the reference token, aren't worth the fees and are dropped
+    #[serde(default = "default_min_trade_value")]
+    pub min_trade_value: f64,
+    /// Slippage for legs trading this token. A leg uses the larger of its two tokens', and
+    /// `--slippage-bps` for tokens that aren't listed.
+    #[serde(default)]
+    pub slippage_bps: BTreeMap<String, u16>,
+    /// SOL left out of the portfolio to pay for fees and rent (UI units)
+    #[serde(default = "default_sol_reserve")]
+    pub sol_reserve: String,
+}
+
+impl RebalanceConfig {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading rebalance config {}", path.display()))?;
+        Self::from_json(&contents)
+    }
+
+    pub fn from_json(contents: &str) -> anyhow::Result<Self> {
+        let config: Self = serde_json::from_str(contents)?;
+
+        if config.targets.is_empty() {
+            return Err(anyhow!("rebalance config has no targets"));
+        }
+        if let Some((token, _)) = config.targets.iter().find(|(_, pct)| **pct < 0.0) {
+            return Err(anyhow!("target for {} is negative", token));
+        }
+        let total: f64 = config.targets.values().sum();
+        if (total - 100.0).abs() > 0.01 {
+            return Err(anyhow!("targets add up to {}%, not 100%", total));
+        }
+        if config.tolerance_pct < 0.0 {
+            return Err(anyhow!("tolerance_pct can't be negative"));
+        }
+
+        Ok(config)
+    }
+}
+
+/// One token of the portfolio as the planner sees it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    /// Raw amount held that may be traded
+    pub amount: u64,
+    /// Value of one token in the reference token (UI units)
+    pub price: f64,
+    /// Percent of the portfolio's value this token should make up
+    pub target_pct: f64,
+    /// Slippage for legs trading this token
+    pub slippage_bps: u16,
+}
+
+impl Asset {
+    /// Value of the holding in the reference token (UI units)
+    pub fn value(&self) -> f64 {
+        self.amount as f64 / 10f64.powi(self.decimals as i32) * self.price
+    }
+}
+
+/// One swap of the plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leg {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    /// Raw amount of the input token to sell
+    pub amount: u64,
+    /// What that's worth in the reference token (UI units)
+    pub value: f64,
+    pub slippage_bps: u16,
+}
+
+/// Each asset's share of the portfolio's value in percent, in the same order
+pub fn allocations(assets: &[Asset]) -> Vec<f64> {
+    let total: f64 = assets.iter().map(Asset::value).sum();
+    assets
+        .iter()
+        .map(|asset| {
+            if total > 0.0 {
+                asset.value() / total * 100.0
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Works out the swaps that bring every asset back to its target. Nothing is traded while every
+/// asset is within `tolerance_pct` of its target. Once one isn't, the biggest excess is sold
+/// into the biggest shortfall until everything is matched up, which takes at most one leg less
+/// than there are assets. Legs worth less than `min_trade_value` are left out.
+pub fn plan_rebalance(assets: &[Asset], tolerance_pct: f64, min_trade_value: f64) -> Vec<Leg> {
+    let total: f64 = assets.iter().map(Asset::value).sum();
+    if total <= 0.0 {
+        return vec![];
+    }
+
+    let drifted = assets
+        .iter()
+        .zip(allocations(assets))
+        .any(|(asset, pct)| (pct - asset.target_pct).abs() > tolerance_pct);
+    if !drifted {
+        return vec![];
+    }
+
+    //positive is worth more than its target and gets sold, negative gets bought
+    let excess: Vec<f64> = assets
+        .iter()
+        .map(|asset| asset.value() - asset.target_pct / 100.0 * total)
+        .collect();
+    let mut sellers: Vec<(usize, f64)> = excess
+        .iter()
+        .enumerate()
+        .filter(|(_, excess)| **excess > 0.0)
+        .map(|(i, excess)| (i, *excess))
+        .collect();
+    let mut buyers: Vec<(usize, f64)> = excess
+        .iter()
+        .enumerate()
+        .filter(|(i, excess)| **excess < 0.0 && assets[*i].price > 0.0)
+        .map(|(i, excess)| (i, -excess))
+        .collect();
+    sellers.sort_by(|a, b| b.1.total_cmp(&a.1));
+    buyers.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut legs = vec![];
+    let (mut s, mut b) = (0, 0);
+    while s < sellers.len() && b < buyers.len() {
+        let value = sellers[s].1.min(buyers[b].1);
+        let seller = &assets[sellers[s].0];
+        let buyer = &assets[buyers[b].0];
+
+        if value >= min_trade_value && seller.price > 0.0 {
+            let amount = (value / seller.price * 10f64.powi(seller.decimals as i32)).round() as u64;
+            legs.push(Leg {
+                input_mint: seller.mint,
+                output_mint: buyer.mint,
+                amount: amount.min(seller.amount),
+                value,
+                slippage_bps: seller.slippage_bps.max(buyer.slippage_bps),
+            });
+        }
+
+        sellers[s].1 -= value;
+        buyers[b].1 -= value;
+        if sellers[s].1 <= 0.0 || sellers[s].1 < min_trade_value {
+            s += 1;
+        }
+        if buyers[b].1 <= 0.0 || buyers[b].1 < min_trade_value {
+            b += 1;
+        }
+    }
+
+    legs
+}
+
+/// The current and target allocation of every asset, then the legs
+pub fn render_plan(
+    assets: &[Asset],
+    legs: &[Leg],
+    reference: &Pubkey,
+    registry: &TokenRegistry,
+) -> String {
+    let decimals: BTreeMap<Pubkey, u8> = assets.iter().map(|a| (a.mint, a.decimals)).collect();
+    let total: f64 = assets.iter().map(Asset::value).sum();
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "{:<12} {:>24} {:>16} {:>9} {:>9}",
+        "token", "balance", "value", "current", "target"
+    )
+    .unwrap();
+    for (asset, pct) in assets.iter().zip(allocations(assets)) {
+        writeln!(
+            out,
+            "{:<12} {:>24} {:>16.2} {:>8.2}% {:>8.2}%",
+            registry.symbol(&asset.mint),
+            raw_to_ui(asset.amount, asset.decimals),
+            asset.value(),
+            pct,
+            asset.target_pct
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "{:<12} {:>24} {:>16.2} {}",
+        "total",
+        "",
+        total,
+        registry.symbol(reference)
+    )
+    .unwrap();
+
+    if legs.is_empty() {
+        writeln!(out, "\nevery token is within tolerance, nothing to trade").unwrap();
+        return out;
+    }
+    writeln!(out).unwrap();
+    for (i, leg) in legs.iter().enumerate() {
+        writeln!(
+            out,
+            "leg {}: sell {} {} for {} (~{:.2} {}, {} bps slippage)",
+            i + 1,
+            raw_to_ui(leg.amount, decimals[&leg.input_mint]),
+            registry.symbol(&leg.input_mint),
+            registry.symbol(&leg.output_mint),
+            leg.value,
+            registry.symbol(reference),
+            leg.slippage_bps
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Looks up the wallet's holdings of every target token and values them against `reference`.
+/// Each holding is priced by quoting all of it, so the value already accounts for price impact.
+/// Tokens the wallet doesn't hold are priced by quoting one token.
+pub async fn value_portfolio(
+    rpc: &RpcClient,
+    jup: &JupiterSwapApiClient,
+    registry: &TokenRegistry,
+    wallet: &Pubkey,
+    config: &RebalanceConfig,
+    default_slippage_bps: u16,
+) -> anyhow::Result<(Pubkey, Vec<Asset>)> {
+    let reference = registry.resolve(&config.reference)?;
+    let reference_decimals = mint_decimals(rpc, &reference).await?;
+
+    let mut held: BTreeMap<Pubkey, u64> = BTreeMap::new();
+    for holding in token_holdings(rpc, wallet).await? {
+        //wSOL is traded as SOL, which comes from the lamports below
+        if holding.mint != spl_token::native_mint::ID {
+            *held.entry(holding.mint).or_default() += holding.amount;
+        }
+    }
+    let reserve = ui_to_raw(&config.sol_reserve, 9)?;
+    let lamports = rpc.get_balance(wallet).await?;
+    held.insert(spl_token::native_mint::ID, lamports.saturating_sub(reserve));
+
+    let mut assets = vec![];
+    for (token, target_pct) in &config.targets {
+        let mint = registry.resolve(token)?;
+        let decimals = mint_decimals(rpc, &mint).await?;
+        let amount = held.get(&mint).copied().unwrap_or(0);
+        let slippage_bps = config
+            .slippage_bps
+            .iter()
+            .find(|(symbol, _)| registry.resolve(symbol).ok() == Some(mint))
+            .map_or(default_slippage_bps, |(_, bps)| *bps);
+
+        let price = if mint == reference {
+            1.0
+        } else {
+            let priced_amount = if amount > 0 {
+                amount
+            } else {
+                10u64.pow(decimals as u32)
+            };
+            let quote = jup
+                .quote(&QuoteRequest {
+                    amount: priced_amount,
+                    input_mint: mint,
+                    output_mint: reference,
+                    slippage_bps: default_slippage_bps,
+                    ..QuoteRequest::default()
+                })
+                .await
+                .with_context(|| format!("pricing {}", token))?;
+            (quote.out_amount as f64 / 10f64.powi(reference_decimals as i32))
+                / (priced_amount as f64 / 10f64.powi(decimals as i32))
+        };
+
+        assets.push(Asset {
+            mint,
+            decimals,
+            amount,
+            price,
+            target_pct: *target_pct,
+            slippage_bps,
+        });
+    }
+
+    Ok((reference, assets))
+}
+
+/// Runs the legs one after the other. The first leg that fails stops the rest, since the plan
+/// was made for balances that no longer hold. Returns the fills of the legs that went through.
+pub async fn execute_plan(
+    jup: &JupiterSwapApiClient,
+    executor: &dyn Executor,
+    assets: &[Asset],
+    legs: &[Leg],
+    max_price_impact_pct: Option<f64>,
+) -> anyhow::Result<Vec<Fill>> {
+    let decimals: BTreeMap<Pubkey, u8> = assets.iter().map(|a| (a.mint, a.decimals)).collect();
+    let guardrails = Guardrails {
+        max_price_impact_pct,
+        min_out: None,
+    };
+
+    let mut fills = vec![];
+    for (i, leg) in legs.iter().enumerate() {
+        let pair = ResolvedPair {
+            input_mint: leg.input_mint,
+            output_mint: leg.output_mint,
+            input_decimals: decimals[&leg.input_mint],
+            output_decimals: decimals[&leg.output_mint],
+            amount: leg.amount,
+            slippage_bps: leg.slippage_bps,
+            exact_out: false,
+        };
+
+        let result = async {
+            let quote = jup.quote(&pair.quote_request()).await?;
+            guardrails.check(&quote, &pair)?;
+            executor.execute(&pair, quote, &guardrails).await
+        }
+        .await;
+
+        match result {
+            Ok(fill) => fills.push(fill),
+            Err(e) => {
+                return Err(e.context(format!(
+                    "leg {} of {} failed after {} went through, stopping",
+                    i + 1,
+                    legs.len(),
+                    fills.len()
+                )))
+            }
+        }
+    }
+
+    Ok(fills)
+}
@@ -0,0 +1,392 @@
+use crate::swap::ResolvedPair;
+use crate::tokens::{raw_to_ui, TokenRegistry};
+use anyhow::{anyhow, Context};
+use jupiter_swap_api_client::quote::QuoteResponse;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{UiTransactionEncoding, UiTransactionTokenBalance};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Base fee per signature, anything the transaction paid above this is priority fee
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// A token balance from the transaction meta, parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub account_index: u8,
+    pub mint: Pubkey,
+    pub owner: Option<Pubkey>,
+    pub amount: u64,
+}
+
+/// The parts of a confirmed transaction's meta the receipt is built from
+#[derive(Debug, Clone, Default)]
+pub struct TxBalances {
+    pub fee: u64,
+    pub signatures: usize,
+    pub block_time: Option<i64>,
+    /// Lamports of every account in the transaction, the wallet is at index 0 as fee payer
+    pub pre_lamports: Vec<u64>,
+    pub post_lamports: Vec<u64>,
+    pub pre_tokens: Vec<TokenBalance>,
+    pub post_tokens: Vec<TokenBalance>,
+}
+
+impl TxBalances {
+    /// Sum of the wallet's balance of `mint` across its token accounts
+    fn token_total(tokens: &[TokenBalance], wallet: &Pubkey, mint: &Pubkey) -> i128 {
+        tokens
+            .iter()
+            .filter(|b| b.owner == Some(*wallet) && b.mint == *mint)
+            .map(|b| b.amount as i128)
+            .sum()
+    }
+
+    fn token_delta(&self, wallet: &Pubkey, mint: &Pubkey) -> i128 {
+        Self::token_total(&self.post_tokens, wallet, mint)
+            - Self::token_total(&self.pre_tokens, wallet, mint)
+    }
+
+    /// Rent for token accounts of the wallet the transaction created and didn't close again
+    fn rent_paid(&self, wallet: &Pubkey) -> u64 {
+        self.post_tokens
+            .iter()
+            .filter(|b| b.owner == Some(*wallet))
+            .map(|b| b.account_index as usize)
+            .filter(|i| self.pre_lamports.get(*i) == Some(&0))
+            .filter_map(|i| self.post_lamports.get(i))
+            .sum()
+    }
+}
+
+/// What the swap actually did, next to what was quoted
+#[derive(Debug, Clone)]
+pub struct Receipt {
+    pub signature: Signature,
+    pub block_time: Option<i64>,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub input_decimals: u8,
+    pub output_decimals: u8,
+    /// Raw amounts that actually left and arrived in the wallet
+    pub in_amount: u64,
+    pub out_amount: u64,
+    pub quoted_in_amount: u64,
+    pub quoted_out_amount: u64,
+    /// Base fee, 5000 lamports per signature
+    pub network_fee: u64,
+    pub priority_fee: u64,
+    /// Rent paid for token accounts the swap created
+    pub rent: u64,
+}
+
+impl Receipt {
+    /// Works out the realized amounts from the balance changes. With `sol_as_lamports` SOL was
+    /// wrapped and unwrapped in the transaction, so the SOL side comes from the wallet's lamports
+    /// after backing out the fee and any rent.
+    pub fn compute(
+        signature: Signature,
+        balances: &TxBalances,
+        wallet: &Pubkey,
+        pair: &ResolvedPair,
+        quote: &QuoteResponse,
+        sol_as_lamports: bool,
+    ) -> Self {
+        Self::compute_quoted(
+            signature,
+            balances,
+            wallet,
+            pair,
+            (quote.in_amount, quote.out_amount),
+            sol_as_lamports,
+        )
+    }
+
+    /// `compute` for a swap whose quote is gone and only its raw in and out amounts are known,
+    /// like one picked up again from a state file
+    pub fn compute_quoted(
+        signature: Signature,
+        balances: &TxBalances,
+        wallet: &Pubkey,
+        pair: &ResolvedPair,
+        (quoted_in_amount, quoted_out_amount): (u64, u64),
+        sol_as_lamports: bool,
+    ) -> Self {
+        let rent = balances.rent_paid(wallet);
+        let network_fee = LAMPORTS_PER_SIGNATURE * balances.signatures as u64;
+        let lamport_delta = match (
+            balances.pre_lamports.first(),
+            balances.post_lamports.first(),
+        ) {
+            (Some(pre), Some(post)) => *post as i128 - *pre as i128,
+            _ => 0,
+        };
+
+        let delta = |mint: &Pubkey| {
+            if sol_as_lamports && *mint == spl_token::native_mint::ID {
+                lamport_delta + balances.fee as i128 + rent as i128
+            } else {
+                balances.token_delta(wallet, mint)
+            }
+        };
+        let clamp = |amount: i128| amount.clamp(0, u64::MAX as i128) as u64;
+
+        Self {
+            signature,
+            block_time: balances.block_time,
+            input_mint: pair.input_mint,
+            output_mint: pair.output_mint,
+            input_decimals: pair.input_decimals,
+            output_decimals: pair.output_decimals,
+            in_amount: clamp(-delta(&pair.input_mint)),
+            out_amount: clamp(delta(&pair.output_mint)),
+            quoted_in_amount,
+            quoted_out_amount,
+            network_fee,
+            priority_fee: balances.fee.saturating_sub(network_fee),
+            rent,
+        }
+    }
+
+    /// Output token per input token, in UI units
+    pub fn effective_price(&self) -> f64 {
+        ui_price(
+            self.in_amount,
+            self.out_amount,
+            self.input_decimals,
+            self.output_decimals,
+        )
+    }
+
+    pub fn quoted_price(&self) -> f64 {
+        ui_price(
+            self.quoted_in_amount,
+            self.quoted_out_amount,
+            self.input_decimals,
+            self.output_decimals,
+        )
+    }
+
+    /// Slippage actually incurred against the quoted price in basis points, positive is worse
+    pub fn slippage_bps(&self) -> f64 {
+        let quoted = self.quoted_price();
+        if quoted == 0.0 {
+            return 0.0;
+        }
+        (quoted - self.effective_price()) / quoted * 10_000.0
+    }
+
+    pub fn render(&self, registry: &TokenRegistry) -> String {
+        let in_symbol = registry.symbol(&self.input_mint);
+        let out_symbol = registry.symbol(&self.output_mint);
+        let mut out = String::new();
+
+        writeln!(out, "receipt {}", self.signature).unwrap();
+        writeln!(
+            out,
+            "  {:<16} {} {} (quoted {})",
+            "in",
+            raw_to_ui(self.in_amount, self.input_decimals),
+            in_symbol,
+            raw_to_ui(self.quoted_in_amount, self.input_decimals)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "  {:<16} {} {} (quoted {})",
+            "out",
+            raw_to_ui(self.out_amount, self.output_decimals),
+            out_symbol,
+            raw_to_ui(self.quoted_out_amount, self.output_decimals)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "  {:<16} {} {}/{} (quoted {})",
+            "price",
+            self.effective_price(),
+            out_symbol,
+            in_symbol,
+            self.quoted_price()
+        )
+        .unwrap();
+        writeln!(out, "  {:<16} {:.2} bps", "slippage", self.slippage_bps()).unwrap();
+        writeln!(
+            out,
+            "  {:<16} {} lamports network + {} lamports priority",
+            "fees", self.network_fee, self.priority_fee
+        )
+        .unwrap();
+        writeln!(out, "  {:<16} {} lamports", "ata rent", self.rent).unwrap();
+        out
+    }
+}
+
+fn ui_price(in_amount: u64, out_amount: u64, input_decimals: u8, output_decimals: u8) -> f64 {
+    if in_amount == 0 {
+        return 0.0;
+    }
+    let ui_in = in_amount as f64 / 10f64.powi(input_decimals as i32);
+    let ui_out = out_amount as f64 / 10f64.powi(output_decimals as i32);
+    ui_out / ui_in
+}
+
+/// Fetches the confirmed swap and pulls the balances out of its meta. Right after confirmation
+/// the rpc node we ask may not have it yet, so this retries for a few seconds.
+pub async fn fetch_balances(rpc: &RpcClient, signature: &Signature) -> anyhow::Result<TxBalances> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(rpc.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    let mut attempts = 0;
+    let tx = loop {
+        match rpc.get_transaction_with_config(signature, config).await {
+            Ok(tx) => break tx,
+            Err(_) if attempts < 10 => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+            Err(e) => return Err(e).context("fetching the swap transaction"),
+        }
+    };
+
+    let meta = tx
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow!("swap transaction {} has no meta", signature))?;
+    let signatures = tx
+        .transaction
+        .transaction
+        .decode()
+        .map_or(1, |decoded| decoded.signatures.len());
+
+    Ok(TxBalances {
+        fee: meta.fee,
+        signatures,
+        block_time: tx.block_time,
+        pre_lamports: meta.pre_balances,
+        post_lamports: meta.post_balances,
+        pre_tokens: parse_token_balances(meta.pre_token_balances)?,
+        post_tokens: parse_token_balances(meta.post_token_balances)?,
+    })
+}
+
+fn parse_token_balances(
+    balances: OptionSerializer<Vec<UiTransactionTokenBalance>>,
+) -> anyhow::Result<Vec<TokenBalance>> {
+    Option::<Vec<UiTransactionTokenBalance>>::from(balances)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|balance| {
+            Ok(TokenBalance {
+                account_index: balance.account_index,
+                mint: Pubkey::from_str(&balance.mint)?,
+                owner: Option::<String>::from(balance.owner)
+                    .map(|owner| Pubkey::from_str(&owner))
+                    .transpose()?,
+                amount: balance.ui_token_amount.amount.parse()?,
+            })
+        })
+        .collect()
+}
+
+/// One row of the trade journal. Amounts are UI units so the journal reads well in a spreadsheet.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    pub block_time: Option<i64>,
+    pub signature: String,
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: String,
+    pub out_amount: String,
+    pub quoted_in_amount: String,
+    pub quoted_out_amount: String,
+    pub effective_price: f64,
+    pub quoted_price: f64,
+    pub slippage_bps: f64,
+    pub network_fee_lamports: u64,
+    pub priority_fee_lamports: u64,
+    pub rent_lamports: u64,
+}
+
+impl From<&Receipt> for JournalEntry {
+    fn from(receipt: &Receipt) -> Self {
+        Self {
+            block_time: receipt.block_time,
+            signature: receipt.signature.to_string(),
+            input_mint: receipt.input_mint.to_string(),
+            output_mint: receipt.output_mint.to_string(),
+            in_amount: raw_to_ui(receipt.in_amount, receipt.input_decimals),
+            out_amount: raw_to_ui(receipt.out_amount, receipt.output_decimals),
+            quoted_in_amount: raw_to_ui(receipt.quoted_in_amount, receipt.input_decimals),
+            quoted_out_amount: raw_to_ui(receipt.quoted_out_amount, receipt.output_decimals),
+            effective_price: receipt.effective_price(),
+            quoted_price: receipt.quoted_price(),
+            slippage_bps: receipt.slippage_bps(),
+            network_fee_lamports: receipt.network_fee,
+            priority_fee_lamports: receipt.priority_fee,
+            rent_lamports: receipt.rent,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "block_time,signature,input_mint,output_mint,in_amount,out_amount,\
+quoted_in_amount,quoted_out_amount,effective_price,quoted_price,slippage_bps,\
+network_fee_lamports,priority_fee_lamports,rent_lamports";
+
+impl JournalEntry {
+    /// None of the fields can contain a comma, so there's no quoting to do
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{:.2},{},{},{}",
+            self.block_time.map_or(String::new(), |t| t.to_string()),
+            self.signature,
+            self.input_mint,
+            self.output_mint,
+            self.in_amount,
+            self.out_amount,
+            self.quoted_in_amount,
+            self.quoted_out_amount,
+            self.effective_price,
+            self.quoted_price,
+            self.slippage_bps,
+            self.network_fee_lamports,
+            self.priority_fee_lamports,
+            self.rent_lamports
+        )
+    }
+}
+
+/// Appends the receipt to the journal at `path`. A `.csv` path gets a CSV row (and the header if
+/// the file is new), anything else gets a line of JSON.
+pub fn append_journal(path: &Path, receipt: &Receipt) -> anyhow::Result<()> {
+    let entry = JournalEntry::from(receipt);
+    let is_csv = path.extension().is_some_and(|ext| ext == "csv");
+    let is_new = !path.exists();
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening journal {}", path.display()))?;
+
+    if is_csv {
+        if is_new {
+            writeln!(file, "{}", CSV_HEADER)?;
+        }
+        writeln!(file, "{}", entry.csv_row())?;
+    } else {
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    }
+    Ok(())
+}
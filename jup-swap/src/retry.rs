@@ -0,0 +1,359 @@
+use crate::cli::RetryArgs;
+use crate::quote::Guardrails;
+use crate::simulate::SimulationFailed;
+use crate::submit::Submitter;
+use crate::swap::{prepare_swap, ResolvedPair, SwapCheck, SwapConfig};
+use crate::verify::JUPITER_V6_PROGRAM_ID;
+use anyhow::{anyhow, Context};
+use jupiter_swap_api_client::quote::QuoteResponse;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::transaction::TransactionError;
+use std::time::Duration;
+
+/// Jupiter v6 `SlippageToleranceExceeded`
+pub const JUPITER_SLIPPAGE_EXCEEDED: u32 = 6001;
+/// Jupiter v6 `ExactOutAmountNotMatched`, the exact out version of running out of slippage
+pub const JUPITER_EXACT_OUT_NOT_MATCHED: u32 = 6017;
+
+/// Why a swap didn't land
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The price moved past our slippage before the swap executed
+    Slippage,
+    /// The blockhash expired before the swap landed
+    Expired,
+    /// We couldn't reach the rpc, the swap may or may not have been sent
+    Transport,
+    /// Anything else, like insufficient funds. Retrying won't help.
+    Other,
+}
+
+impl FailureKind {
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, FailureKind::Other)
+    }
+}
+
+/// Works out why a swap failed from the error building, checking or submitting it returned
+pub fn classify(err: &anyhow::Error) -> FailureKind {
+    match classify_typed(err) {
+        //some errors only carry the program logs as text, Jupiter's failure is in there
+        FailureKind::Other => classify_logs(format!("{:#}", err).lines()),
+        kind => kind,
+    }
+}
+
+fn classify_typed(err: &anyhow::Error) -> FailureKind {
+    if let Some(simulation) = err.downcast_ref::<SimulationFailed>() {
+        return match classify_transaction_error(&simulation.err) {
+            FailureKind::Other => classify_logs(simulation.logs.iter().map(String::as_str)),
+            kind => kind,
+        };
+    }
+
+    if let Some(client_err) = err.downcast_ref::<ClientError>() {
+        if let Some(tx_err) = client_err.get_transaction_error() {
+            return classify_transaction_error(&tx_err);
+        }
+        return match client_err.kind() {
+            ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => FailureKind::Transport,
+            //what send_and_confirm_transaction gives up with once the blockhash is too old
+            _ if client_err
+                .to_string()
+                .contains("unable to confirm transaction") =>
+            {
+                FailureKind::Expired
+            }
+            _ => FailureKind::Other,
+        };
+    }
+
+    match err.downcast_ref::<TransactionError>() {
+        Some(tx_err) => classify_transaction_error(tx_err),
+        None => FailureKind::Other,
+    }
+}
+
+pub fn classify_transaction_error(err: &TransactionError) -> FailureKind {
+    match err {
+        TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(JUPITER_SLIPPAGE_EXCEEDED | JUPITER_EXACT_OUT_NOT_MATCHED),
+        ) => FailureKind::Slippage,
+        TransactionError::BlockhashNotFound => FailureKind::Expired,
+        _ => FailureKind::Other,
+    }
+}
+
+/// `Slippage` if the logs show the Jupiter program failing with a slippage error, like
+/// `Program JUP6... failed: custom program error: 0x1771`, otherwise `Other`
+pub fn classify_logs<'a>(logs: impl IntoIterator<Item = &'a str>) -> FailureKind {
+    let prefix = format!(
+        "Program {} failed: custom program error: ",
+        JUPITER_V6_PROGRAM_ID
+    );
+    let slippage = logs.into_iter().any(|line| {
+        line.trim()
+            .strip_prefix(&prefix)
+            .and_then(|code| u32::from_str_radix(code.trim().trim_start_matches("0x"), 16).ok())
+            .is_some_and(|code| {
+                matches!(
+                    code,
+                    JUPITER_SLIPPAGE_EXCEEDED | JUPITER_EXACT_OUT_NOT_MATCHED
+                )
+            })
+    });
+    if slippage {
+        FailureKind::Slippage
+    } else {
+        FailureKind::Other
+    }
+}
+
+/// How many times to try and how far slippage may be raised along the way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one
+    pub max_attempts: u32,
+    /// Added to the slippage after each slippage failure. 0 requotes at the same slippage.
+    pub slippage_step_bps: u16,
+    /// Slippage is never raised past this
+    pub max_slippage_bps: u16,
+    /// How often to check on a swap that may still land
+    pub poll_interval: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            slippage_step_bps: 0,
+            max_slippage_bps: 0,
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The policy from the retry flags. Without `--max-slippage-bps` slippage stays at the
+    /// pair's.
+    pub fn from_args(args: &RetryArgs, slippage_bps: u16) -> Self {
+        Self {
+            max_attempts: args.max_attempts,
+            slippage_step_bps: args.slippage_step_bps,
+            max_slippage_bps: args.max_slippage_bps.unwrap_or(slippage_bps),
+            ..Self::default()
+        }
+    }
+
+    /// Slippage for the next attempt after a failure of `kind`, `None` once a slippage failure
+    /// would need more than the ceiling
+    pub fn next_slippage_bps(&self, current: u16, kind: FailureKind) -> Option<u16> {
+        if kind != FailureKind::Slippage || self.slippage_step_bps == 0 {
+            return Some(current);
+        }
+        if current >= self.max_slippage_bps {
+            return None;
+        }
+        Some(
+            current
+                .saturating_add(self.slippage_step_bps)
+                .min(self.max_slippage_bps),
+        )
+    }
+}
+
+/// What finally happened to a swap we sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Landing {
+    Landed,
+    Failed(TransactionError),
+    /// Never processed and its blockhash has expired, so it never will be
+    Expired,
+}
+
+/// Waits until the swap can't change state anymore. It's only safe to send a new swap once the
+/// old one has failed or its blockhash has expired, otherwise both could land.
+pub async fn wait_until_final(
+    submitter: &dyn Submitter,
+    signature: &Signature,
+    blockhash: &Hash,
+    poll_interval: Duration,
+) -> anyhow::Result<Landing> {
+    loop {
+        match submitter.signature_status(signature).await? {
+            Some(Ok(())) => return Ok(Landing::Landed),
+            Some(Err(err)) => return Ok(Landing::Failed(err)),
+            None => {}
+        }
+
+        if !submitter.is_blockhash_valid(blockhash).await? {
+            //it could have landed between the two calls, so look one last time
+            return Ok(match submitter.signature_status(signature).await? {
+                Some(Ok(())) => Landing::Landed,
+                Some(Err(err)) => Landing::Failed(err),
+                None => Landing::Expired,
+            });
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// A signed swap that's about to be sent. Modes that keep a state file save it first, so after a
+/// crash they can find out what became of it instead of losing the fill or swapping twice.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingSwap {
+    pub signature: String,
+    pub blockhash: String,
+    /// Raw amounts of the quote the swap was built from
+    pub quoted_in_amount: u64,
+    pub quoted_out_amount: u64,
+}
+
+/// A swap was sent but whether it landed couldn't be found out, because the rpc failed while
+/// checking. It may still land, so a mode with a state file has to keep it pending and settle it
+/// later instead of recording it as failed.
+#[derive(Debug)]
+pub struct Unsettled {
+    pub pending: PendingSwap,
+    /// Why checking on it failed
+    pub source: anyhow::Error,
+}
+
+impl std::fmt::Display for Unsettled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "couldn't find out whether tx {} landed: {:#}",
+            self.pending.signature, self.source
+        )
+    }
+}
+
+impl std::error::Error for Unsettled {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Called with every attempt before it's sent. An error stops the swap, nothing is sent.
+pub type BeforeSend<'a> = dyn Fn(&PendingSwap) -> anyhow::Result<()> + Sync + 'a;
+
+/// The swap that landed and the quote it was built from
+#[derive(Debug, Clone)]
+pub struct SwapOutcome {
+    pub signature: Signature,
+    pub quote: QuoteResponse,
+    pub attempts: u32,
+}
+
+/// Runs the swap, requoting and retrying failed attempts according to `policy`. `quote` is used
+/// for the first attempt, every retry gets a fresh one that has to pass the guardrails again.
+#[allow(clippy::too_many_arguments)]
+pub async fn swap_with_retries(
+    submitter: &dyn Submitter,
+    jup: &JupiterSwapApiClient,
+    wallet: &Keypair,
+    pair: &ResolvedPair,
+    quote: QuoteResponse,
+    guardrails: &Guardrails,
+    config: &(dyn Fn() -> anyhow::Result<SwapConfig> + Sync),
+    checks: &[&dyn SwapCheck],
+    policy: &RetryPolicy,
+    before_send: &BeforeSend<'_>,
+) -> anyhow::Result<SwapOutcome> {
+    let mut quote = quote;
+    let mut slippage_bps = pair.slippage_bps;
+
+    for attempt in 1..=policy.max_attempts.max(1) {
+        if attempt > 1 {
+            let mut request = pair.quote_request();
+            request.slippage_bps = slippage_bps;
+            quote = jup.quote(&request).await.context("requoting")?;
+            guardrails.check(&quote, pair)?;
+            println!(
+                "attempt {} of {}: requoted with {} bps slippage",
+                attempt, policy.max_attempts, slippage_bps
+            );
+        }
+
+        let signed_tx = match prepare_swap(jup, wallet, pair, &quote, config()?, checks).await {
+            Ok(signed_tx) => signed_tx,
+            //nothing was sent, so a stale route can be requoted right away
+            Err(err) => {
+                let kind = classify(&err);
+                if kind != FailureKind::Slippage || attempt >= policy.max_attempts {
+                    return Err(err);
+                }
+                println!(
+                    "swap attempt {} failed before sending ({:?}): {:#}",
+                    attempt, kind, err
+                );
+                slippage_bps = next_slippage_bps(policy, slippage_bps, kind)?;
+                continue;
+            }
+        };
+        let signature = signed_tx.signatures[0];
+        let blockhash = *signed_tx.message.recent_blockhash();
+        let pending = PendingSwap {
+            signature: signature.to_string(),
+            blockhash: blockhash.to_string(),
+            quoted_in_amount: quote.in_amount,
+            quoted_out_amount: quote.out_amount,
+        };
+        before_send(&pending)?;
+        println!("sending tx {} ...", signature);
+
+        let err = match submitter.submit(&signed_tx).await {
+            Ok(signature) => {
+                return Ok(SwapOutcome {
+                    signature,
+                    quote,
+                    attempts: attempt,
+                })
+            }
+            Err(err) => err,
+        };
+
+        let kind = classify(&err);
+        println!("swap attempt {} failed ({:?}): {:#}", attempt, kind, err);
+
+        //an rpc error doesn't mean the swap didn't land, so find out before sending another
+        let landing = wait_until_final(submitter, &signature, &blockhash, policy.poll_interval)
+            .await
+            .map_err(|source| Unsettled { pending, source })?;
+        match landing {
+            Landing::Landed => {
+                return Ok(SwapOutcome {
+                    signature,
+                    quote,
+                    attempts: attempt,
+                })
+            }
+            Landing::Failed(_) | Landing::Expired => {}
+        }
+
+        if !kind.is_retryable() || attempt >= policy.max_attempts {
+            return Err(err.context(format!("swap failed after {} attempt(s)", attempt)));
+        }
+
+        slippage_bps = next_slippage_bps(policy, slippage_bps, kind)?;
+    }
+
+    unreachable!("the last attempt always returns")
+}
+
+fn next_slippage_bps(policy: &RetryPolicy, current: u16, kind: FailureKind) -> anyhow::Result<u16> {
+    policy.next_slippage_bps(current, kind).ok_or_else(|| {
+        anyhow!(
+            "swap exceeded slippage at {} bps, already at --max-slippage-bps",
+            current
+        )
+    })
+}
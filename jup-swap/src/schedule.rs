@@ -0,0 +1,81 @@
+use crate::swap::ResolvedPair;
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Modes that keep trading on their own stop once this many attempts in a row have failed,
+/// whatever is wrong won't fix itself by waiting
+pub const MAX_CONSECUTIVE_FAILURES: usize = 3;
+
+/// Where the scheduled modes get the time from, so tests can run a day of orders instantly
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Unix seconds
+    fn now(&self) -> u64;
+
+    async fn sleep_until(&self, at: u64);
+}
+
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    async fn sleep_until(&self, at: u64) {
+        let now = self.now();
+        if at > now {
+            tokio::time::sleep(Duration::from_secs(at - now)).await;
+        }
+    }
+}
+
+/// Reads a state file, `None` if there isn't one yet
+pub fn load_state<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Option<T>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    serde_json::from_str(&contents)
+        .map(Some)
+        .with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Writes a temporary file and renames it over the old state, so a crash halfway through can't
+/// leave a truncated state behind
+pub fn save_state<T: Serialize>(path: &Path, state: &T) -> anyhow::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(state)?)
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
+
+/// Refuses to pick up a state file that was written for a different pair
+pub fn ensure_same_pair(
+    path: &Path,
+    mode: &str,
+    input_mint: &str,
+    output_mint: &str,
+    pair: &ResolvedPair,
+) -> anyhow::Result<()> {
+    if input_mint != pair.input_mint.to_string() || output_mint != pair.output_mint.to_string() {
+        return Err(anyhow!(
+            "{} is the state of a {} -> {} {}, use another --state for this pair",
+            path.display(),
+            input_mint,
+            output_mint,
+            mode
+        ));
+    }
+    Ok(())
+}
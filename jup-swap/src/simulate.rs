@@ -0,0 +1,386 @@
+use crate::message::{resolve_accounts, MessageAccount};
+use crate::swap::{ResolvedPair, SwapCheck};
+use crate::tokens::{raw_to_ui, TOKEN_2022_PROGRAM_ID};
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use jupiter_swap_api_client::quote::QuoteResponse;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::account::Account;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::solana_program::program_pack::Pack;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Where the wallet's balance of one side of the swap lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceSource {
+    /// Native SOL that Jupiter wraps and unwraps for us, so it's the wallet's lamports
+    Lamports(Pubkey),
+    /// A token account, usually the wallet's ATA for the mint
+    TokenAccount(Pubkey),
+}
+
+impl BalanceSource {
+    pub fn address(&self) -> Pubkey {
+        match self {
+            BalanceSource::Lamports(address) | BalanceSource::TokenAccount(address) => *address,
+        }
+    }
+
+    /// The balance this source holds in `account`. An account that doesn't exist yet (an ATA the
+    /// swap is about to create) has a balance of zero.
+    pub fn balance(&self, account: Option<&Account>) -> anyhow::Result<u64> {
+        let Some(account) = account else {
+            return Ok(0);
+        };
+
+        match self {
+            BalanceSource::Lamports(_) => Ok(account.lamports),
+            BalanceSource::TokenAccount(address) => {
+                //Token-2022 extensions come after the base layout, so this reads both programs
+                if account.data.len() < spl_token::state::Account::LEN {
+                    return Err(anyhow!("{} is not a token account", address));
+                }
+                let token_account = spl_token::state::Account::unpack_from_slice(
+                    &account.data[..spl_token::state::Account::LEN],
+                )?;
+                Ok(token_account.amount)
+            }
+        }
+    }
+}
+
+/// What the simulated swap actually does to the wallet
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub input: BalanceSource,
+    pub output: BalanceSource,
+    /// Raw amount of the input token that left the wallet
+    pub input_spent: i128,
+    /// Raw amount of the output token that arrived in the wallet
+    pub output_received: i128,
+    /// Transaction fee in lamports, already backed out of lamport deltas
+    pub fee: u64,
+    pub units_consumed: Option<u64>,
+    /// Writable token accounts of the wallet the swap has no business touching
+    pub unexpected_writable: Vec<Pubkey>,
+}
+
+impl SimulationReport {
+    /// Builds the report from the balances before and after simulating.
+    /// Lamport balances also pay the transaction fee, so it's added back to get the swap amounts.
+    pub fn from_balances(
+        input: BalanceSource,
+        output: BalanceSource,
+        (input_pre, input_post): (u64, u64),
+        (output_pre, output_post): (u64, u64),
+        fee: u64,
+    ) -> Self {
+        let fee_on = |source: &BalanceSource| match source {
+            BalanceSource::Lamports(_) => fee as i128,
+            BalanceSource::TokenAccount(_) => 0,
+        };
+
+        Self {
+            input,
+            output,
+            input_spent: input_pre as i128 - input_post as i128 - fee_on(&input),
+            output_received: output_post as i128 - output_pre as i128 + fee_on(&output),
+            fee,
+            units_consumed: None,
+            unexpected_writable: vec![],
+        }
+    }
+
+    /// Refuses the swap if the simulated output is worse than the quote allows, or if it writes
+    /// to wallet accounts it shouldn't.
+    ///
+    /// Native SOL spent isn't checked against the quote because it also pays rent for any ATAs the
+    /// swap creates, so it's only reported.
+    pub fn check(&self, quote: &QuoteResponse, pair: &ResolvedPair) -> anyhow::Result<()> {
+        if !self.unexpected_writable.is_empty() {
+            return Err(anyhow!(
+                "swap writes to wallet token accounts outside the pair: {}, not sending",
+                self.unexpected_writable
+                    .iter()
+                    .map(|account| account.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        //for exact out the out amount is fixed and other_amount_threshold caps the input instead
+        let (min_received, max_spent) = if pair.exact_out {
+            (quote.out_amount, quote.other_amount_threshold)
+        } else {
+            (quote.other_amount_threshold, quote.in_amount)
+        };
+
+        if self.output_received < min_received as i128 {
+            return Err(anyhow!(
+                "simulated output {} is below the quoted minimum {}, not sending",
+                ui_delta(self.output_received, pair.output_decimals),
+                raw_to_ui(min_received, pair.output_decimals)
+            ));
+        }
+
+        if matches!(self.input, BalanceSource::TokenAccount(_))
+            && self.input_spent > max_spent as i128
+        {
+            return Err(anyhow!(
+                "simulated input {} is above the quoted maximum {}, not sending",
+                ui_delta(self.input_spent, pair.input_decimals),
+                raw_to_ui(max_spent, pair.input_decimals)
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn render(&self, pair: &ResolvedPair) -> String {
+        let mut out = String::new();
+        writeln!(out, "simulation").unwrap();
+        writeln!(
+            out,
+            "  {:<14} {}",
+            "spent",
+            ui_delta(self.input_spent, pair.input_decimals)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "  {:<14} {}",
+            "received",
+            ui_delta(self.output_received, pair.output_decimals)
+        )
+        .unwrap();
+        writeln!(out, "  {:<14} {} lamports", "fee", self.fee).unwrap();
+        if let Some(units) = self.units_consumed {
+            writeln!(out, "  {:<14} {}", "compute units", units).unwrap();
+        }
+        out
+    }
+}
+
+/// The accounts holding each side of the pair for `wallet`. With wrap and unwrap on, SOL comes
+/// out of and goes back into the wallet's lamports rather than a wSOL account.
+pub async fn balance_sources(
+    rpc: &RpcClient,
+    wallet: &Pubkey,
+    pair: &ResolvedPair,
+    wrap_and_unwrap_sol: bool,
+    destination_token_account: Option<Pubkey>,
+) -> anyhow::Result<(BalanceSource, BalanceSource)> {
+    let mints = rpc
+        .get_multiple_accounts(&[pair.input_mint, pair.output_mint])
+        .await
+        .context("fetching mints")?;
+
+    let mut sources = vec![];
+    for (mint, account) in [pair.input_mint, pair.output_mint].iter().zip(mints) {
+        let account = account.ok_or_else(|| anyhow!("mint {} not found", mint))?;
+        let source = if wrap_and_unwrap_sol && *mint == spl_token::native_mint::ID {
+            BalanceSource::Lamports(*wallet)
+        } else {
+            BalanceSource::TokenAccount(get_associated_token_address_with_program_id(
+                wallet,
+                mint,
+                &account.owner,
+            ))
+        };
+        sources.push(source);
+    }
+
+    let output = match destination_token_account {
+        Some(destination) => BalanceSource::TokenAccount(destination),
+        None => sources[1],
+    };
+    Ok((sources[0], output))
+}
+
+/// Writable accounts in the message that are token accounts of the wallet but not one of the two
+/// the swap is supposed to move
+pub fn unexpected_writable(
+    accounts: &[MessageAccount],
+    wallet_token_accounts: &HashSet<Pubkey>,
+    expected: &[Pubkey],
+) -> Vec<Pubkey> {
+    accounts
+        .iter()
+        .filter(|account| account.writable)
+        .map(|account| account.pubkey)
+        .filter(|pubkey| wallet_token_accounts.contains(pubkey) && !expected.contains(pubkey))
+        .collect()
+}
+
+/// Every SPL Token and Token-2022 account the wallet owns
+pub async fn wallet_token_accounts(
+    rpc: &RpcClient,
+    wallet: &Pubkey,
+) -> anyhow::Result<HashSet<Pubkey>> {
+    let mut accounts = HashSet::new();
+    for program in [spl_token::ID, TOKEN_2022_PROGRAM_ID] {
+        for keyed in rpc
+            .get_token_accounts_by_owner(wallet, TokenAccountsFilter::ProgramId(program))
+            .await
+            .context("fetching wallet token accounts")?
+        {
+            accounts.insert(Pubkey::from_str(&keyed.pubkey)?);
+        }
+    }
+    Ok(accounts)
+}
+
+/// The swap failed in simulation, with the logs to tell why. Kept typed so the retry loop can
+/// requote a swap whose route went stale before it was ever sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationFailed {
+    pub err: TransactionError,
+    pub logs: Vec<String>,
+}
+
+impl std::fmt::Display for SimulationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "swap simulation failed: {}\n{}",
+            self.err,
+            self.logs.join("\n")
+        )
+    }
+}
+
+impl std::error::Error for SimulationFailed {}
+
+/// Simulates the unsigned swap transaction and measures what it does to the wallet's balances
+pub async fn simulate_swap(
+    rpc: &RpcClient,
+    tx: &VersionedTransaction,
+    wallet: &Pubkey,
+    pair: &ResolvedPair,
+    wrap_and_unwrap_sol: bool,
+    destination_token_account: Option<Pubkey>,
+) -> anyhow::Result<SimulationReport> {
+    let (input, output) = balance_sources(
+        rpc,
+        wallet,
+        pair,
+        wrap_and_unwrap_sol,
+        destination_token_account,
+    )
+    .await?;
+    let watched = [input.address(), output.address()];
+
+    let accounts = resolve_accounts(rpc, &tx.message).await?;
+    let owned = wallet_token_accounts(rpc, wallet).await?;
+
+    let pre = rpc
+        .get_multiple_accounts(&watched)
+        .await
+        .context("fetching balances before simulating")?;
+
+    let fee = match &tx.message {
+        VersionedMessage::Legacy(message) => rpc.get_fee_for_message(message).await?,
+        VersionedMessage::V0(message) => rpc.get_fee_for_message(message).await?,
+    };
+
+    let simulation = rpc
+        .simulate_transaction_with_config(
+            tx,
+            RpcSimulateTransactionConfig {
+                //the transaction isn't signed yet
+                sig_verify: false,
+                commitment: Some(rpc.commitment()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: watched.iter().map(|address| address.to_string()).collect(),
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .await
+        .context("simulating swap")?
+        .value;
+
+    if let Some(err) = simulation.err {
+        return Err(SimulationFailed {
+            err,
+            logs: simulation.logs.unwrap_or_default(),
+        }
+        .into());
+    }
+
+    let post: Vec<Option<Account>> = simulation
+        .accounts
+        .ok_or_else(|| anyhow!("simulation didn't return the requested accounts"))?
+        .into_iter()
+        .map(|account| account.and_then(|account| account.decode::<Account>()))
+        .collect();
+
+    let mut report = SimulationReport::from_balances(
+        input,
+        output,
+        (
+            input.balance(pre[0].as_ref())?,
+            input.balance(post[0].as_ref())?,
+        ),
+        (
+            output.balance(pre[1].as_ref())?,
+            output.balance(post[1].as_ref())?,
+        ),
+        fee,
+    );
+    report.units_consumed = simulation.units_consumed;
+    report.unexpected_writable = unexpected_writable(&accounts, &owned, &watched);
+
+    Ok(report)
+}
+
+/// Runs `simulate_swap` right before signing and refuses to send if the result doesn't hold up
+pub struct SimulationCheck<'a> {
+    pub rpc: &'a RpcClient,
+    pub wrap_and_unwrap_sol: bool,
+    pub destination_token_account: Option<Pubkey>,
+}
+
+#[async_trait]
+impl SwapCheck for SimulationCheck<'_> {
+    async fn check(
+        &self,
+        wallet: &Pubkey,
+        pair: &ResolvedPair,
+        quote: &QuoteResponse,
+        tx: &VersionedTransaction,
+    ) -> anyhow::Result<()> {
+        let report = simulate_swap(
+            self.rpc,
+            tx,
+            wallet,
+            pair,
+            self.wrap_and_unwrap_sol,
+            self.destination_token_account,
+        )
+        .await?;
+        println!("{}", report.render(pair));
+        report.check(quote, pair)
+    }
+}
+
+/// Signed raw delta as a UI amount
+fn ui_delta(amount: i128, decimals: u8) -> String {
+    let ui = raw_to_ui(amount.unsigned_abs().min(u64::MAX as u128) as u64, decimals);
+    if amount < 0 {
+        format!("-{}", ui)
+    } else {
+        ui
+    }
+}
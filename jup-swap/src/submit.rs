@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{self, VersionedTransaction};
+
+/// Something that can land a signed transaction. The real thing is an `RpcClient`, the tests
+/// use a `solana-program-test` bank so the whole swap path runs without a network.
+#[async_trait]
+pub trait Submitter: Send + Sync {
+    /// Sends the signed transaction and waits until it is confirmed
+    async fn submit(&self, tx: &VersionedTransaction) -> anyhow::Result<Signature>;
+
+    /// `None` if the transaction hasn't been processed (yet), otherwise whether it succeeded
+    async fn signature_status(
+        &self,
+        signature: &Signature,
+    ) -> anyhow::Result<Option<transaction::Result<()>>>;
+
+    /// Whether a transaction using this blockhash could still land
+    async fn is_blockhash_valid(&self, blockhash: &Hash) -> anyhow::Result<bool>;
+}
+
+#[async_trait]
+impl Submitter for RpcClient {
+    async fn submit(&self, tx: &VersionedTransaction) -> anyhow::Result<Signature> {
+        Ok(self.send_and_confirm_transaction(tx).await?)
+    }
+
+    async fn signature_status(
+        &self,
+        signature: &Signature,
+    ) -> anyhow::Result<Option<transaction::Result<()>>> {
+        Ok(self.get_signature_status(signature).await?)
+    }
+
+    async fn is_blockhash_valid(&self, blockhash: &Hash) -> anyhow::Result<bool> {
+        Ok(RpcClient::is_blockhash_valid(self, blockhash, self.commitment()).await?)
+    }
+}
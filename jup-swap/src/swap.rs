@@ -0,0 +1,208 @@
+use crate::cli::{PairArgs, TxArgs};
+use crate::submit::Submitter;
+use crate::tokens::{mint_decimals, ui_to_raw, TokenRegistry};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use jupiter_swap_api_client::quote::{QuoteRequest, QuoteResponse, SwapMode};
+use jupiter_swap_api_client::swap::{SwapRequest, SwapResponse};
+use jupiter_swap_api_client::transaction_config::{
+    ComputeUnitPriceMicroLamports, TransactionConfig,
+};
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+use std::str::FromStr;
+
+/// A pair resolved from the CLI args: mints, their decimals and the raw amount
+#[derive(Debug, Clone)]
+pub struct ResolvedPair {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub input_decimals: u8,
+    pub output_decimals: u8,
+    /// Raw amount of the input token, or of the output token for exact out
+    pub amount: u64,
+    pub slippage_bps: u16,
+    pub exact_out: bool,
+}
+
+impl ResolvedPair {
+    /// Resolves symbols through the registry and converts the UI amount using the on-chain decimals
+    pub async fn resolve(
+        args: &PairArgs,
+        registry: &TokenRegistry,
+        rpc: &RpcClient,
+    ) -> anyhow::Result<Self> {
+        let input_mint = registry.resolve(&args.input)?;
+        let output_mint = registry.resolve(&args.output)?;
+        let input_decimals = mint_decimals(rpc, &input_mint).await?;
+        let output_decimals = mint_decimals(rpc, &output_mint).await?;
+
+        //exact out amounts are denominated in the output token
+        let amount_decimals = if args.exact_out {
+            output_decimals
+        } else {
+            input_decimals
+        };
+        let amount = ui_to_raw(&args.amount, amount_decimals)?;
+
+        Ok(Self {
+            input_mint,
+            output_mint,
+            input_decimals,
+            output_decimals,
+            amount,
+            slippage_bps: args.slippage_bps,
+            exact_out: args.exact_out,
+        })
+    }
+
+    pub fn quote_request(&self) -> QuoteRequest {
+        QuoteRequest {
+            amount: self.amount,
+            input_mint: self.input_mint,
+            output_mint: self.output_mint,
+            slippage_bps: self.slippage_bps,
+            swap_mode: self.exact_out.then_some(SwapMode::ExactOut),
+            ..QuoteRequest::default()
+        }
+    }
+}
+
+/// Jupiter's `TransactionConfig` plus the `/swap` options the client crate has no field for
+#[derive(Debug, Default)]
+pub struct SwapConfig {
+    pub transaction: TransactionConfig,
+    /// Let Jupiter simulate the swap and set the compute unit limit to what it used
+    pub dynamic_compute_unit_limit: bool,
+}
+
+/// The `/swap` request body with the options `SwapRequest` doesn't have
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapBody {
+    #[serde(flatten)]
+    request: SwapRequest,
+    dynamic_compute_unit_limit: bool,
+}
+
+/// Maps the wrap/unwrap, shared accounts, destination and compute budget flags onto Jupiter's
+/// `TransactionConfig`. The compute unit price is worked out separately, see `compute_unit_price`.
+pub fn transaction_config(
+    args: &TxArgs,
+    compute_unit_price: Option<u64>,
+) -> anyhow::Result<SwapConfig> {
+    let destination_token_account = args
+        .destination_token_account
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|e| anyhow!("bad --destination-token-account: {}", e))?;
+
+    Ok(SwapConfig {
+        transaction: TransactionConfig {
+            wrap_and_unwrap_sol: args.wrap_unwrap_sol,
+            use_shared_accounts: args.use_shared_accounts,
+            destination_token_account,
+            compute_unit_price_micro_lamports: compute_unit_price
+                .map(ComputeUnitPriceMicroLamports::MicroLamports),
+            ..TransactionConfig::default()
+        },
+        dynamic_compute_unit_limit: args.dynamic_compute_unit_limit,
+    })
+}
+
+/// Asks Jupiter to build the swap transaction for `quote`. The transaction comes back unsigned.
+/// Posted here rather than through `JupiterSwapApiClient::swap` so the options the client crate
+/// doesn't know about go along.
+pub async fn build_swap_transaction(
+    jup: &JupiterSwapApiClient,
+    user: Pubkey,
+    quote: QuoteResponse,
+    config: SwapConfig,
+) -> anyhow::Result<VersionedTransaction> {
+    let body = SwapBody {
+        request: SwapRequest {
+            user_public_key: user,
+            quote_response: quote,
+            config: config.transaction,
+        },
+        dynamic_compute_unit_limit: config.dynamic_compute_unit_limit,
+    };
+    let response = reqwest::Client::new()
+        .post(format!("{}/swap", jup.base_path))
+        .json(&body)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "swap request failed with {}: {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        ));
+    }
+    let swap_response: SwapResponse = response.json().await?;
+
+    let versioned_tx: VersionedTransaction = bincode::deserialize(&swap_response.swap_transaction)?;
+    Ok(versioned_tx)
+}
+
+/// Signs the message Jupiter built with our wallet
+pub fn sign_swap_transaction(
+    tx: VersionedTransaction,
+    wallet: &Keypair,
+) -> anyhow::Result<VersionedTransaction> {
+    Ok(VersionedTransaction::try_new(tx.message, &[wallet])?)
+}
+
+/// A check run on the transaction Jupiter built for `quote` before we sign it. Returning an error
+/// aborts the swap with nothing signed.
+#[async_trait]
+pub trait SwapCheck: Send + Sync {
+    async fn check(
+        &self,
+        wallet: &Pubkey,
+        pair: &ResolvedPair,
+        quote: &QuoteResponse,
+        tx: &VersionedTransaction,
+    ) -> anyhow::Result<()>;
+}
+
+/// Builds the swap for `quote`, runs every check on it and signs it
+pub async fn prepare_swap(
+    jup: &JupiterSwapApiClient,
+    wallet: &Keypair,
+    pair: &ResolvedPair,
+    quote: &QuoteResponse,
+    config: SwapConfig,
+    checks: &[&dyn SwapCheck],
+) -> anyhow::Result<VersionedTransaction> {
+    let swap_tx = build_swap_transaction(jup, wallet.pubkey(), quote.clone(), config).await?;
+    println!("got swap response, checking tx ...");
+
+    for check in checks {
+        check.check(&wallet.pubkey(), pair, quote, &swap_tx).await?;
+    }
+
+    sign_swap_transaction(swap_tx, wallet)
+}
+
+/// Builds the swap for `quote`, runs every check on it, then signs and submits it
+pub async fn execute_swap(
+    submitter: &dyn Submitter,
+    jup: &JupiterSwapApiClient,
+    wallet: &Keypair,
+    pair: &ResolvedPair,
+    quote: QuoteResponse,
+    config: SwapConfig,
+    checks: &[&dyn SwapCheck],
+) -> anyhow::Result<Signature> {
+    let signed_tx = prepare_swap(jup, wallet, pair, &quote, config, checks).await?;
+    println!("sending tx ...");
+
+    submitter.submit(&signed_tx).await
+}
@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Context};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use spl_token::solana_program::program_pack::Pack;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Maps token symbols like `SOL` or `BONK` to their mint addresses.
+/// Loaded from a JSON file of `"SYMBOL": "mint address"` pairs, see `tokens.json`.
+#[derive(Debug, Default, Clone)]
+pub struct TokenRegistry {
+    tokens: BTreeMap<String, Pubkey>,
+}
+
+impl TokenRegistry {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading token registry {}", path.display()))?;
+        Self::from_json(&contents)
+    }
+
+    pub fn from_json(contents: &str) -> anyhow::Result<Self> {
+        let raw: BTreeMap<String, String> = serde_json::from_str(contents)?;
+
+        let mut tokens = BTreeMap::new();
+        for (symbol, mint) in raw {
+            let mint = Pubkey::from_str(&mint)
+                .map_err(|e| anyhow!("bad mint for {} in token registry: {}", symbol, e))?;
+            tokens.insert(symbol.to_uppercase(), mint);
+        }
+
+        Ok(Self { tokens })
+    }
+
+    /// Accepts either a symbol from the registry (case insensitive) or a raw mint address
+    pub fn resolve(&self, token: &str) -> anyhow::Result<Pubkey> {
+        if let Some(mint) = self.tokens.get(&token.to_uppercase()) {
+            return Ok(*mint);
+        }
+
+        Pubkey::from_str(token).map_err(|_| {
+            anyhow!(
+                "unknown token {}, use a mint address or one of: {}",
+                token,
+                self.tokens.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+
+    /// Symbol for a mint if it's in the registry, otherwise the mint address
+    pub fn symbol(&self, mint: &Pubkey) -> String {
+        self.tokens
+            .iter()
+            .find(|(_, m)| *m == mint)
+            .map_or(mint.to_string(), |(symbol, _)| symbol.clone())
+    }
+}
+
+/// Reads the decimals off the mint account. Works for both SPL Token and Token-2022 mints since
+/// the extensions are stored after the base mint layout.
+pub async fn mint_decimals(rpc: &RpcClient, mint: &Pubkey) -> anyhow::Result<u8> {
+    let account = rpc
+        .get_account(mint)
+        .await
+        .with_context(|| format!("fetching mint {}", mint))?;
+
+    if account.owner != spl_token::ID && account.owner != TOKEN_2022_PROGRAM_ID {
+        return Err(anyhow!("{} is not a token mint", mint));
+    }
+    if account.data.len() < spl_token::state::Mint::LEN {
+        return Err(anyhow!("mint account {} is too small", mint));
+    }
+
+    let mint =
+        spl_token::state::Mint::unpack_from_slice(&account.data[..spl_token::state::Mint::LEN])?;
+    Ok(mint.decimals)
+}
+
+/// Converts a UI amount like `1.5` into raw base units (`1_500_000_000` for 9 decimals).
+/// Done on the string so there's no float rounding.
+pub fn ui_to_raw(amount: &str, decimals: u8) -> anyhow::Result<u64> {
+    let amount = amount.trim();
+    let (whole, frac) = amount.split_once('.').unwrap_or((amount, ""));
+
+    if whole.is_empty() && frac.is_empty() {
+        return Err(anyhow!("invalid amount {}", amount));
+    }
+    if !whole.chars().all(|c| c.is_ascii_digit()) || !frac.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("invalid amount {}", amount));
+    }
+    if frac.len() > decimals as usize {
+        return Err(anyhow!(
+            "amount {} has more than {} decimal places",
+            amount,
+            decimals
+        ));
+    }
+
+    let scale = 10u64
+        .checked_pow(decimals as u32)
+        .ok_or_else(|| anyhow!("too many decimals {}", decimals))?;
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let frac_raw: u64 = if frac.is_empty() {
+        0
+    } else {
+        format!("{:0<width$}", frac, width = decimals as usize).parse()?
+    };
+
+    whole
+        .checked_mul(scale)
+        .and_then(|w| w.checked_add(frac_raw))
+        .ok_or_else(|| anyhow!("amount {} is too large", amount))
+}
+
+/// Formats raw base units as a UI amount, trimming trailing zeros
+pub fn raw_to_ui(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (whole, frac) = digits.split_at(digits.len() - decimals as usize);
+    let frac = frac.trim_end_matches('0');
+
+    if frac.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, frac)
+    }
+}
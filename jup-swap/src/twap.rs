@@ -0,0 +1,283 @@
+use crate::executor::{Executor, Fill};
+use crate::limit::quoted_price;
+use crate::quote::{price_impact_pct, Guardrails};
+use crate::schedule::{Clock, MAX_CONSECUTIVE_FAILURES};
+use crate::swap::ResolvedPair;
+use crate::tokens::{raw_to_ui, TokenRegistry};
+use jupiter_swap_api_client::quote::QuoteResponse;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use std::fmt::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Most a slice may be moved off its even spacing, in percent of the interval between slices.
+/// Up to half an interval either way keeps the slices in order.
+pub const MAX_JITTER_PCT: u8 = 50;
+
+/// How a large swap is split up over time and when to give up on it
+#[derive(Debug, Clone)]
+pub struct TwapPlan {
+    /// Raw input amount across all slices
+    pub total: u64,
+    pub slices: u32,
+    pub duration: Duration,
+    /// How far each slice may be moved off its even spacing, in percent of an interval
+    pub jitter_pct: u8,
+    /// Stop before a slice would take the price impact of all slices so far past this percent
+    pub max_cumulative_impact_pct: Option<f64>,
+    /// Stop before a slice would drag the average price more than this percent below the first
+    /// slice's quote
+    pub max_price_degradation_pct: Option<f64>,
+}
+
+impl TwapPlan {
+    /// Raw input amount of every slice. Slices are as even as they can be, the first ones take
+    /// the remainder.
+    pub fn amounts(&self) -> Vec<u64> {
+        let slices = self.slices.max(1) as u64;
+        let (base, remainder) = (self.total / slices, self.total % slices);
+        (0..slices).map(|i| base + (i < remainder) as u64).collect()
+    }
+
+    /// Unix seconds every slice is due at. Slice `i` is `i` intervals after `start`, moved by up
+    /// to `jitter_pct` percent of an interval either way, but never outside the duration.
+    /// `random` returns numbers in `[0, 1)`.
+    pub fn times(&self, start: u64, random: &mut dyn FnMut() -> f64) -> Vec<u64> {
+        let slices = self.slices.max(1);
+        let interval = self.duration.as_secs_f64() / slices as f64;
+        let jitter = self.jitter_pct.min(MAX_JITTER_PCT) as f64 / 100.0;
+        let end = start as f64 + self.duration.as_secs_f64();
+
+        (0..slices)
+            .map(|i| {
+                let shift = (random() * 2.0 - 1.0) * jitter * interval;
+                let at = start as f64 + i as f64 * interval + shift;
+                at.clamp(start as f64, end).round() as u64
+            })
+            .collect()
+    }
+
+    /// Why the swap should stop before this slice, `None` if it can go ahead. `impact` is the
+    /// slice's price impact in percent.
+    pub fn abort_reason(
+        &self,
+        summary: &TwapSummary,
+        quote: &QuoteResponse,
+        pair: &ResolvedPair,
+        impact: f64,
+    ) -> Option<String> {
+        if let Some(max) = self.max_cumulative_impact_pct {
+            let cumulative = summary.cumulative_impact_pct + impact;
+            if cumulative > max {
+                return Some(format!(
+                    "cumulative price impact would reach {:.4}%, above {}%",
+                    cumulative, max
+                ));
+            }
+        }
+
+        if let (Some(max), Some(reference)) =
+            (self.max_price_degradation_pct, summary.reference_price)
+        {
+            let in_amount = summary.in_amount() + quote.in_amount;
+            let out_amount = summary.out_amount() + quote.out_amount;
+            let average = ui_price(in_amount, out_amount, pair);
+            let degradation = (reference - average) / reference * 100.0;
+            if degradation > max {
+                return Some(format!(
+                    "average price would be {:.4}% below the first slice's, above {}%",
+                    degradation, max
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+/// Output per input in UI units
+fn ui_price(in_amount: u64, out_amount: u64, pair: &ResolvedPair) -> f64 {
+    if in_amount == 0 {
+        return 0.0;
+    }
+    let ui_in = in_amount as f64 / 10f64.powi(pair.input_decimals as i32);
+    let ui_out = out_amount as f64 / 10f64.powi(pair.output_decimals as i32);
+    ui_out / ui_in
+}
+
+/// Random numbers in `[0, 1)` for `TwapPlan::times`, seeded from the system clock. This isn't
+/// meant to be unpredictable, only to keep the slices off a fixed grid.
+pub fn time_seeded_random() -> impl FnMut() -> f64 {
+    let mut x = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+        | 1;
+    move || {
+        //xorshift64
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// What a TWAP swap did
+#[derive(Debug, Clone, Default)]
+pub struct TwapSummary {
+    pub fills: Vec<Fill>,
+    /// Slices that were due but didn't go through
+    pub failed: u32,
+    /// Price impact of the filled slices' quotes added up, in percent
+    pub cumulative_impact_pct: f64,
+    /// Output per input of the first slice's quote, what the average is measured against
+    pub reference_price: Option<f64>,
+    /// Why the swap stopped before its last slice
+    pub aborted: Option<String>,
+}
+
+impl TwapSummary {
+    pub fn in_amount(&self) -> u64 {
+        self.fills.iter().map(|fill| fill.in_amount).sum()
+    }
+
+    pub fn out_amount(&self) -> u64 {
+        self.fills.iter().map(|fill| fill.out_amount).sum()
+    }
+
+    /// Volume weighted average execution price, output per input in UI units
+    pub fn vwap(&self, pair: &ResolvedPair) -> Option<f64> {
+        if self.fills.is_empty() {
+            return None;
+        }
+        Some(ui_price(self.in_amount(), self.out_amount(), pair))
+    }
+
+    pub fn render(&self, pair: &ResolvedPair, total: u64, registry: &TokenRegistry) -> String {
+        let in_symbol = registry.symbol(&pair.input_mint);
+        let out_symbol = registry.symbol(&pair.output_mint);
+        let mut out = String::new();
+
+        writeln!(
+            out,
+            "{:<16} {} of {} {} in {} slices ({} failed)",
+            "sold",
+            raw_to_ui(self.in_amount(), pair.input_decimals),
+            raw_to_ui(total, pair.input_decimals),
+            in_symbol,
+            self.fills.len(),
+            self.failed
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{:<16} {} {}",
+            "received",
+            raw_to_ui(self.out_amount(), pair.output_decimals),
+            out_symbol
+        )
+        .unwrap();
+        if let Some(vwap) = self.vwap(pair) {
+            writeln!(out, "{:<16} {} {}/{}", "vwap", vwap, out_symbol, in_symbol).unwrap();
+        }
+        if let Some(reference) = self.reference_price {
+            writeln!(
+                out,
+                "{:<16} {} {}/{}",
+                "first quote", reference, out_symbol, in_symbol
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "{:<16} {:.4}%",
+            "total impact", self.cumulative_impact_pct
+        )
+        .unwrap();
+        if let Some(reason) = &self.aborted {
+            writeln!(out, "{:<16} {}", "aborted", reason).unwrap();
+        }
+        out
+    }
+}
+
+/// Runs a TWAP swap slice by slice
+pub struct Twap<'a> {
+    pub jup: &'a JupiterSwapApiClient,
+    pub executor: &'a dyn Executor,
+    pub clock: &'a dyn Clock,
+    /// The pair to sell. Its amount is replaced by each slice's.
+    pub pair: ResolvedPair,
+    pub plan: TwapPlan,
+}
+
+impl Twap<'_> {
+    /// Waits for each slice in `schedule`, a list of due times and raw amounts, requotes it and
+    /// swaps it unless the plan's limits say to stop. A failed slice is skipped, after
+    /// `MAX_CONSECUTIVE_FAILURES` in a row the swap stops.
+    pub async fn run(&self, schedule: &[(u64, u64)]) -> TwapSummary {
+        let mut summary = TwapSummary::default();
+        let mut failures = 0;
+
+        for (i, (at, amount)) in schedule.iter().enumerate() {
+            self.clock.sleep_until(*at).await;
+
+            let pair = ResolvedPair {
+                amount: *amount,
+                ..self.pair.clone()
+            };
+            match self.slice(&pair, &mut summary).await {
+                Ok(Some(fill)) => {
+                    failures = 0;
+                    println!(
+                        "slice {} of {}: {} -> {}",
+                        i + 1,
+                        schedule.len(),
+                        raw_to_ui(fill.in_amount, pair.input_decimals),
+                        raw_to_ui(fill.out_amount, pair.output_decimals)
+                    );
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    failures += 1;
+                    summary.failed += 1;
+                    println!("slice {} of {} failed: {:#}", i + 1, schedule.len(), e);
+                    if failures >= MAX_CONSECUTIVE_FAILURES {
+                        summary.aborted = Some(format!(
+                            "{} slices in a row failed",
+                            MAX_CONSECUTIVE_FAILURES
+                        ));
+                        break;
+                    }
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// Quotes and swaps one slice. `None` if the plan's limits stopped the swap.
+    async fn slice(
+        &self,
+        pair: &ResolvedPair,
+        summary: &mut TwapSummary,
+    ) -> anyhow::Result<Option<Fill>> {
+        let quote = self.jup.quote(&pair.quote_request()).await?;
+        let impact = price_impact_pct(&quote)?;
+        if summary.reference_price.is_none() {
+            summary.reference_price = Some(quoted_price(&quote, pair));
+        }
+
+        if let Some(reason) = self.plan.abort_reason(summary, &quote, pair, impact) {
+            summary.aborted = Some(reason);
+            return Ok(None);
+        }
+
+        let fill = self
+            .executor
+            .execute(pair, quote, &Guardrails::default())
+            .await?;
+        summary.cumulative_impact_pct += impact;
+        summary.fills.push(fill.clone());
+        Ok(Some(fill))
+    }
+}
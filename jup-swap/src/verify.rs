@@ -0,0 +1,382 @@
+use crate::message::{resolve_accounts, MessageAccount};
+use crate::swap::{ResolvedPair, SwapCheck};
+use crate::tokens::TOKEN_2022_PROGRAM_ID;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use jupiter_swap_api_client::quote::QuoteResponse;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction::SystemInstruction;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::{compute_budget, system_program};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::instruction::TokenInstruction;
+use std::fmt::Write;
+
+pub const JUPITER_V6_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+/// Anchor discriminators of the Jupiter v6 route instructions, for naming them in the breakdown
+const JUPITER_ROUTES: [([u8; 8], &str); 6] = [
+    ([229, 23, 203, 151, 122, 227, 173, 42], "route"),
+    (
+        [193, 32, 155, 51, 65, 214, 156, 129],
+        "shared_accounts_route",
+    ),
+    ([208, 51, 239, 151, 123, 43, 237, 92], "exact_out_route"),
+    (
+        [176, 209, 105, 168, 154, 125, 69, 62],
+        "shared_accounts_exact_out_route",
+    ),
+    (
+        [150, 86, 71, 116, 167, 93, 14, 104],
+        "route_with_token_ledger",
+    ),
+    (
+        [230, 121, 143, 80, 119, 159, 106, 170],
+        "shared_accounts_route_with_token_ledger",
+    ),
+];
+
+/// Programs a Jupiter swap transaction is allowed to invoke at the top level
+#[derive(Debug, Clone)]
+pub struct Allowlist {
+    programs: Vec<(Pubkey, String)>,
+}
+
+impl Default for Allowlist {
+    fn default() -> Self {
+        Self {
+            programs: vec![
+                (JUPITER_V6_PROGRAM_ID, "jupiter v6".to_string()),
+                (spl_token::ID, "token".to_string()),
+                (TOKEN_2022_PROGRAM_ID, "token-2022".to_string()),
+                (
+                    spl_associated_token_account::ID,
+                    "associated token account".to_string(),
+                ),
+                (compute_budget::ID, "compute budget".to_string()),
+                (system_program::ID, "system".to_string()),
+            ],
+        }
+    }
+}
+
+impl Allowlist {
+    /// Allows an extra program on top of the defaults
+    pub fn allow(&mut self, program: Pubkey) {
+        if self.name(&program).is_none() {
+            self.programs
+                .push((program, "allowed by --allow-program".to_string()));
+        }
+    }
+
+    pub fn name(&self, program: &Pubkey) -> Option<&str> {
+        self.programs
+            .iter()
+            .find(|(p, _)| p == program)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+/// One top-level instruction in human readable form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionSummary {
+    pub program: Pubkey,
+    /// Allowlist name of the program, `None` if it isn't allowed
+    pub program_name: Option<String>,
+    pub description: String,
+    pub accounts: usize,
+}
+
+/// Everything we found looking through the transaction
+#[derive(Debug, Clone, Default)]
+pub struct Verification {
+    pub instructions: Vec<InstructionSummary>,
+    /// Reasons not to sign. Empty means the transaction looks like a plain swap.
+    pub problems: Vec<String>,
+}
+
+impl Verification {
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "instructions ({})", self.instructions.len()).unwrap();
+        for (i, ix) in self.instructions.iter().enumerate() {
+            writeln!(
+                out,
+                "  {:<3} {:<26} {} ({} accounts)",
+                i + 1,
+                ix.program_name
+                    .clone()
+                    .unwrap_or_else(|| ix.program.to_string()),
+                ix.description,
+                ix.accounts
+            )
+            .unwrap();
+        }
+        for problem in &self.problems {
+            writeln!(out, "  !! {}", problem).unwrap();
+        }
+        out
+    }
+
+    pub fn into_result(self) -> anyhow::Result<()> {
+        if self.problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "refusing to sign the swap transaction: {}",
+                self.problems.join("; ")
+            ))
+        }
+    }
+}
+
+/// Checks the fee payer, every program invoked and every instruction that could move the wallet's
+/// funds somewhere else. `accounts` are the message's resolved accounts, see `resolve_accounts`.
+pub fn verify_message(
+    accounts: &[MessageAccount],
+    instructions: &[CompiledInstruction],
+    wallet: &Pubkey,
+    allowlist: &Allowlist,
+) -> Verification {
+    let mut verification = Verification::default();
+
+    match accounts.first() {
+        Some(fee_payer) if fee_payer.pubkey == *wallet && fee_payer.signer => {}
+        Some(fee_payer) => verification.problems.push(format!(
+            "fee payer is {}, not our wallet {}",
+            fee_payer.pubkey, wallet
+        )),
+        None => verification
+            .problems
+            .push("transaction has no accounts".to_string()),
+    }
+
+    //the wSOL account Jupiter wraps SOL into is ours, so transfers into it are fine
+    let ours = [
+        *wallet,
+        get_associated_token_address(wallet, &spl_token::native_mint::ID),
+    ];
+
+    for (i, ix) in instructions.iter().enumerate() {
+        let key = |index: u8| accounts.get(index as usize).map(|a| a.pubkey);
+        let ix_accounts: Vec<Pubkey> = ix.accounts.iter().filter_map(|a| key(*a)).collect();
+        let account = |n: usize| ix_accounts.get(n).copied().unwrap_or_default();
+
+        let Some(program) = key(ix.program_id_index) else {
+            verification
+                .problems
+                .push(format!("instruction {} has a bad program index", i + 1));
+            continue;
+        };
+        let program_name = allowlist.name(&program).map(str::to_string);
+        if program_name.is_none() {
+            verification.problems.push(format!(
+                "instruction {} invokes {} which isn't on the allowlist",
+                i + 1,
+                program
+            ));
+        }
+
+        let (description, problem) = if program == system_program::ID {
+            describe_system(&ix.data, account, wallet, &ours)
+        } else if program == spl_token::ID || program == TOKEN_2022_PROGRAM_ID {
+            describe_token(&ix.data, account, wallet)
+        } else if program == spl_associated_token_account::ID {
+            let name = match ix.data.first() {
+                None | Some(0) => "create",
+                Some(1) => "create_idempotent",
+                Some(2) => "recover_nested",
+                Some(_) => "unknown",
+            };
+            (format!("{} for mint {}", name, account(3)), None)
+        } else if program == compute_budget::ID {
+            (describe_compute_budget(&ix.data), None)
+        } else if program == JUPITER_V6_PROGRAM_ID {
+            let name = JUPITER_ROUTES
+                .iter()
+                .find(|(discm, _)| ix.data.starts_with(discm))
+                .map_or("unknown instruction", |(_, name)| name);
+            (name.to_string(), None)
+        } else {
+            (format!("{} bytes of data", ix.data.len()), None)
+        };
+
+        if let Some(problem) = problem {
+            verification
+                .problems
+                .push(format!("instruction {} {}", i + 1, problem));
+        }
+        verification.instructions.push(InstructionSummary {
+            program,
+            program_name,
+            description,
+            accounts: ix.accounts.len(),
+        });
+    }
+
+    verification
+}
+
+/// Names the system instruction, flagging anything that sends the wallet's lamports or ownership
+/// to someone else
+fn describe_system(
+    data: &[u8],
+    account: impl Fn(usize) -> Pubkey,
+    wallet: &Pubkey,
+    ours: &[Pubkey],
+) -> (String, Option<String>) {
+    match bincode::deserialize::<SystemInstruction>(data) {
+        Ok(SystemInstruction::Transfer { lamports }) => {
+            let (from, to) = (account(0), account(1));
+            let problem = (from == *wallet && !ours.contains(&to))
+                .then(|| format!("transfers {} lamports from the wallet to {}", lamports, to));
+            (format!("transfer {} lamports to {}", lamports, to), problem)
+        }
+        Ok(SystemInstruction::Assign { owner }) => {
+            let problem =
+                (account(0) == *wallet).then(|| format!("assigns the wallet to program {}", owner));
+            (format!("assign {} to {}", account(0), owner), problem)
+        }
+        Ok(SystemInstruction::CreateAccount { lamports, .. }) => (
+            format!("create account {} with {} lamports", account(1), lamports),
+            None,
+        ),
+        Ok(other) => {
+            //anything else moving wallet lamports has no place in a swap
+            let problem = (account(0) == *wallet)
+                .then(|| format!("runs system instruction {:?} on the wallet", other));
+            (format!("{:?}", other), problem)
+        }
+        Err(_) => ("unknown system instruction".to_string(), None),
+    }
+}
+
+/// Names the token instruction, flagging approvals, authority changes and anything the wallet
+/// signs that moves tokens or rent somewhere else. Jupiter moves tokens inside its own program,
+/// so a top-level transfer signed by the wallet is already suspicious.
+fn describe_token(
+    data: &[u8],
+    account: impl Fn(usize) -> Pubkey,
+    wallet: &Pubkey,
+) -> (String, Option<String>) {
+    let Ok(ix) = TokenInstruction::unpack(data) else {
+        return (
+            format!(
+                "instruction tag {}",
+                data.first().copied().unwrap_or_default()
+            ),
+            None,
+        );
+    };
+
+    match ix {
+        TokenInstruction::Approve { amount } => {
+            let delegate = account(1);
+            let problem = (delegate != *wallet)
+                .then(|| format!("approves {} for {} tokens", delegate, amount));
+            (format!("approve {} for {}", delegate, amount), problem)
+        }
+        TokenInstruction::ApproveChecked { amount, .. } => {
+            let delegate = account(2);
+            let problem = (delegate != *wallet)
+                .then(|| format!("approves {} for {} tokens", delegate, amount));
+            (
+                format!("approve_checked {} for {}", delegate, amount),
+                problem,
+            )
+        }
+        TokenInstruction::SetAuthority { new_authority, .. } => {
+            let new_authority = Option::<Pubkey>::from(new_authority);
+            let problem = (new_authority != Some(*wallet)).then(|| {
+                format!(
+                    "changes the authority of {} to {:?}",
+                    account(0),
+                    new_authority
+                )
+            });
+            (format!("set_authority on {}", account(0)), problem)
+        }
+        TokenInstruction::Transfer { amount } => {
+            let (destination, authority) = (account(1), account(2));
+            let problem = (authority == *wallet).then(|| {
+                format!(
+                    "transfers {} tokens from the wallet to {}",
+                    amount, destination
+                )
+            });
+            (format!("transfer {} to {}", amount, destination), problem)
+        }
+        TokenInstruction::TransferChecked { amount, .. } => {
+            let (destination, authority) = (account(2), account(3));
+            let problem = (authority == *wallet).then(|| {
+                format!(
+                    "transfers {} tokens from the wallet to {}",
+                    amount, destination
+                )
+            });
+            (
+                format!("transfer_checked {} to {}", amount, destination),
+                problem,
+            )
+        }
+        TokenInstruction::CloseAccount => {
+            let destination = account(1);
+            let problem = (destination != *wallet).then(|| {
+                format!(
+                    "closes {} and sends the rent to {}",
+                    account(0),
+                    destination
+                )
+            });
+            (format!("close_account {}", account(0)), problem)
+        }
+        TokenInstruction::SyncNative => (format!("sync_native {}", account(0)), None),
+        other => (format!("{:?}", other), None),
+    }
+}
+
+fn describe_compute_budget(data: &[u8]) -> String {
+    match data.split_first() {
+        Some((2, limit)) if limit.len() >= 4 => format!(
+            "set_compute_unit_limit {}",
+            u32::from_le_bytes(limit[..4].try_into().unwrap())
+        ),
+        Some((3, price)) if price.len() >= 8 => format!(
+            "set_compute_unit_price {} micro lamports",
+            u64::from_le_bytes(price[..8].try_into().unwrap())
+        ),
+        Some((tag, _)) => format!("instruction tag {}", tag),
+        None => "empty instruction".to_string(),
+    }
+}
+
+/// Decodes the transaction Jupiter built, prints the breakdown and refuses to sign anything that
+/// doesn't look like a plain swap for our wallet
+pub struct VerifyCheck<'a> {
+    pub rpc: &'a RpcClient,
+    pub allowlist: Allowlist,
+}
+
+#[async_trait]
+impl SwapCheck for VerifyCheck<'_> {
+    async fn check(
+        &self,
+        wallet: &Pubkey,
+        _pair: &ResolvedPair,
+        _quote: &QuoteResponse,
+        tx: &VersionedTransaction,
+    ) -> anyhow::Result<()> {
+        let accounts = resolve_accounts(self.rpc, &tx.message).await?;
+        let verification = verify_message(
+            &accounts,
+            tx.message.instructions(),
+            wallet,
+            &self.allowlist,
+        );
+        println!("{}", verification.render());
+        verification.into_result()
+    }
+}
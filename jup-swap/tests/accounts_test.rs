@@ -0,0 +1,87 @@
+use jup_swap::accounts::{render_balances, TokenHolding};
+use jup_swap::tokens::{TokenRegistry, TOKEN_2022_PROGRAM_ID};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+#[test]
+fn test_holding_from_json_parsed() {
+    let account = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    //what getTokenAccountsByOwner returns under account.data.parsed
+    let parsed = json!({
+        "type": "account",
+        "info": {
+            "isNative": false,
+            "mint": BONK.to_string(),
+            "owner": owner.to_string(),
+            "state": "initialized",
+            "tokenAmount": {
+                "amount": "123456789",
+                "decimals": 5,
+                "uiAmount": 1234.56789,
+                "uiAmountString": "1234.56789"
+            }
+        }
+    });
+
+    let holding = TokenHolding::from_parsed(account, spl_token::ID, &parsed).unwrap();
+    assert_eq!(
+        holding,
+        TokenHolding {
+            account,
+            mint: BONK,
+            token_program: spl_token::ID,
+            amount: 123_456_789,
+            decimals: 5,
+        }
+    );
+
+    assert!(TokenHolding::from_parsed(account, spl_token::ID, &json!({"info": {}})).is_err());
+}
+
+#[test]
+fn test_render_balances() {
+    let registry =
+        TokenRegistry::from_json(&format!(r#"{{"SOL": "{}", "BONK": "{}"}}"#, SOL, BONK)).unwrap();
+    //USDC isn't in this registry, so it sorts by address between BONK and wSOL
+    let unknown_mint = solana_sdk::pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+    let wsol_account = Pubkey::new_unique();
+
+    let holdings = vec![
+        TokenHolding {
+            account: Pubkey::new_unique(),
+            mint: BONK,
+            token_program: spl_token::ID,
+            amount: 123_456_789,
+            decimals: 5,
+        },
+        TokenHolding {
+            account: wsol_account,
+            mint: SOL,
+            token_program: spl_token::ID,
+            amount: 500_000_000,
+            decimals: 9,
+        },
+        TokenHolding {
+            account: Pubkey::new_unique(),
+            mint: unknown_mint,
+            token_program: TOKEN_2022_PROGRAM_ID,
+            amount: 42,
+            decimals: 0,
+        },
+    ];
+
+    let out = render_balances(1_250_000_000, &holdings, &registry);
+    let lines: Vec<_> = out.lines().collect();
+
+    assert_eq!(lines.len(), 5);
+    assert!(lines[1].starts_with("SOL") && lines[1].contains("1.25"));
+    assert!(lines[2].starts_with("BONK") && lines[2].contains("1234.56789"));
+    //unknown mints show up by address, wSOL is kept apart from SOL
+    assert!(lines[3].starts_with(&unknown_mint.to_string()[..12]));
+    assert!(lines[3].contains("token-2022"));
+    assert!(lines[4].starts_with("wSOL") && lines[4].contains(&wsol_account.to_string()));
+}
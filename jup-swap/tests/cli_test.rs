@@ -0,0 +1,27 @@
+use jup_swap::cli::parse_duration;
+use std::time::Duration;
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(15 * 60));
+    assert_eq!(
+        parse_duration(" 2h ").unwrap(),
+        Duration::from_secs(2 * 60 * 60)
+    );
+    assert_eq!(
+        parse_duration("7d").unwrap(),
+        Duration::from_secs(7 * 24 * 60 * 60)
+    );
+
+    assert!(parse_duration("10").is_err());
+    assert!(parse_duration("10w").is_err());
+    assert!(parse_duration("m").is_err());
+    assert!(parse_duration("0h").is_err());
+}
+
+#[test]
+fn test_parse_duration_overflow() {
+    let e = parse_duration("300000000000000d").unwrap_err();
+    assert!(e.to_string().contains("too long"), "{}", e);
+}
@@ -0,0 +1,155 @@
+use jup_swap::compare::{
+    compare_routes, default_variants, recommend, Comparison, RouteStats, RouteVariant,
+};
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use serde_json::Value;
+use solana_program_test::tokio;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+/// The best price, but split across two hops
+fn two_hops() -> Value {
+    let mut quote = quote_json(SOL, BONK, 10_000_000, 123_456_789, 50, "0.0001");
+    let step = quote["routePlan"][0].clone();
+    quote["routePlan"].as_array_mut().unwrap().push(step);
+    quote
+}
+
+fn stats(out_amount: u64, hops: usize, accounts: usize) -> RouteStats {
+    RouteStats {
+        in_amount: 10_000_000,
+        out_amount,
+        hops,
+        price_impact_pct: 0.01,
+        accounts: Some(accounts),
+        amms: vec![],
+    }
+}
+
+fn compared(name: &str, result: Result<RouteStats, String>) -> Comparison {
+    Comparison {
+        variant: RouteVariant::named(name),
+        result,
+    }
+}
+
+#[tokio::test]
+async fn test_compare_quotes_every_variant() -> anyhow::Result<()> {
+    let mock = MockJupiter::start_with_routes(
+        two_hops(),
+        vec![
+            (
+                "onlyDirectRoutes",
+                "true",
+                quote_json(SOL, BONK, 10_000_000, 120_000_000, 50, "0.0001"),
+            ),
+            (
+                "maxAccounts",
+                "32",
+                quote_json(SOL, BONK, 10_000_000, 123_450_000, 50, "0.0001"),
+            ),
+        ],
+        Pubkey::new_unique(),
+        Hash::default(),
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let pair = sol_to_bonk(10_000_000);
+
+    let variants = default_variants(&[32], &[], &["Mock AMM".to_string()]);
+    let names: Vec<&str> = variants.iter().map(|v| v.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "default",
+            "direct",
+            "restricted",
+            "max-accounts-32",
+            "exclude-dexes"
+        ]
+    );
+
+    let comparisons = compare_routes(&jup, &pair, &variants, Some(Pubkey::new_unique())).await;
+    let outs: Vec<u64> = comparisons
+        .iter()
+        .map(|c| c.result.as_ref().unwrap().out_amount)
+        .collect();
+    assert_eq!(
+        outs,
+        vec![
+            123_456_789,
+            120_000_000,
+            123_456_789,
+            123_450_000,
+            123_456_789
+        ]
+    );
+    assert_eq!(comparisons[0].result.as_ref().unwrap().hops, 2);
+    //the mock swap is a plain transfer: user, pool and the system program
+    assert_eq!(comparisons[1].result.as_ref().unwrap().accounts, Some(3));
+
+    //every variant's settings made it into its request
+    let queries: Vec<String> = mock
+        .requests()
+        .await
+        .iter()
+        .filter(|r| r.url.path() == "/quote")
+        .filter_map(|r| r.url.query().map(str::to_string))
+        .collect();
+    assert_eq!(queries.len(), 5);
+    assert!(queries.iter().any(|q| q.contains("onlyDirectRoutes=true")));
+    assert!(queries
+        .iter()
+        .any(|q| q.contains("restrictIntermediateTokens=true")));
+    assert!(queries.iter().any(|q| q.contains("maxAccounts=32")));
+
+    //max-accounts-32 is within 5 bps of the best and takes one hop instead of two
+    assert_eq!(recommend(&comparisons, false, 5), Some(3));
+    //with no margin the best out amount wins outright
+    assert_eq!(recommend(&comparisons, false, 0), Some(0));
+    Ok(())
+}
+
+#[test]
+fn test_recommend_prefers_smaller_transactions_among_equals() {
+    let comparisons = vec![
+        compared("default", Ok(stats(1_000_000, 3, 40))),
+        compared("direct", Err("no route".to_string())),
+        compared("restricted", Ok(stats(999_950, 2, 30))),
+        compared("max-accounts-32", Ok(stats(999_950, 2, 30))),
+        compared("max-accounts-20", Ok(stats(990_000, 1, 18))),
+    ];
+
+    //1 bp of 1_000_000 is 100, so restricted is as good and ties go to the first listed
+    assert_eq!(recommend(&comparisons, false, 1), Some(2));
+    assert_eq!(recommend(&comparisons, false, 0), Some(0));
+    //a wide enough margin lets the smallest transaction win
+    assert_eq!(recommend(&comparisons, false, 100), Some(4));
+
+    //for exact out it's the least in that counts
+    let exact_out = vec![
+        compared(
+            "default",
+            Ok(RouteStats {
+                in_amount: 1_000,
+                ..stats(0, 2, 40)
+            }),
+        ),
+        compared(
+            "direct",
+            Ok(RouteStats {
+                in_amount: 990,
+                ..stats(0, 1, 40)
+            }),
+        ),
+    ];
+    assert_eq!(recommend(&exact_out, true, 0), Some(1));
+
+    assert_eq!(
+        recommend(&[compared("direct", Err("no route".to_string()))], false, 5),
+        None
+    );
+}
@@ -0,0 +1,349 @@
+use jup_swap::dca::{Dca, DcaPlan, DcaState, OrderStatus, PendingOrder};
+use jup_swap::retry::{PendingSwap, Unsettled};
+use jup_swap::schedule::Clock;
+use jup_swap::swap::ResolvedPair;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use solana_program_test::tokio;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::time::Duration;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+const START: u64 = 1_700_000_000;
+const HOUR: u64 = 60 * 60;
+
+fn plan() -> DcaPlan {
+    DcaPlan {
+        order_amount: 10_000_000,
+        budget: 25_000_000,
+        interval: Duration::from_secs(HOUR),
+        max_price: None,
+        max_price_impact_pct: None,
+    }
+}
+
+/// 0.01 SOL for 1234.56789 BONK
+async fn mock_jupiter(price_impact: &str) -> MockJupiter {
+    MockJupiter::start(
+        quote_json(SOL, BONK, 10_000_000, 123_456_789, 50, price_impact),
+        Pubkey::new_unique(),
+        Hash::default(),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_dca_spends_budget_one_interval_apart() -> anyhow::Result<()> {
+    let mock = mock_jupiter("0.0001").await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let path = state_path("dca-budget");
+
+    let dca = Dca {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(10_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = DcaState::new(&dca.pair, clock.now());
+    dca.run(&mut state).await?;
+
+    //two full orders and what was left of the budget
+    assert_eq!(executor.amounts(), vec![10_000_000, 10_000_000, 5_000_000]);
+    assert_eq!(state.spent, 25_000_000);
+    assert_eq!(state.received, 3 * 123_456_789);
+    let times: Vec<u64> = state.orders.iter().map(|order| order.at).collect();
+    assert_eq!(times, vec![START, START + HOUR, START + 2 * HOUR]);
+
+    //each order asked Jupiter for its own amount
+    let amounts: Vec<String> = mock
+        .requests()
+        .await
+        .iter()
+        .filter(|r| r.url.path() == "/quote")
+        .map(|r| {
+            r.url
+                .query_pairs()
+                .find(|(key, _)| key == "amount")
+                .unwrap()
+                .1
+                .to_string()
+        })
+        .collect();
+    assert_eq!(amounts, vec!["10000000", "10000000", "5000000"]);
+
+    //the saved state matches what's in memory
+    assert_eq!(DcaState::load_or_new(&path, &dca.pair, 0)?, state);
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dca_resumes_from_state_file() -> anyhow::Result<()> {
+    let mock = mock_jupiter("0.0001").await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let path = state_path("dca-resume");
+
+    let dca = Dca {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(10_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = DcaState::load_or_new(&path, &dca.pair, clock.now())?;
+    dca.step(&mut state).await?;
+
+    //restarted a day later: one order now, no catching up on the ones missed
+    clock.set(START + 24 * HOUR);
+    let mut state = DcaState::load_or_new(&path, &dca.pair, clock.now())?;
+    assert_eq!(state.spent, 10_000_000);
+    assert_eq!(state.next_order_at, START + HOUR);
+
+    dca.step(&mut state).await?;
+    assert_eq!(state.spent, 20_000_000);
+    assert_eq!(state.orders[1].at, START + 24 * HOUR);
+    assert_eq!(state.next_order_at, START + 25 * HOUR);
+
+    //a state file for another pair isn't picked up
+    let other = ResolvedPair {
+        output_mint: Pubkey::new_unique(),
+        ..sol_to_bonk(10_000_000)
+    };
+    assert!(DcaState::load_or_new(&path, &other, clock.now()).is_err());
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dca_skips_orders_over_limits() -> anyhow::Result<()> {
+    let mock = mock_jupiter("0.02").await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let path = state_path("dca-skip");
+
+    //the quote prices BONK at about 0.0000081 SOL
+    let mut dca = Dca {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(10_000_000),
+        plan: DcaPlan {
+            max_price: Some(0.000005),
+            ..plan()
+        },
+        state_path: path.clone(),
+    };
+    let mut state = DcaState::new(&dca.pair, clock.now());
+    let order = dca.step(&mut state).await?.unwrap();
+    assert_eq!(order.status, OrderStatus::Skipped);
+    assert!(order.reason.unwrap().contains("price"));
+
+    //2% price impact
+    dca.plan = DcaPlan {
+        max_price_impact_pct: Some(1.0),
+        ..plan()
+    };
+    let order = dca.step(&mut state).await?.unwrap();
+    assert_eq!(order.status, OrderStatus::Skipped);
+    assert!(order.reason.unwrap().contains("price impact"));
+
+    //skipped orders spend nothing but still wait an interval
+    assert!(executor.amounts().is_empty());
+    assert_eq!(state.spent, 0);
+    assert_eq!(state.next_order_at, START + 2 * HOUR);
+
+    dca.plan = DcaPlan {
+        max_price: Some(0.00001),
+        max_price_impact_pct: Some(5.0),
+        ..plan()
+    };
+    let order = dca.step(&mut state).await?.unwrap();
+    assert_eq!(order.status, OrderStatus::Filled);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dca_settles_order_left_pending_by_crash() -> anyhow::Result<()> {
+    let mock = mock_jupiter("0.0001").await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let path = state_path("dca-pending");
+
+    //the run dies right after sending the first order
+    let crashing = FakeExecutor {
+        hang_after_send: true,
+        ..FakeExecutor::default()
+    };
+    let dca = Dca {
+        jup: &jup,
+        executor: &crashing,
+        clock: &clock,
+        pair: sol_to_bonk(10_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = DcaState::new(&dca.pair, clock.now());
+    tokio::select! {
+        _ = dca.step(&mut state) => panic!("the step should hang once the swap is sent"),
+        _ = crashing.sent.notified() => {}
+    }
+
+    //the swap was saved before it went out
+    let mut state = DcaState::load_or_new(&path, &dca.pair, clock.now())?;
+    let pending = state.pending.clone().unwrap();
+    assert_eq!(pending.at, START);
+    assert_eq!(pending.swap.quoted_in_amount, 10_000_000);
+    assert!(state.orders.is_empty());
+
+    //on restart it turns out to have landed: recorded as filled, nothing new is sent
+    clock.set(START + 10);
+    let executor = FakeExecutor {
+        pending_landed: true,
+        ..FakeExecutor::default()
+    };
+    let dca = Dca {
+        executor: &executor,
+        ..dca
+    };
+    let order = dca.step(&mut state).await?.unwrap();
+    assert_eq!(order.status, OrderStatus::Filled);
+    assert_eq!(order.at, START);
+    assert_eq!(order.signature, Some(pending.swap.signature));
+    assert_eq!(state.spent, 10_000_000);
+    assert_eq!(state.received, 123_456_789);
+    assert_eq!(state.next_order_at, START + HOUR);
+    assert!(state.pending.is_none());
+    assert!(executor.amounts().is_empty());
+    assert_eq!(DcaState::load_or_new(&path, &dca.pair, 0)?, state);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dca_keeps_order_pending_when_landing_is_unknown() -> anyhow::Result<()> {
+    let mock = mock_jupiter("0.0001").await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let path = state_path("dca-unsettled");
+
+    //the swap goes out, then the rpc fails before we know whether it landed
+    let unsettled = FakeExecutor {
+        unsettled_after_send: true,
+        ..FakeExecutor::default()
+    };
+    let dca = Dca {
+        jup: &jup,
+        executor: &unsettled,
+        clock: &clock,
+        pair: sol_to_bonk(10_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = DcaState::new(&dca.pair, clock.now());
+    let err = dca.step(&mut state).await.unwrap_err();
+    assert!(err.is::<Unsettled>(), "{:#}", err);
+
+    //not recorded as failed, it's still pending both here and in the state file
+    assert!(state.orders.is_empty());
+    assert_eq!(state.spent, 0);
+    let pending = state.pending.clone().unwrap();
+    assert_eq!(pending.at, START);
+    assert_eq!(DcaState::load_or_new(&path, &dca.pair, 0)?, state);
+
+    //the next step finds out it landed and counts it toward the budget
+    let executor = FakeExecutor {
+        pending_landed: true,
+        ..FakeExecutor::default()
+    };
+    let dca = Dca {
+        executor: &executor,
+        ..dca
+    };
+    let order = dca.step(&mut state).await?.unwrap();
+    assert_eq!(order.status, OrderStatus::Filled);
+    assert_eq!(order.signature, Some(pending.swap.signature));
+    assert_eq!(state.spent, 10_000_000);
+    assert!(state.pending.is_none());
+    assert!(executor.amounts().is_empty());
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dca_pending_order_that_never_landed_is_failed() -> anyhow::Result<()> {
+    let mock = mock_jupiter("0.0001").await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let path = state_path("dca-pending-lost");
+
+    let dca = Dca {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(10_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = DcaState::new(&dca.pair, clock.now());
+    state.pending = Some(PendingOrder {
+        at: START,
+        swap: PendingSwap {
+            signature: Signature::new_unique().to_string(),
+            blockhash: Hash::default().to_string(),
+            quoted_in_amount: 10_000_000,
+            quoted_out_amount: 123_456_789,
+        },
+    });
+
+    let order = dca.step(&mut state).await?.unwrap();
+    assert_eq!(order.status, OrderStatus::Failed);
+    assert_eq!(state.spent, 0);
+    assert!(state.pending.is_none());
+
+    //the next order goes out as usual an interval later
+    let order = dca.step(&mut state).await?.unwrap();
+    assert_eq!(order.status, OrderStatus::Filled);
+    assert_eq!(order.at, START + HOUR);
+    assert_eq!(executor.amounts(), vec![10_000_000]);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dca_interval_past_the_end_of_time_is_an_error() {
+    let jup = JupiterSwapApiClient::new(String::new());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let dca = Dca {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(10_000_000),
+        plan: DcaPlan {
+            interval: Duration::from_secs(u64::MAX),
+            ..plan()
+        },
+        state_path: state_path("dca-overflow"),
+    };
+    let mut state = DcaState::new(&dca.pair, clock.now());
+    let step = dca.step(&mut state).await;
+    assert!(step.unwrap_err().to_string().contains("--interval"));
+}
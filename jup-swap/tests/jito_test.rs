@@ -0,0 +1,127 @@
+use jup_swap::jito::DEFAULT_TIP_ACCOUNT;
+use jup_swap::submit::Submitter;
+use solana_program_test::{tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::VersionedTransaction;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+/// A bank with a funded wallet, and a signed "swap" from it against the bank's blockhash
+async fn bank_and_swap(wallet: &Keypair) -> (ProgramTestContext, VersionedTransaction) {
+    let mut validator = ProgramTest::default();
+    validator.add_account(
+        wallet.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let context = validator.start_with_context().await;
+
+    let ix = system_instruction::transfer(&wallet.pubkey(), &Pubkey::new_unique(), 10_000_000);
+    let message =
+        v0::Message::try_compile(&wallet.pubkey(), &[ix], &[], context.last_blockhash).unwrap();
+    let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[wallet]).unwrap();
+    (context, tx)
+}
+
+#[tokio::test]
+async fn test_landed_bundle_skips_rpc() -> anyhow::Result<()> {
+    let wallet = Keypair::new();
+    let (context, swap) = bank_and_swap(&wallet).await;
+    let rpc = BanksSubmitter(context.banks_client.clone());
+    let engine = MockBlockEngine::start(vec!["Pending", "Landed"]).await;
+    let submitter = engine.submitter(&wallet, DEFAULT_TIP_ACCOUNT, &rpc);
+
+    let signature = submitter.submit(&swap).await?;
+    assert_eq!(signature, swap.signatures[0]);
+    //it landed through the bundle, the bank never saw it
+    assert_eq!(rpc.signature_status(&signature).await?, None);
+
+    //the swap goes first and the tip last, on the same blockhash
+    let bundles = engine.bundles().await;
+    assert_eq!(bundles.len(), 1);
+    let [sent, tip] = &bundles[0][..] else {
+        panic!("expected the swap and a tip, got {:?}", bundles[0]);
+    };
+    assert_eq!(sent, &swap);
+    assert_eq!(
+        tip.message.recent_blockhash(),
+        swap.message.recent_blockhash()
+    );
+    assert_eq!(
+        tip.message.instructions()[0].data,
+        system_instruction::transfer(&wallet.pubkey(), &DEFAULT_TIP_ACCOUNT, 10_000).data
+    );
+    assert!(tip
+        .message
+        .static_account_keys()
+        .contains(&DEFAULT_TIP_ACCOUNT));
+    assert!(tip.verify_with_results().iter().all(|ok| *ok));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_bundle_falls_back_to_rpc() -> anyhow::Result<()> {
+    let wallet = Keypair::new();
+    let (context, swap) = bank_and_swap(&wallet).await;
+    let rpc = BanksSubmitter(context.banks_client.clone());
+    let engine = MockBlockEngine::start(vec!["Pending", "Failed"]).await;
+    let submitter = engine.submitter(&wallet, DEFAULT_TIP_ACCOUNT, &rpc);
+
+    let signature = submitter.submit(&swap).await?;
+    assert_eq!(rpc.signature_status(&signature).await?, Some(Ok(())));
+    assert_eq!(engine.bundles().await.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejected_or_stuck_bundle_falls_back_to_rpc() -> anyhow::Result<()> {
+    let wallet = Keypair::new();
+    let (context, swap) = bank_and_swap(&wallet).await;
+    let rpc = BanksSubmitter(context.banks_client.clone());
+
+    let rejecting = MockBlockEngine::start(vec![]).await;
+    let submitter = rejecting.submitter(&wallet, DEFAULT_TIP_ACCOUNT, &rpc);
+    let signature = submitter.submit(&swap).await?;
+    assert_eq!(rpc.signature_status(&signature).await?, Some(Ok(())));
+
+    //pending past the timeout counts as not landed
+    let (context, swap) = bank_and_swap(&wallet).await;
+    let rpc = BanksSubmitter(context.banks_client.clone());
+    let stuck = MockBlockEngine::start(vec!["Pending"]).await;
+    let submitter = stuck.submitter(&wallet, DEFAULT_TIP_ACCOUNT, &rpc);
+    let signature = submitter.submit(&swap).await?;
+    assert_eq!(rpc.signature_status(&signature).await?, Some(Ok(())));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_bundle_is_pending_until_timeout() -> anyhow::Result<()> {
+    //right after sendBundle the block engine may not know the id yet and says Invalid
+    let wallet = Keypair::new();
+    let (context, swap) = bank_and_swap(&wallet).await;
+    let rpc = BanksSubmitter(context.banks_client.clone());
+    let engine = MockBlockEngine::start(vec!["Invalid", "Landed"]).await;
+    let submitter = engine.submitter(&wallet, DEFAULT_TIP_ACCOUNT, &rpc);
+
+    let signature = submitter.submit(&swap).await?;
+    //no fallback, the bank never saw it
+    assert_eq!(rpc.signature_status(&signature).await?, None);
+    assert_eq!(engine.bundles().await.len(), 1);
+
+    //an id that stays unknown past the timeout still falls back
+    let (context, swap) = bank_and_swap(&wallet).await;
+    let rpc = BanksSubmitter(context.banks_client.clone());
+    let unknown = MockBlockEngine::start(vec!["Invalid"]).await;
+    let submitter = unknown.submitter(&wallet, DEFAULT_TIP_ACCOUNT, &rpc);
+    let signature = submitter.submit(&swap).await?;
+    assert_eq!(rpc.signature_status(&signature).await?, Some(Ok(())));
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use jup_swap::limit::{LimitOrder, LimitPlan, LimitState, LimitStatus, PendingChunk, Poll};
+use jup_swap::retry::{PendingSwap, Unsettled};
+use jup_swap::schedule::Clock;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use serde_json::Value;
+use solana_program_test::tokio;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::time::Duration;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+const START: u64 = 1_700_000_000;
+const POLL: u64 = 30;
+
+/// Sell 0.025 SOL in 0.01 chunks for at least 120k BONK per SOL
+fn plan() -> LimitPlan {
+    LimitPlan {
+        amount: 25_000_000,
+        chunk: 10_000_000,
+        min_price: 120_000.0,
+        poll_interval: Duration::from_secs(POLL),
+        max_price_impact_pct: None,
+    }
+}
+
+/// 100k BONK per SOL
+fn below() -> Value {
+    quote_json(SOL, BONK, 10_000_000, 100_000_000, 50, "0.0001")
+}
+
+/// 123456.789 BONK per SOL
+fn above() -> Value {
+    quote_json(SOL, BONK, 10_000_000, 123_456_789, 50, "0.0001")
+}
+
+#[tokio::test]
+async fn test_limit_waits_for_price_then_fills_in_chunks() -> anyhow::Result<()> {
+    let mock = MockJupiter::start_with_quotes(
+        vec![below(), below(), above()],
+        Pubkey::new_unique(),
+        Hash::default(),
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let path = state_path("limit-fill");
+
+    let order = LimitOrder {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(25_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = LimitState::new(&order.pair, None);
+    assert_eq!(order.run(&mut state).await?, LimitStatus::Filled);
+
+    //nothing until the third quote, then every chunk with the rest last
+    assert_eq!(executor.amounts(), vec![10_000_000, 10_000_000, 5_000_000]);
+    assert_eq!(state.filled, 25_000_000);
+    let times: Vec<u64> = state.fills.iter().map(|fill| fill.at).collect();
+    assert_eq!(
+        times,
+        vec![START + 2 * POLL, START + 3 * POLL, START + 4 * POLL]
+    );
+
+    //a filled order stays filled, even across restarts
+    let mut state = LimitState::load_or_new(&path, &order.pair, None)?;
+    assert_eq!(state.status, LimitStatus::Filled);
+    assert_eq!(order.poll(&mut state).await?, Poll::Done);
+    assert_eq!(executor.amounts().len(), 3);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limit_never_fills_below_the_limit_after_slippage() -> anyhow::Result<()> {
+    //the quoted price is above 120k BONK per SOL, but with 3% slippage the worst case is only
+    //119753.086, so the order waits. At 0.5% the worst case is 122839.505 and it fills.
+    let slippery = quote_json(SOL, BONK, 10_000_000, 123_456_789, 300, "0.0001");
+    let mock = MockJupiter::start_with_quotes(
+        vec![slippery, above()],
+        Pubkey::new_unique(),
+        Hash::default(),
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let path = state_path("limit-slippage");
+
+    let order = LimitOrder {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(25_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = LimitState::new(&order.pair, None);
+
+    let poll = order.poll(&mut state).await?;
+    let Poll::Waiting(reason) = poll else {
+        panic!("expected the order to wait, got {:?}", poll);
+    };
+    assert!(reason.contains("after slippage"), "{}", reason);
+    assert!(executor.amounts().is_empty());
+
+    //and the swap that does go out can't fill below the limit either
+    let poll = order.poll(&mut state).await?;
+    assert!(matches!(poll, Poll::Filled(_)));
+    assert_eq!(*executor.min_outs.lock().unwrap(), vec![Some(120_000_000)]);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limit_expires_partially_filled() -> anyhow::Result<()> {
+    let mock = MockJupiter::start_with_quotes(
+        vec![above(), below()],
+        Pubkey::new_unique(),
+        Hash::default(),
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let path = state_path("limit-expire");
+
+    let order = LimitOrder {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(25_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = LimitState::load_or_new(&path, &order.pair, Some(START + 100))?;
+    let poll = order.poll(&mut state).await?;
+    assert!(matches!(poll, Poll::Filled(_)));
+
+    //restarting doesn't push the expiry out
+    let mut state = LimitState::load_or_new(&path, &order.pair, Some(START + 10_000))?;
+    assert_eq!(state.expires_at, Some(START + 100));
+
+    assert_eq!(order.run(&mut state).await?, LimitStatus::Expired);
+    assert_eq!(clock.now(), START + 4 * POLL);
+    assert_eq!(state.filled, 10_000_000);
+    assert_eq!(executor.amounts(), vec![10_000_000]);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limit_settles_chunk_left_pending_by_crash() -> anyhow::Result<()> {
+    let mock =
+        MockJupiter::start_with_quotes(vec![above()], Pubkey::new_unique(), Hash::default()).await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let path = state_path("limit-pending");
+
+    //the run dies right after sending the first chunk
+    let crashing = FakeExecutor {
+        hang_after_send: true,
+        ..FakeExecutor::default()
+    };
+    let order = LimitOrder {
+        jup: &jup,
+        executor: &crashing,
+        clock: &clock,
+        pair: sol_to_bonk(25_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = LimitState::new(&order.pair, None);
+    tokio::select! {
+        _ = order.poll(&mut state) => panic!("the poll should hang once the swap is sent"),
+        _ = crashing.sent.notified() => {}
+    }
+
+    //the swap was saved before it went out
+    let mut state = LimitState::load_or_new(&path, &order.pair, None)?;
+    let pending = state.pending.clone().unwrap();
+    assert_eq!(pending.at, START);
+    assert_eq!(pending.swap.quoted_in_amount, 10_000_000);
+    assert!(state.fills.is_empty());
+
+    //on restart it turns out to have landed: recorded as the fill, the chunk isn't sold again
+    clock.set(START + 10);
+    let executor = FakeExecutor {
+        pending_landed: true,
+        ..FakeExecutor::default()
+    };
+    let order = LimitOrder {
+        executor: &executor,
+        ..order
+    };
+    let poll = order.poll(&mut state).await?;
+    let Poll::Filled(fill) = poll else {
+        panic!("expected the pending chunk to fill, got {:?}", poll);
+    };
+    assert_eq!(fill.at, START);
+    assert_eq!(fill.signature, pending.swap.signature);
+    assert_eq!(state.filled, 10_000_000);
+    assert_eq!(state.received, 123_456_789);
+    assert!(state.pending.is_none());
+    assert!(executor.amounts().is_empty());
+    assert_eq!(LimitState::load_or_new(&path, &order.pair, None)?, state);
+
+    //the rest fills as usual, without a second go at the first chunk
+    assert_eq!(order.run(&mut state).await?, LimitStatus::Filled);
+    assert_eq!(executor.amounts(), vec![10_000_000, 5_000_000]);
+    assert_eq!(state.fills.len(), 3);
+    assert_eq!(state.filled, 25_000_000);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limit_pending_chunk_that_never_landed_is_sold_again() -> anyhow::Result<()> {
+    let mock =
+        MockJupiter::start_with_quotes(vec![above()], Pubkey::new_unique(), Hash::default()).await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+    let path = state_path("limit-pending-lost");
+
+    let order = LimitOrder {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(25_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = LimitState::new(&order.pair, None);
+    state.pending = Some(PendingChunk {
+        at: START,
+        swap: PendingSwap {
+            signature: Signature::new_unique().to_string(),
+            blockhash: Hash::default().to_string(),
+            quoted_in_amount: 10_000_000,
+            quoted_out_amount: 123_456_789,
+        },
+    });
+
+    //nothing was sold, so the same poll quotes and sends the chunk
+    let poll = order.poll(&mut state).await?;
+    assert!(matches!(poll, Poll::Filled(_)));
+    assert!(state.pending.is_none());
+    assert_eq!(state.filled, 10_000_000);
+    assert_eq!(executor.amounts(), vec![10_000_000]);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_limit_keeps_chunk_pending_when_landing_is_unknown() -> anyhow::Result<()> {
+    let mock =
+        MockJupiter::start_with_quotes(vec![above()], Pubkey::new_unique(), Hash::default()).await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let path = state_path("limit-unsettled");
+
+    //the swap goes out, then the rpc fails before we know whether it landed
+    let unsettled = FakeExecutor {
+        unsettled_after_send: true,
+        ..FakeExecutor::default()
+    };
+    let order = LimitOrder {
+        jup: &jup,
+        executor: &unsettled,
+        clock: &clock,
+        pair: sol_to_bonk(25_000_000),
+        plan: plan(),
+        state_path: path.clone(),
+    };
+    let mut state = LimitState::new(&order.pair, None);
+    let err = order.poll(&mut state).await.unwrap_err();
+    assert!(err.is::<Unsettled>(), "{:#}", err);
+
+    //still pending, both here and in the state file
+    let pending = state.pending.clone().unwrap();
+    assert_eq!(pending.swap.quoted_in_amount, 10_000_000);
+    assert_eq!(LimitState::load_or_new(&path, &order.pair, None)?, state);
+
+    //the next poll finds out it landed instead of selling the chunk again
+    let executor = FakeExecutor {
+        pending_landed: true,
+        ..FakeExecutor::default()
+    };
+    let order = LimitOrder {
+        executor: &executor,
+        ..order
+    };
+    let poll = order.poll(&mut state).await?;
+    assert!(matches!(poll, Poll::Filled(_)));
+    assert_eq!(state.filled, 10_000_000);
+    assert!(state.pending.is_none());
+    assert!(executor.amounts().is_empty());
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
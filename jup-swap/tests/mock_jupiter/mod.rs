@@ -0,0 +1,532 @@
+//! A local stand-in for the Jupiter swap API so the quote -> swap -> sign -> submit path can run
+//! under `cargo test` with no network. It serves `/quote` and `/swap` the same way the real API
+//! does, and the "swap" transaction it builds is a plain lamport transfer from the user to a mock
+//! pool account, built against the blockhash of a local `solana-program-test` bank.
+//!
+//! The fakes for the scheduled modes live here too: a clock that only moves when slept on and an
+//! executor that fills every swap at the quote. So does a stand-in block engine for bundles.
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use base64::Engine;
+use jup_swap::executor::{Executor, Fill};
+use jup_swap::jito::BundleSubmitter;
+use jup_swap::quote::Guardrails;
+use jup_swap::retry::{BeforeSend, PendingSwap, Unsettled};
+use jup_swap::schedule::Clock;
+use jup_swap::submit::Submitter;
+use jup_swap::swap::ResolvedPair;
+use jupiter_swap_api_client::quote::QuoteResponse;
+use serde_json::{json, Value};
+use solana_program_test::{BanksClient, BanksClientError};
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{self, VersionedTransaction};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+pub const SOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+pub const BONK: Pubkey = pubkey!("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263");
+pub const MOCK_AMM: Pubkey = pubkey!("AMM55ShdkoGRB5jVYPjWziwk8m5MpwyDgsMWHaMSQWH6");
+
+/// Exact in SOL -> BONK at 50 bps, `amount` in lamports
+pub fn sol_to_bonk(amount: u64) -> ResolvedPair {
+    ResolvedPair {
+        input_mint: SOL,
+        output_mint: BONK,
+        input_decimals: 9,
+        output_decimals: 5,
+        amount,
+        slippage_bps: 50,
+        exact_out: false,
+    }
+}
+
+/// A fresh state file in the temp dir, so tests running at the same time don't share one
+pub fn state_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jup-swap-{}-{}.json", name, Pubkey::new_unique()))
+}
+
+/// A canned quote in the shape the real `/quote` endpoint returns. Amounts are strings there.
+pub fn quote_json(
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    in_amount: u64,
+    out_amount: u64,
+    slippage_bps: u16,
+    price_impact: &str,
+) -> Value {
+    let threshold = out_amount - out_amount * slippage_bps as u64 / 10_000;
+    json!({
+        "inputMint": input_mint.to_string(),
+        "inAmount": in_amount.to_string(),
+        "outputMint": output_mint.to_string(),
+        "outAmount": out_amount.to_string(),
+        "otherAmountThreshold": threshold.to_string(),
+        "swapMode": "ExactIn",
+        "slippageBps": slippage_bps,
+        "platformFee": null,
+        "priceImpactPct": price_impact,
+        "routePlan": [{
+            "swapInfo": {
+                "ammKey": MOCK_AMM.to_string(),
+                "label": "Mock AMM",
+                "inputMint": input_mint.to_string(),
+                "outputMint": output_mint.to_string(),
+                "inAmount": in_amount.to_string(),
+                "outAmount": out_amount.to_string(),
+                "feeAmount": "0",
+                "feeMint": input_mint.to_string()
+            },
+            "percent": 100
+        }],
+        "contextSlot": 1,
+        "timeTaken": 0.001
+    })
+}
+
+/// Same canned quote deserialized into the client's type, for tests that don't need the server
+pub fn quote_response(
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    in_amount: u64,
+    out_amount: u64,
+    slippage_bps: u16,
+    price_impact: &str,
+) -> QuoteResponse {
+    serde_json::from_value(quote_json(
+        input_mint,
+        output_mint,
+        in_amount,
+        out_amount,
+        slippage_bps,
+        price_impact,
+    ))
+    .unwrap()
+}
+
+/// The mock Jupiter API. `base_url()` is what gets passed to `JupiterSwapApiClient::new`.
+pub struct MockJupiter {
+    pub server: MockServer,
+}
+
+impl MockJupiter {
+    /// Starts a server that answers every `/quote` with `quote` and every `/swap` with a transfer of
+    /// the quote's in amount from the user to `pool`, using `blockhash`
+    pub async fn start(quote: Value, pool: Pubkey, blockhash: Hash) -> Self {
+        Self::start_with_stale_swaps(quote, pool, blockhash, 0).await
+    }
+
+    /// Like `start`, but the first `stale` swap transactions use a blockhash the bank has never
+    /// seen, so they fail the way an expired transaction does
+    pub async fn start_with_stale_swaps(
+        quote: Value,
+        pool: Pubkey,
+        blockhash: Hash,
+        stale: usize,
+    ) -> Self {
+        Self::serve(vec![quote], pool, blockhash, stale).await
+    }
+
+    /// Like `start`, but `/quote` answers with each of `quotes` in turn and then keeps repeating
+    /// the last one, for tests where the price moves
+    pub async fn start_with_quotes(quotes: Vec<Value>, pool: Pubkey, blockhash: Hash) -> Self {
+        Self::serve(quotes, pool, blockhash, 0).await
+    }
+
+    /// Like `start`, but a `/quote` request with one of the `(param, value, quote)` query params in
+    /// `routes` gets that quote instead, for tests that compare routing settings
+    pub async fn start_with_routes(
+        quote: Value,
+        routes: Vec<(&str, &str, Value)>,
+        pool: Pubkey,
+        blockhash: Hash,
+    ) -> Self {
+        let routes = routes
+            .into_iter()
+            .map(|(param, value, quote)| (param.to_string(), value.to_string(), quote))
+            .collect();
+        Self::serve_routes(vec![quote], routes, pool, blockhash, 0).await
+    }
+
+    async fn serve(quotes: Vec<Value>, pool: Pubkey, blockhash: Hash, stale: usize) -> Self {
+        Self::serve_routes(quotes, vec![], pool, blockhash, stale).await
+    }
+
+    async fn serve_routes(
+        quotes: Vec<Value>,
+        routes: Vec<(String, String, Value)>,
+        pool: Pubkey,
+        blockhash: Hash,
+        stale: usize,
+    ) -> Self {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/quote"))
+            .respond_with(QuoteResponder {
+                quotes,
+                routes,
+                served: AtomicUsize::new(0),
+            })
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/swap"))
+            .respond_with(SwapResponder {
+                pool,
+                blockhash,
+                stale: AtomicUsize::new(stale),
+            })
+            .mount(&server)
+            .await;
+
+        Self { server }
+    }
+
+    pub fn base_url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The raw requests the client made, for asserting on query params and bodies
+    pub async fn requests(&self) -> Vec<Request> {
+        self.server.received_requests().await.unwrap_or_default()
+    }
+}
+
+struct QuoteResponder {
+    quotes: Vec<Value>,
+    /// Quotes for requests with a given query param, checked first
+    routes: Vec<(String, String, Value)>,
+    served: AtomicUsize,
+}
+
+impl Respond for QuoteResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let params: Vec<(String, String)> = request.url.query_pairs().into_owned().collect();
+        for (param, value, quote) in &self.routes {
+            if params.iter().any(|(k, v)| k == param && v == value) {
+                return ResponseTemplate::new(200).set_body_json(quote);
+            }
+        }
+
+        let n = self.served.fetch_add(1, Ordering::SeqCst);
+        let quote = &self.quotes[n.min(self.quotes.len() - 1)];
+        ResponseTemplate::new(200).set_body_json(quote)
+    }
+}
+
+/// Builds the unsigned v0 transaction for `/swap` from the request body, like the real API does
+struct SwapResponder {
+    pool: Pubkey,
+    blockhash: Hash,
+    stale: AtomicUsize,
+}
+
+impl Respond for SwapResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body: Value = match serde_json::from_slice(&request.body) {
+            Ok(body) => body,
+            Err(e) => return ResponseTemplate::new(400).set_body_string(e.to_string()),
+        };
+
+        let user = body["userPublicKey"]
+            .as_str()
+            .and_then(|user| Pubkey::from_str(user).ok());
+        let in_amount = body["quoteResponse"]["inAmount"]
+            .as_str()
+            .and_then(|amount| amount.parse::<u64>().ok());
+        let (Some(user), Some(in_amount)) = (user, in_amount) else {
+            return ResponseTemplate::new(400).set_body_string("bad swap request");
+        };
+
+        let stale = self
+            .stale
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        let blockhash = if stale {
+            Hash::new_unique()
+        } else {
+            self.blockhash
+        };
+
+        let ix = system_instruction::transfer(&user, &self.pool, in_amount);
+        let message = v0::Message::try_compile(&user, &[ix], &[], blockhash).unwrap();
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+            message: VersionedMessage::V0(message),
+        };
+
+        let swap_transaction =
+            base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&tx).unwrap());
+
+        ResponseTemplate::new(200).set_body_json(json!({
+            "swapTransaction": swap_transaction,
+            "lastValidBlockHeight": 1000,
+            "prioritizationFeeLamports": 0
+        }))
+    }
+}
+
+/// Submits to a `solana-program-test` bank instead of an rpc
+pub struct BanksSubmitter(pub BanksClient);
+
+#[async_trait]
+impl Submitter for BanksSubmitter {
+    async fn submit(&self, tx: &VersionedTransaction) -> anyhow::Result<Signature> {
+        //BanksClient needs &mut self, but it's just a handle so a clone is cheap
+        let mut client = self.0.clone();
+        //simulate first like the rpc's preflight does, the bank panics on an unknown blockhash
+        //if it's handed one straight away
+        client
+            .process_transaction_with_preflight(tx.clone())
+            .await
+            .map_err(|e| match e {
+                //surface the TransactionError like the rpc client does so it can be classified
+                BanksClientError::TransactionError(err)
+                | BanksClientError::SimulationError { err, .. } => anyhow::Error::new(err),
+                other => other.into(),
+            })?;
+        Ok(tx.signatures[0])
+    }
+
+    async fn signature_status(
+        &self,
+        signature: &Signature,
+    ) -> anyhow::Result<Option<transaction::Result<()>>> {
+        let mut client = self.0.clone();
+        let status = client.get_transaction_status(*signature).await?;
+        Ok(status.map(|status| status.err.map_or(Ok(()), Err)))
+    }
+
+    /// The bank only ever hands out its latest blockhash, so anything else can't land
+    async fn is_blockhash_valid(&self, blockhash: &Hash) -> anyhow::Result<bool> {
+        let mut client = self.0.clone();
+        Ok(client.get_latest_blockhash().await? == *blockhash)
+    }
+}
+
+/// Time only moves when something sleeps on it, or when the test moves it
+pub struct FakeClock(AtomicU64);
+
+impl FakeClock {
+    pub fn at(now: u64) -> Self {
+        Self(AtomicU64::new(now))
+    }
+
+    pub fn set(&self, now: u64) {
+        self.0.store(now, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Clock for FakeClock {
+    fn now(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    async fn sleep_until(&self, at: u64) {
+        self.0.fetch_max(at, Ordering::SeqCst);
+    }
+}
+
+/// Fills every swap in full at the quoted out amount and remembers the amounts it was asked for.
+/// Every swap goes through `before_send` first, with a made up signature.
+#[derive(Default)]
+pub struct FakeExecutor {
+    pub amounts: Mutex<Vec<u64>>,
+    /// The `min_out` guardrail of every swap it was asked for
+    pub min_outs: Mutex<Vec<Option<u64>>>,
+    /// Never returns once the swap is sent, so dropping the future is like crashing right then
+    pub hang_after_send: bool,
+    /// Notified once a swap has been through `before_send`, so a test knows when to "crash"
+    pub sent: Notify,
+    /// Fails every swap once it's sent as if the rpc went away while checking on it
+    pub unsettled_after_send: bool,
+    /// Whether `settle` finds swaps left pending landed, at their quoted amounts
+    pub pending_landed: bool,
+}
+
+impl FakeExecutor {
+    pub fn amounts(&self) -> Vec<u64> {
+        self.amounts.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Executor for FakeExecutor {
+    async fn execute_tracked(
+        &self,
+        pair: &ResolvedPair,
+        quote: QuoteResponse,
+        guardrails: &Guardrails,
+        before_send: &BeforeSend<'_>,
+    ) -> anyhow::Result<Fill> {
+        let signature = Signature::new_unique();
+        let pending = PendingSwap {
+            signature: signature.to_string(),
+            blockhash: Hash::default().to_string(),
+            quoted_in_amount: quote.in_amount,
+            quoted_out_amount: quote.out_amount,
+        };
+        before_send(&pending)?;
+        self.amounts.lock().unwrap().push(pair.amount);
+        self.min_outs.lock().unwrap().push(guardrails.min_out);
+        self.sent.notify_one();
+        if self.unsettled_after_send {
+            return Err(Unsettled {
+                pending,
+                source: anyhow::anyhow!("connection reset"),
+            }
+            .into());
+        }
+        if self.hang_after_send {
+            std::future::pending::<()>().await;
+        }
+        Ok(Fill {
+            signature,
+            in_amount: pair.amount,
+            out_amount: quote.out_amount,
+        })
+    }
+
+    async fn settle(
+        &self,
+        _pair: &ResolvedPair,
+        pending: &PendingSwap,
+    ) -> anyhow::Result<Option<Fill>> {
+        if !self.pending_landed {
+            return Ok(None);
+        }
+        Ok(Some(Fill {
+            signature: pending.signature.parse()?,
+            in_amount: pending.quoted_in_amount,
+            out_amount: pending.quoted_out_amount,
+        }))
+    }
+}
+
+/// A block engine that accepts every bundle as `bundle-1` and reports it with each of `statuses`
+/// in turn, repeating the last. With no statuses it rejects `sendBundle` instead.
+pub struct MockBlockEngine {
+    pub server: MockServer,
+}
+
+impl MockBlockEngine {
+    pub async fn start(statuses: Vec<&str>) -> Self {
+        let server = MockServer::start().await;
+
+        let send = if statuses.is_empty() {
+            ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": -32602, "message": "bundle contains an already processed transaction" }
+            }))
+        } else {
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": "bundle-1" }))
+        };
+        Mock::given(method("POST"))
+            .and(path("/api/v1/bundles"))
+            .and(body_partial_json(json!({ "method": "sendBundle" })))
+            .respond_with(send)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/v1/bundles"))
+            .and(body_partial_json(
+                json!({ "method": "getInflightBundleStatuses" }),
+            ))
+            .respond_with(BundleStatusResponder {
+                statuses: statuses.into_iter().map(str::to_string).collect(),
+                served: AtomicUsize::new(0),
+            })
+            .mount(&server)
+            .await;
+
+        Self { server }
+    }
+
+    pub fn url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The transactions of every bundle sent, decoded
+    pub async fn bundles(&self) -> Vec<Vec<VersionedTransaction>> {
+        let requests = self.server.received_requests().await.unwrap_or_default();
+        requests
+            .iter()
+            .filter_map(|request| serde_json::from_slice::<Value>(&request.body).ok())
+            .filter(|body| body["method"] == "sendBundle")
+            .map(|body| {
+                body["params"][0]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|tx| {
+                        let bytes = base64::engine::general_purpose::STANDARD
+                            .decode(tx.as_str().unwrap())
+                            .unwrap();
+                        bincode::deserialize(&bytes).unwrap()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// A bundle submitter pointed at this block engine that polls fast and gives up quickly
+    pub fn submitter<'a>(
+        &self,
+        payer: &'a Keypair,
+        tip_account: Pubkey,
+        fallback: &'a dyn Submitter,
+    ) -> BundleSubmitter<'a> {
+        BundleSubmitter {
+            http: reqwest::Client::new(),
+            url: self.url(),
+            payer,
+            tip_account,
+            tip_lamports: 10_000,
+            timeout: Duration::from_millis(200),
+            poll_interval: Duration::from_millis(10),
+            fallback,
+        }
+    }
+}
+
+struct BundleStatusResponder {
+    statuses: Vec<String>,
+    served: AtomicUsize,
+}
+
+impl Respond for BundleStatusResponder {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let n = self.served.fetch_add(1, Ordering::SeqCst);
+        let value = match self
+            .statuses
+            .get(n.min(self.statuses.len().saturating_sub(1)))
+        {
+            Some(status) => {
+                json!([{ "bundle_id": "bundle-1", "status": status, "landed_slot": null }])
+            }
+            None => json!([]),
+        };
+        ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "context": { "slot": 1 }, "value": value }
+        }))
+    }
+}
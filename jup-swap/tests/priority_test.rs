@@ -0,0 +1,130 @@
+use jup_swap::priority::{
+    compute_budget, max_fee, percentile, price_for_lamports, priority_fee_lamports, route_accounts,
+    FeeCapCheck, PriorityFee,
+};
+use jup_swap::swap::SwapCheck;
+use solana_program_test::tokio;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::VersionedTransaction;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+#[test]
+fn test_parse_priority_fee() {
+    assert_eq!(
+        "10000".parse::<PriorityFee>().unwrap(),
+        PriorityFee::Lamports(10_000)
+    );
+    assert_eq!("auto".parse::<PriorityFee>().unwrap(), PriorityFee::Auto);
+    assert_eq!("AUTO".parse::<PriorityFee>().unwrap(), PriorityFee::Auto);
+    assert_eq!(
+        "p90".parse::<PriorityFee>().unwrap(),
+        PriorityFee::Percentile(90)
+    );
+    assert!("p101".parse::<PriorityFee>().is_err());
+    assert!("fast".parse::<PriorityFee>().is_err());
+    assert!("-5".parse::<PriorityFee>().is_err());
+}
+
+#[test]
+fn test_percentile() {
+    assert_eq!(percentile(&[], 75), 0);
+    assert_eq!(percentile(&[42], 0), 42);
+
+    //nearest rank, the input doesn't have to be sorted
+    let fees = [0, 500, 100, 0, 10_000, 2_000, 0, 1_000, 0, 50];
+    assert_eq!(percentile(&fees, 50), 50);
+    assert_eq!(percentile(&fees, 75), 1_000);
+    assert_eq!(percentile(&fees, 90), 2_000);
+    assert_eq!(percentile(&fees, 100), 10_000);
+}
+
+#[test]
+fn test_fee_conversions() {
+    //1 micro lamport per CU over 1.4M CU rounds up to 2 lamports
+    assert_eq!(priority_fee_lamports(1, 1_400_000), 2);
+    assert_eq!(priority_fee_lamports(10_000, 200_000), 2_000);
+    assert_eq!(priority_fee_lamports(0, 1_400_000), 0);
+
+    assert_eq!(price_for_lamports(2_000, 200_000), 10_000);
+    assert_eq!(price_for_lamports(10_000, 0), 0);
+
+    //round trip never spends more than asked
+    let price = price_for_lamports(12_345, 1_400_000);
+    assert!(priority_fee_lamports(price, 1_400_000) <= 12_345);
+}
+
+#[test]
+fn test_compute_budget_of_message() {
+    let payer = Pubkey::new_unique();
+    let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+
+    let message = VersionedMessage::Legacy(Message::new(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+            ComputeBudgetInstruction::set_compute_unit_price(50_000),
+            transfer.clone(),
+        ],
+        Some(&payer),
+    ));
+    assert_eq!(compute_budget(&message), (300_000, 50_000));
+    assert_eq!(max_fee(&message), 5000 + 15_000);
+
+    //no compute budget instructions: 200k per instruction and no priority fee
+    let message =
+        VersionedMessage::Legacy(Message::new(&[transfer.clone(), transfer], Some(&payer)));
+    assert_eq!(compute_budget(&message), (400_000, 0));
+    assert_eq!(max_fee(&message), 5000);
+}
+
+#[test]
+fn test_route_accounts() {
+    let quote = quote_response(SOL, BONK, 1_000_000, 10_000_000, 50, "0");
+    assert_eq!(route_accounts(&quote), vec![MOCK_AMM]);
+}
+
+#[tokio::test]
+async fn test_fee_cap_counts_the_bundle() {
+    let payer = Pubkey::new_unique();
+    let pair = sol_to_bonk(10_000_000);
+    let quote = quote_response(SOL, BONK, 10_000_000, 1_000_000_000, 50, "0");
+    //5000 base plus 5000 priority
+    let message = VersionedMessage::Legacy(Message::new(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(100_000),
+            ComputeBudgetInstruction::set_compute_unit_price(50_000),
+            system_instruction::transfer(&payer, &Pubkey::new_unique(), 1),
+        ],
+        Some(&payer),
+    ));
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message,
+    };
+
+    assert!(FeeCapCheck::new(None, 15_000).unwrap().is_none());
+
+    //the swap alone fits, with a 10000 tip and the tip transaction's 5000 it doesn't
+    let without_bundle = FeeCapCheck::new(Some(20_000), 0).unwrap().unwrap();
+    assert!(without_bundle
+        .check(&payer, &pair, &quote, &tx)
+        .await
+        .is_ok());
+    let with_bundle = FeeCapCheck::new(Some(20_000), 15_000).unwrap().unwrap();
+    let e = with_bundle
+        .check(&payer, &pair, &quote, &tx)
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("plus 15000 for the bundle"), "{}", e);
+    let raised = FeeCapCheck::new(Some(25_000), 15_000).unwrap().unwrap();
+    assert!(raised.check(&payer, &pair, &quote, &tx).await.is_ok());
+
+    //a tip that leaves less than a signature's fee for the swap is refused up front
+    assert!(FeeCapCheck::new(Some(20_000), 15_001).is_err());
+    assert!(FeeCapCheck::new(Some(20_000), 15_000).is_ok());
+}
@@ -0,0 +1,130 @@
+use jup_swap::rebalance::{allocations, plan_rebalance, Asset, Leg, RebalanceConfig};
+use solana_sdk::pubkey::Pubkey;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+fn usdc() -> Pubkey {
+    Pubkey::new_from_array([7; 32])
+}
+
+/// 50% SOL at 100 USDC, 30% USDC, 20% BONK at 0.00002 USDC
+fn portfolio(sol: u64, usdc_amount: u64, bonk: u64) -> Vec<Asset> {
+    vec![
+        Asset {
+            mint: SOL,
+            decimals: 9,
+            amount: sol,
+            price: 100.0,
+            target_pct: 50.0,
+            slippage_bps: 50,
+        },
+        Asset {
+            mint: usdc(),
+            decimals: 6,
+            amount: usdc_amount,
+            price: 1.0,
+            target_pct: 30.0,
+            slippage_bps: 10,
+        },
+        Asset {
+            mint: BONK,
+            decimals: 5,
+            amount: bonk,
+            price: 0.00002,
+            target_pct: 20.0,
+            slippage_bps: 150,
+        },
+    ]
+}
+
+/// Applies the legs at the planner's prices, to check where they leave the portfolio
+fn apply(assets: &[Asset], legs: &[Leg]) -> Vec<Asset> {
+    let mut after = assets.to_vec();
+    for leg in legs {
+        let seller = after.iter().position(|a| a.mint == leg.input_mint).unwrap();
+        let buyer = after
+            .iter()
+            .position(|a| a.mint == leg.output_mint)
+            .unwrap();
+        after[seller].amount -= leg.amount;
+        let bought = leg.value / after[buyer].price * 10f64.powi(after[buyer].decimals as i32);
+        after[buyer].amount += bought as u64;
+    }
+    after
+}
+
+#[test]
+fn test_on_target_within_tolerance_trades_nothing() {
+    //500 USDC of SOL, 300 USDC, 200 USDC of BONK
+    let assets = portfolio(5_000_000_000, 300_000_000, 1_000_000_000_000);
+    assert_eq!(plan_rebalance(&assets, 1.0, 1.0), vec![]);
+
+    //52/29/19 is within 2 points of every target
+    let assets = portfolio(5_200_000_000, 290_000_000, 950_000_000_000);
+    assert_eq!(plan_rebalance(&assets, 2.0, 1.0), vec![]);
+}
+
+#[test]
+fn test_overweight_token_is_sold_into_the_others() {
+    //800 USDC of SOL, 100 USDC, 100 USDC of BONK, so 80/10/10
+    let assets = portfolio(8_000_000_000, 100_000_000, 500_000_000_000);
+    let legs = plan_rebalance(&assets, 1.0, 1.0);
+
+    //300 USDC worth of SOL has to go: 200 into USDC and 100 into BONK
+    assert_eq!(legs.len(), 2);
+    assert_eq!(legs[0].input_mint, SOL);
+    assert_eq!(legs[0].output_mint, usdc());
+    assert_eq!(legs[0].amount, 2_000_000_000);
+    assert_eq!(legs[0].slippage_bps, 50);
+    assert_eq!(legs[1].input_mint, SOL);
+    assert_eq!(legs[1].output_mint, BONK);
+    assert_eq!(legs[1].amount, 1_000_000_000);
+    //a leg gets the looser slippage of its two tokens
+    assert_eq!(legs[1].slippage_bps, 150);
+
+    let after = allocations(&apply(&assets, &legs));
+    for (pct, target) in after.iter().zip([50.0, 30.0, 20.0]) {
+        assert!((pct - target).abs() < 0.01, "{} vs {}", pct, target);
+    }
+}
+
+#[test]
+fn test_legs_match_biggest_excess_with_biggest_shortfall() {
+    //0 SOL, 600 USDC, 400 USDC of BONK: SOL is 500 short, USDC 300 over, BONK 200 over
+    let assets = portfolio(0, 600_000_000, 2_000_000_000_000);
+    let legs = plan_rebalance(&assets, 1.0, 1.0);
+
+    assert_eq!(legs.len(), 2);
+    assert_eq!((legs[0].input_mint, legs[0].output_mint), (usdc(), SOL));
+    assert_eq!(legs[0].amount, 300_000_000);
+    assert_eq!((legs[1].input_mint, legs[1].output_mint), (BONK, SOL));
+    assert_eq!(legs[1].amount, 1_000_000_000_000);
+}
+
+#[test]
+fn test_dust_legs_are_dropped() {
+    //SOL is 300 over, but the 100 BONK is short is below the minimum trade
+    let assets = portfolio(8_000_000_000, 100_000_000, 500_000_000_000);
+    let legs = plan_rebalance(&assets, 1.0, 150.0);
+    assert_eq!(legs.len(), 1);
+    assert_eq!(legs[0].output_mint, usdc());
+
+    //nothing to value, nothing to do
+    assert_eq!(plan_rebalance(&portfolio(0, 0, 0), 1.0, 1.0), vec![]);
+}
+
+#[test]
+fn test_config_targets_have_to_add_up() {
+    let config = RebalanceConfig::from_json(
+        r#"{ "targets": { "SOL": 50, "USDC": 30, "BONK": 20 }, "slippage_bps": { "BONK": 150 } }"#,
+    )
+    .unwrap();
+    assert_eq!(config.reference, "USDC");
+    assert_eq!(config.tolerance_pct, 1.0);
+    assert_eq!(config.slippage_bps["BONK"], 150);
+
+    assert!(RebalanceConfig::from_json(r#"{ "targets": { "SOL": 50, "USDC": 30 } }"#).is_err());
+    assert!(RebalanceConfig::from_json(r#"{ "targets": { "SOL": 110, "USDC": -10 } }"#).is_err());
+    assert!(RebalanceConfig::from_json(r#"{ "targets": {} }"#).is_err());
+}
@@ -0,0 +1,129 @@
+use jup_swap::receipt::{append_journal, Receipt, TokenBalance, TxBalances};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+const ATA_RENT: u64 = 2_039_280;
+
+/// SOL -> BONK where the BONK ATA at index 3 gets created by the swap
+fn balances(wallet: Pubkey, out_amount: u64, fee: u64) -> TxBalances {
+    let sol_in = 10_000_000;
+    TxBalances {
+        fee,
+        signatures: 1,
+        block_time: Some(1_700_000_000),
+        pre_lamports: vec![1_000_000_000, 1, 1, 0],
+        post_lamports: vec![1_000_000_000 - sol_in - fee - ATA_RENT, 1, 1, ATA_RENT],
+        pre_tokens: vec![],
+        post_tokens: vec![TokenBalance {
+            account_index: 3,
+            mint: BONK,
+            owner: Some(wallet),
+            amount: out_amount,
+        }],
+    }
+}
+
+#[test]
+fn test_receipt_from_balances() {
+    let wallet = Pubkey::new_unique();
+    let quote = quote_response(SOL, BONK, 10_000_000, 100_000_000, 50, "0");
+
+    let receipt = Receipt::compute(
+        Signature::default(),
+        &balances(wallet, 99_500_000, 15_000),
+        &wallet,
+        &sol_to_bonk(10_000_000),
+        &quote,
+        true,
+    );
+
+    assert_eq!(receipt.in_amount, 10_000_000);
+    assert_eq!(receipt.out_amount, 99_500_000);
+    assert_eq!(receipt.network_fee, 5000);
+    assert_eq!(receipt.priority_fee, 10_000);
+    assert_eq!(receipt.rent, ATA_RENT);
+
+    //0.01 SOL for 1000 BONK quoted, 995 realized
+    assert!((receipt.quoted_price() - 100_000.0).abs() < 1e-6);
+    assert!((receipt.effective_price() - 99_500.0).abs() < 1e-6);
+    assert!((receipt.slippage_bps() - 50.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_token_balances_of_other_owners_ignored() {
+    let wallet = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+    let quote = quote_response(SOL, BONK, 10_000_000, 100_000_000, 50, "0");
+
+    let mut balances = balances(wallet, 100_000_000, 5000);
+    balances.pre_tokens.push(TokenBalance {
+        account_index: 1,
+        mint: BONK,
+        owner: Some(pool),
+        amount: 1_000_000_000,
+    });
+    balances.post_tokens.push(TokenBalance {
+        account_index: 1,
+        mint: BONK,
+        owner: Some(pool),
+        amount: 900_000_000,
+    });
+
+    let receipt = Receipt::compute(
+        Signature::default(),
+        &balances,
+        &wallet,
+        &sol_to_bonk(10_000_000),
+        &quote,
+        true,
+    );
+    assert_eq!(receipt.out_amount, 100_000_000);
+    assert_eq!(receipt.priority_fee, 0);
+    assert!(receipt.slippage_bps().abs() < 1e-6);
+}
+
+#[test]
+fn test_journal_formats() {
+    let wallet = Pubkey::new_unique();
+    let quote = quote_response(SOL, BONK, 10_000_000, 100_000_000, 50, "0");
+    let receipt = Receipt::compute(
+        Signature::default(),
+        &balances(wallet, 99_500_000, 15_000),
+        &wallet,
+        &sol_to_bonk(10_000_000),
+        &quote,
+        true,
+    );
+
+    let dir = std::env::temp_dir().join(format!("jup-swap-journal-{}", Pubkey::new_unique()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    //csv gets a header once, then a row per swap
+    let csv = dir.join("trades.csv");
+    append_journal(&csv, &receipt).unwrap();
+    append_journal(&csv, &receipt).unwrap();
+    let contents = std::fs::read_to_string(&csv).unwrap();
+    let lines: Vec<_> = contents.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("block_time,signature,"));
+    assert!(lines[1].starts_with("1700000000,"));
+    assert!(lines[1].contains(",0.01,995,0.01,1000,"));
+    assert_eq!(lines[0].split(',').count(), lines[1].split(',').count());
+
+    let jsonl = dir.join("trades.jsonl");
+    append_journal(&jsonl, &receipt).unwrap();
+    append_journal(&jsonl, &receipt).unwrap();
+    let contents = std::fs::read_to_string(&jsonl).unwrap();
+    let entries: Vec<serde_json::Value> = contents
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["out_amount"], "995");
+    assert_eq!(entries[0]["rent_lamports"], ATA_RENT);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,128 @@
+use jup_swap::retry::{classify, FailureKind, RetryPolicy, JUPITER_SLIPPAGE_EXCEEDED};
+use jup_swap::simulate::SimulationFailed;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+#[test]
+fn test_classify_transaction_errors() {
+    let slippage =
+        TransactionError::InstructionError(3, InstructionError::Custom(JUPITER_SLIPPAGE_EXCEEDED));
+    assert_eq!(
+        classify(&anyhow::Error::new(slippage.clone())),
+        FailureKind::Slippage
+    );
+
+    //the same error coming back from the rpc client
+    let client_err = ClientError::from(ClientErrorKind::TransactionError(slippage));
+    assert_eq!(classify(&client_err.into()), FailureKind::Slippage);
+
+    assert_eq!(
+        classify(&anyhow::Error::new(TransactionError::BlockhashNotFound)),
+        FailureKind::Expired
+    );
+    assert_eq!(
+        classify(&anyhow::Error::new(
+            TransactionError::InsufficientFundsForFee
+        )),
+        FailureKind::Other
+    );
+
+    //some other program's custom error is not a slippage error
+    let other = TransactionError::InstructionError(0, InstructionError::Custom(1));
+    assert_eq!(classify(&anyhow::Error::new(other)), FailureKind::Other);
+}
+
+#[test]
+fn test_classify_slippage_in_simulation() {
+    let logs = vec![
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 invoke [1]".to_string(),
+        "Program log: Error: SlippageToleranceExceeded".to_string(),
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 failed: custom program error: 0x1771"
+            .to_string(),
+    ];
+
+    //a stale route fails our own simulation before anything is sent
+    let simulation = SimulationFailed {
+        err: TransactionError::InstructionError(
+            3,
+            InstructionError::Custom(JUPITER_SLIPPAGE_EXCEEDED),
+        ),
+        logs: logs.clone(),
+    };
+    assert_eq!(classify(&simulation.into()), FailureKind::Slippage);
+
+    //the logs are enough when the error itself doesn't say
+    let simulation = SimulationFailed {
+        err: TransactionError::InstructionError(3, InstructionError::ProgramFailedToComplete),
+        logs: logs.clone(),
+    };
+    assert_eq!(classify(&simulation.into()), FailureKind::Slippage);
+
+    //and when they only come back as text
+    let text = anyhow::anyhow!("simulation failed:\n{}", logs.join("\n"));
+    assert_eq!(classify(&text), FailureKind::Slippage);
+
+    //0x1771 from some other program is not Jupiter running out of slippage
+    let other = SimulationFailed {
+        err: TransactionError::InstructionError(3, InstructionError::ProgramFailedToComplete),
+        logs: vec![
+            "Program 11111111111111111111111111111111 failed: custom program error: 0x1771"
+                .to_string(),
+        ],
+    };
+    assert_eq!(classify(&other.into()), FailureKind::Other);
+}
+
+#[test]
+fn test_classify_client_errors() {
+    let io = ClientError::from(std::io::Error::new(
+        std::io::ErrorKind::ConnectionReset,
+        "connection reset",
+    ));
+    assert_eq!(classify(&io.into()), FailureKind::Transport);
+
+    assert_eq!(
+        classify(&anyhow::anyhow!("something else")),
+        FailureKind::Other
+    );
+    assert!(!FailureKind::Other.is_retryable());
+    assert!(FailureKind::Transport.is_retryable());
+}
+
+#[test]
+fn test_slippage_escalation() {
+    let policy = RetryPolicy {
+        max_attempts: 5,
+        slippage_step_bps: 25,
+        max_slippage_bps: 100,
+        ..RetryPolicy::default()
+    };
+
+    assert_eq!(
+        policy.next_slippage_bps(50, FailureKind::Slippage),
+        Some(75)
+    );
+    assert_eq!(
+        policy.next_slippage_bps(90, FailureKind::Slippage),
+        Some(100)
+    );
+    assert_eq!(policy.next_slippage_bps(100, FailureKind::Slippage), None);
+
+    //only slippage failures raise the slippage
+    assert_eq!(policy.next_slippage_bps(50, FailureKind::Expired), Some(50));
+    assert_eq!(
+        policy.next_slippage_bps(100, FailureKind::Transport),
+        Some(100)
+    );
+
+    //no step means requote at the same slippage
+    let policy = RetryPolicy {
+        max_attempts: 5,
+        ..RetryPolicy::default()
+    };
+    assert_eq!(
+        policy.next_slippage_bps(50, FailureKind::Slippage),
+        Some(50)
+    );
+}
@@ -0,0 +1,166 @@
+use jup_swap::message::MessageAccount;
+use jup_swap::simulate::{unexpected_writable, BalanceSource, SimulationReport};
+use jup_swap::swap::ResolvedPair;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+const USDC: Pubkey = solana_sdk::pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+fn pair(input_mint: Pubkey, output_mint: Pubkey, exact_out: bool) -> ResolvedPair {
+    ResolvedPair {
+        input_mint,
+        output_mint,
+        input_decimals: 6,
+        output_decimals: 5,
+        amount: 1_000_000,
+        slippage_bps: 50,
+        exact_out,
+    }
+}
+
+#[test]
+fn test_output_at_threshold_passes() {
+    //10_000_000 out with 50 bps slippage has a threshold of 9_950_000
+    let quote = quote_response(USDC, BONK, 1_000_000, 10_000_000, 50, "0");
+    let input = BalanceSource::TokenAccount(Pubkey::new_unique());
+    let output = BalanceSource::TokenAccount(Pubkey::new_unique());
+
+    let report = SimulationReport::from_balances(
+        input,
+        output,
+        (5_000_000, 4_000_000),
+        (0, 9_950_000),
+        5000,
+    );
+    assert_eq!(report.input_spent, 1_000_000);
+    assert_eq!(report.output_received, 9_950_000);
+    report.check(&quote, &pair(USDC, BONK, false)).unwrap();
+}
+
+#[test]
+fn test_output_below_threshold_fails() {
+    let quote = quote_response(USDC, BONK, 1_000_000, 10_000_000, 50, "0");
+    let report = SimulationReport::from_balances(
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        (5_000_000, 4_000_000),
+        (0, 9_949_999),
+        5000,
+    );
+
+    let err = report.check(&quote, &pair(USDC, BONK, false)).unwrap_err();
+    assert!(err.to_string().contains("below the quoted minimum"));
+}
+
+#[test]
+fn test_spending_more_than_quoted_fails() {
+    let quote = quote_response(USDC, BONK, 1_000_000, 10_000_000, 50, "0");
+    let report = SimulationReport::from_balances(
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        (5_000_000, 3_999_999),
+        (0, 10_000_000),
+        5000,
+    );
+
+    let err = report.check(&quote, &pair(USDC, BONK, false)).unwrap_err();
+    assert!(err.to_string().contains("above the quoted maximum"));
+}
+
+#[test]
+fn test_lamport_deltas_back_out_the_fee() {
+    let wallet = Pubkey::new_unique();
+    let quote = quote_response(SOL, BONK, 1_000_000, 10_000_000, 50, "0");
+
+    //SOL in: the wallet pays the swap amount plus the fee
+    let report = SimulationReport::from_balances(
+        BalanceSource::Lamports(wallet),
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        (5_000_000, 5_000_000 - 1_000_000 - 5000),
+        (0, 10_000_000),
+        5000,
+    );
+    assert_eq!(report.input_spent, 1_000_000);
+    report.check(&quote, &pair(SOL, BONK, false)).unwrap();
+
+    //SOL out: the fee comes off what arrives
+    let report = SimulationReport::from_balances(
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        BalanceSource::Lamports(wallet),
+        (10_000_000, 0),
+        (5_000_000, 5_000_000 + 1_000_000 - 5000),
+        5000,
+    );
+    assert_eq!(report.output_received, 1_000_000);
+}
+
+#[test]
+fn test_exact_out_checks_max_in() {
+    //exact out: the threshold is the max in, 1_000_000 + 50 bps
+    let mut quote = quote_response(USDC, BONK, 1_000_000, 10_000_000, 50, "0");
+    quote.other_amount_threshold = 1_005_000;
+
+    let report = SimulationReport::from_balances(
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        (5_000_000, 5_000_000 - 1_005_000),
+        (0, 10_000_000),
+        5000,
+    );
+    report.check(&quote, &pair(USDC, BONK, true)).unwrap();
+
+    let report = SimulationReport::from_balances(
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        BalanceSource::TokenAccount(Pubkey::new_unique()),
+        (5_000_000, 5_000_000 - 1_005_001),
+        (0, 10_000_000),
+        5000,
+    );
+    assert!(report.check(&quote, &pair(USDC, BONK, true)).is_err());
+}
+
+#[test]
+fn test_unexpected_writable_wallet_accounts() {
+    let input_ata = Pubkey::new_unique();
+    let output_ata = Pubkey::new_unique();
+    let other_wallet_ata = Pubkey::new_unique();
+    let readonly_wallet_ata = Pubkey::new_unique();
+    let pool = Pubkey::new_unique();
+
+    let account = |pubkey, writable| MessageAccount {
+        pubkey,
+        signer: false,
+        writable,
+        from_lookup_table: true,
+    };
+    let accounts = vec![
+        account(input_ata, true),
+        account(output_ata, true),
+        account(pool, true),
+        account(other_wallet_ata, true),
+        account(readonly_wallet_ata, false),
+    ];
+    let owned: HashSet<Pubkey> = [input_ata, output_ata, other_wallet_ata, readonly_wallet_ata]
+        .into_iter()
+        .collect();
+
+    assert_eq!(
+        unexpected_writable(&accounts, &owned, &[input_ata, output_ata]),
+        vec![other_wallet_ata]
+    );
+
+    let quote = quote_response(USDC, BONK, 1_000_000, 10_000_000, 50, "0");
+    let mut report = SimulationReport::from_balances(
+        BalanceSource::TokenAccount(input_ata),
+        BalanceSource::TokenAccount(output_ata),
+        (5_000_000, 4_000_000),
+        (0, 10_000_000),
+        5000,
+    );
+    report.unexpected_writable = vec![other_wallet_ata];
+    let err = report.check(&quote, &pair(USDC, BONK, false)).unwrap_err();
+    assert!(err.to_string().contains(&other_wallet_ata.to_string()));
+}
@@ -0,0 +1,261 @@
+use async_trait::async_trait;
+use jup_swap::quote::Guardrails;
+use jup_swap::retry::{swap_with_retries, PendingSwap, RetryPolicy, Unsettled};
+use jup_swap::submit::Submitter;
+use jup_swap::swap::{execute_swap, SwapConfig};
+use jupiter_swap_api_client::quote::QuoteRequest;
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use solana_program_test::{tokio, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::{self, VersionedTransaction};
+use std::sync::Mutex;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+#[tokio::test]
+async fn test_quote_swap_sign_submit() -> anyhow::Result<()> {
+    let wallet = Keypair::new();
+    let pool = Pubkey::new_unique();
+
+    let mut validator = ProgramTest::default();
+    validator.add_account(
+        wallet.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut context = validator.start_with_context().await;
+
+    let mock = MockJupiter::start(
+        quote_json(SOL, BONK, 10_000_000, 123_456_789, 50, "0.0001"),
+        pool,
+        context.last_blockhash,
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+
+    let quote = jup
+        .quote(&QuoteRequest {
+            amount: 10_000_000,
+            input_mint: SOL,
+            output_mint: BONK,
+            slippage_bps: 50,
+            ..QuoteRequest::default()
+        })
+        .await?;
+
+    assert_eq!(quote.in_amount, 10_000_000);
+    assert_eq!(quote.out_amount, 123_456_789);
+    assert_eq!(quote.route_plan.len(), 1);
+
+    let pair = sol_to_bonk(10_000_000);
+    let submitter = BanksSubmitter(context.banks_client.clone());
+    let sig = execute_swap(
+        &submitter,
+        &jup,
+        &wallet,
+        &pair,
+        quote,
+        SwapConfig::default(),
+        &[],
+    )
+    .await?;
+
+    //the mock swap moves the in amount from the wallet to the pool
+    let pool_balance = context.banks_client.get_balance(pool).await?;
+    assert_eq!(pool_balance, 10_000_000);
+
+    let status = context.banks_client.get_transaction_status(sig).await?;
+    assert!(status.is_some(), "swap transaction should have landed");
+
+    //the client should have asked for the quote we built
+    let requests = mock.requests().await;
+    let quote_req = requests
+        .iter()
+        .find(|r| r.url.path() == "/quote")
+        .expect("no quote request");
+    let params: std::collections::HashMap<_, _> = quote_req.url.query_pairs().collect();
+    assert_eq!(params["inputMint"], SOL.to_string());
+    assert_eq!(params["outputMint"], BONK.to_string());
+    assert_eq!(params["amount"], "10000000");
+    assert_eq!(params["slippageBps"], "50");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_swap_signed_by_wrong_wallet_fails() -> anyhow::Result<()> {
+    let wallet = Keypair::new();
+    let pool = Pubkey::new_unique();
+
+    let mut validator = ProgramTest::default();
+    validator.add_account(
+        wallet.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let context = validator.start_with_context().await;
+
+    let mock = MockJupiter::start(
+        quote_json(SOL, BONK, 10_000_000, 123_456_789, 50, "0.0001"),
+        pool,
+        context.last_blockhash,
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let quote = quote_response(SOL, BONK, 10_000_000, 123_456_789, 50, "0.0001");
+
+    //the transaction is built for `wallet` as fee payer, so signing it with anyone else has to fail
+    let wrong_wallet = Keypair::new();
+    let swap_tx =
+        jup_swap::swap::build_swap_transaction(&jup, wallet.pubkey(), quote, Default::default())
+            .await?;
+    assert!(jup_swap::swap::sign_swap_transaction(swap_tx, &wrong_wallet).is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expired_swap_is_retried_once() -> anyhow::Result<()> {
+    let wallet = Keypair::new();
+    let pool = Pubkey::new_unique();
+
+    let mut validator = ProgramTest::default();
+    validator.add_account(
+        wallet.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut context = validator.start_with_context().await;
+
+    //the first swap transaction comes back with a blockhash the bank doesn't know
+    let mock = MockJupiter::start_with_stale_swaps(
+        quote_json(SOL, BONK, 10_000_000, 123_456_789, 50, "0.0001"),
+        pool,
+        context.last_blockhash,
+        1,
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+
+    let pair = sol_to_bonk(10_000_000);
+    let quote = jup.quote(&pair.quote_request()).await?;
+
+    let submitter = BanksSubmitter(context.banks_client.clone());
+    let sent: Mutex<Vec<PendingSwap>> = Mutex::default();
+    let outcome = swap_with_retries(
+        &submitter,
+        &jup,
+        &wallet,
+        &pair,
+        quote,
+        &Guardrails::default(),
+        &|| Ok(SwapConfig::default()),
+        &[],
+        &RetryPolicy {
+            max_attempts: 3,
+            ..RetryPolicy::default()
+        },
+        &|pending| {
+            sent.lock().unwrap().push(pending.clone());
+            Ok(())
+        },
+    )
+    .await?;
+
+    assert_eq!(outcome.attempts, 2);
+    //both attempts were handed over before they went out, the second one is what landed
+    let sent = sent.into_inner().unwrap();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[1].signature, outcome.signature.to_string());
+    assert_eq!(sent[1].blockhash, context.last_blockhash.to_string());
+    assert_ne!(sent[0].blockhash, sent[1].blockhash);
+
+    //exactly one swap landed
+    let pool_balance = context.banks_client.get_balance(pool).await?;
+    assert_eq!(pool_balance, 10_000_000);
+
+    let requests = mock.requests().await;
+    let swaps = requests.iter().filter(|r| r.url.path() == "/swap").count();
+    let quotes = requests.iter().filter(|r| r.url.path() == "/quote").count();
+    assert_eq!(swaps, 2);
+    assert_eq!(quotes, 2, "the retry should requote");
+
+    Ok(())
+}
+
+/// An rpc that goes away as soon as the swap is sent, so there's no telling whether it landed
+struct DeadRpc;
+
+#[async_trait]
+impl Submitter for DeadRpc {
+    async fn submit(&self, _tx: &VersionedTransaction) -> anyhow::Result<Signature> {
+        Err(anyhow::anyhow!("connection reset"))
+    }
+
+    async fn signature_status(
+        &self,
+        _signature: &Signature,
+    ) -> anyhow::Result<Option<transaction::Result<()>>> {
+        Err(anyhow::anyhow!("connection reset"))
+    }
+
+    async fn is_blockhash_valid(&self, _blockhash: &Hash) -> anyhow::Result<bool> {
+        Err(anyhow::anyhow!("connection reset"))
+    }
+}
+
+#[tokio::test]
+async fn test_swap_with_unknown_landing_is_unsettled() -> anyhow::Result<()> {
+    let wallet = Keypair::new();
+    let mock = MockJupiter::start(
+        quote_json(SOL, BONK, 10_000_000, 123_456_789, 50, "0.0001"),
+        Pubkey::new_unique(),
+        Hash::default(),
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+
+    let pair = sol_to_bonk(10_000_000);
+    let quote = jup.quote(&pair.quote_request()).await?;
+    let sent: Mutex<Vec<PendingSwap>> = Mutex::default();
+    let err = swap_with_retries(
+        &DeadRpc,
+        &jup,
+        &wallet,
+        &pair,
+        quote,
+        &Guardrails::default(),
+        &|| Ok(SwapConfig::default()),
+        &[],
+        &RetryPolicy {
+            max_attempts: 3,
+            ..RetryPolicy::default()
+        },
+        &|pending| {
+            sent.lock().unwrap().push(pending.clone());
+            Ok(())
+        },
+    )
+    .await
+    .unwrap_err();
+
+    //it may still land, so it isn't retried and comes back as the swap that was sent
+    let unsettled = err
+        .downcast_ref::<Unsettled>()
+        .expect("should be unsettled");
+    let sent = sent.into_inner().unwrap();
+    assert_eq!(sent, vec![unsettled.pending.clone()]);
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use jup_swap::tokens::{raw_to_ui, ui_to_raw, TokenRegistry};
+use solana_sdk::pubkey;
+
+#[test]
+fn test_ui_to_raw() {
+    assert_eq!(ui_to_raw("0.01", 9).unwrap(), 10_000_000);
+    assert_eq!(ui_to_raw("1.5", 9).unwrap(), 1_500_000_000);
+    assert_eq!(ui_to_raw(".5", 6).unwrap(), 500_000);
+    assert_eq!(ui_to_raw("42", 0).unwrap(), 42);
+
+    //more decimal places than the mint has
+    assert!(ui_to_raw("1.1234567", 6).is_err());
+    assert!(ui_to_raw("abc", 6).is_err());
+    assert!(ui_to_raw("-1", 6).is_err());
+    assert!(ui_to_raw(".", 6).is_err());
+    //overflows a u64 once scaled
+    assert!(ui_to_raw("99999999999999", 9).is_err());
+}
+
+#[test]
+fn test_raw_to_ui() {
+    assert_eq!(raw_to_ui(10_000_000, 9), "0.01");
+    assert_eq!(raw_to_ui(1_500_000_000, 9), "1.5");
+    assert_eq!(raw_to_ui(5, 9), "0.000000005");
+    assert_eq!(raw_to_ui(1_000_000, 6), "1");
+    assert_eq!(raw_to_ui(42, 0), "42");
+}
+
+#[test]
+fn test_registry_resolve() {
+    let registry = TokenRegistry::from_json(
+        r#"{ "SOL": "So11111111111111111111111111111111111111112", "Bonk": "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263" }"#,
+    )
+    .unwrap();
+
+    let sol = pubkey!("So11111111111111111111111111111111111111112");
+    let bonk = pubkey!("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263");
+    let usdc = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    assert_eq!(registry.resolve("SOL").unwrap(), sol);
+    assert_eq!(registry.resolve("bonk").unwrap(), bonk);
+    //mints that aren't in the registry still work
+    assert_eq!(registry.resolve(&usdc.to_string()).unwrap(), usdc);
+    assert!(registry.resolve("NOTATOKEN").is_err());
+
+    assert_eq!(registry.symbol(&bonk), "BONK");
+    assert_eq!(registry.symbol(&usdc), usdc.to_string());
+}
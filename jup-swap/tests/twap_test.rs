@@ -0,0 +1,156 @@
+use jup_swap::schedule::Clock;
+use jup_swap::twap::{Twap, TwapPlan};
+use jupiter_swap_api_client::JupiterSwapApiClient;
+use serde_json::Value;
+use solana_program_test::tokio;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use std::time::Duration;
+
+mod mock_jupiter;
+use mock_jupiter::*;
+
+const START: u64 = 1_700_000_000;
+const HOUR: u64 = 60 * 60;
+
+/// 0.04 SOL in 4 slices over 4 hours
+fn plan() -> TwapPlan {
+    TwapPlan {
+        total: 40_000_000,
+        slices: 4,
+        duration: Duration::from_secs(4 * HOUR),
+        jitter_pct: 0,
+        max_cumulative_impact_pct: None,
+        max_price_degradation_pct: None,
+    }
+}
+
+/// 123456.789 BONK per SOL
+fn good(price_impact: &str) -> Value {
+    quote_json(SOL, BONK, 10_000_000, 123_456_789, 50, price_impact)
+}
+
+/// 100k BONK per SOL
+fn worse() -> Value {
+    quote_json(SOL, BONK, 10_000_000, 100_000_000, 50, "0.0001")
+}
+
+fn schedule(plan: &TwapPlan) -> Vec<(u64, u64)> {
+    let times = plan.times(START, &mut || 0.5);
+    times.into_iter().zip(plan.amounts()).collect()
+}
+
+#[test]
+fn test_slices_split_amount_and_stay_within_duration() {
+    let plan = TwapPlan {
+        total: 10,
+        slices: 4,
+        jitter_pct: 50,
+        ..plan()
+    };
+    //the first slices take the remainder
+    assert_eq!(plan.amounts(), vec![3, 3, 2, 2]);
+
+    //the middle of the jitter range is the even spacing
+    let even: Vec<u64> = (0..4).map(|i| START + i * HOUR).collect();
+    assert_eq!(plan.times(START, &mut || 0.5), even);
+
+    //the most jitter moves a slice half an interval, but never before the start
+    let early = plan.times(START, &mut || 0.0);
+    assert_eq!(early[0], START);
+    assert_eq!(early[3], START + 3 * HOUR - HOUR / 2);
+    let late = plan.times(START, &mut || 0.999_999);
+    assert_eq!(late[3], START + 3 * HOUR + HOUR / 2);
+
+    //whatever the randomness, slices stay in order and inside the duration
+    let mut random = jup_swap::twap::time_seeded_random();
+    let times = plan.times(START, &mut random);
+    assert!(times.windows(2).all(|w| w[0] <= w[1]));
+    assert!(times
+        .iter()
+        .all(|at| (START..=START + 4 * HOUR).contains(at)));
+}
+
+#[tokio::test]
+async fn test_twap_fills_every_slice_and_reports_vwap() -> anyhow::Result<()> {
+    let mock = MockJupiter::start(good("0.0001"), Pubkey::new_unique(), Hash::default()).await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+
+    let twap = Twap {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(40_000_000),
+        plan: plan(),
+    };
+    let summary = twap.run(&schedule(&twap.plan)).await;
+
+    assert_eq!(summary.aborted, None);
+    assert_eq!(executor.amounts(), vec![10_000_000; 4]);
+    assert_eq!(clock.now(), START + 3 * HOUR);
+    assert_eq!(summary.in_amount(), 40_000_000);
+    assert_eq!(summary.out_amount(), 4 * 123_456_789);
+    let vwap = summary.vwap(&twap.pair).unwrap();
+    assert!((vwap - 123_456.789).abs() < 1e-6, "{}", vwap);
+    assert!((summary.cumulative_impact_pct - 0.04).abs() < 1e-9);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_twap_aborts_when_average_price_degrades() -> anyhow::Result<()> {
+    let mock = MockJupiter::start_with_quotes(
+        vec![good("0.0001"), good("0.0001"), worse()],
+        Pubkey::new_unique(),
+        Hash::default(),
+    )
+    .await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+
+    let twap = Twap {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(40_000_000),
+        plan: TwapPlan {
+            max_price_degradation_pct: Some(2.0),
+            ..plan()
+        },
+    };
+    let summary = twap.run(&schedule(&twap.plan)).await;
+
+    //the third slice would drag the average about 6% below the first quote
+    assert_eq!(executor.amounts(), vec![10_000_000, 10_000_000]);
+    assert!(summary.aborted.unwrap().contains("average price"));
+    assert_eq!(clock.now(), START + 2 * HOUR);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_twap_aborts_on_cumulative_price_impact() -> anyhow::Result<()> {
+    //0.4% impact per slice
+    let mock = MockJupiter::start(good("0.004"), Pubkey::new_unique(), Hash::default()).await;
+    let jup = JupiterSwapApiClient::new(mock.base_url());
+    let clock = FakeClock::at(START);
+    let executor = FakeExecutor::default();
+
+    let twap = Twap {
+        jup: &jup,
+        executor: &executor,
+        clock: &clock,
+        pair: sol_to_bonk(40_000_000),
+        plan: TwapPlan {
+            max_cumulative_impact_pct: Some(1.0),
+            ..plan()
+        },
+    };
+    let summary = twap.run(&schedule(&twap.plan)).await;
+
+    assert_eq!(executor.amounts().len(), 2);
+    assert!(summary.aborted.unwrap().contains("cumulative price impact"));
+    assert!((summary.cumulative_impact_pct - 0.8).abs() < 1e-9);
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use jup_swap::message::legacy_accounts;
+use jup_swap::verify::{verify_message, Allowlist, Verification, JUPITER_V6_PROGRAM_ID};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+use spl_associated_token_account::get_associated_token_address;
+
+fn verify(instructions: &[Instruction], payer: &Pubkey, wallet: &Pubkey) -> Verification {
+    verify_message_with(instructions, payer, wallet, &Allowlist::default())
+}
+
+fn verify_message_with(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    allowlist: &Allowlist,
+) -> Verification {
+    let message = Message::new(instructions, Some(payer));
+    verify_message(
+        &legacy_accounts(&message),
+        &message.instructions,
+        wallet,
+        allowlist,
+    )
+}
+
+/// Roughly what Jupiter sends for SOL -> token: compute budget, wrap SOL, route
+fn swap_instructions(wallet: &Pubkey) -> Vec<Instruction> {
+    let wsol = get_associated_token_address(wallet, &spl_token::native_mint::ID);
+    let mut route_data = vec![229, 23, 203, 151, 122, 227, 173, 42];
+    route_data.extend_from_slice(&[0; 16]);
+
+    vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(1_400_000),
+        ComputeBudgetInstruction::set_compute_unit_price(1000),
+        system_instruction::transfer(wallet, &wsol, 10_000_000),
+        spl_token::instruction::sync_native(&spl_token::ID, &wsol).unwrap(),
+        Instruction::new_with_bytes(
+            JUPITER_V6_PROGRAM_ID,
+            &route_data,
+            vec![
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(*wallet, true),
+                AccountMeta::new(wsol, false),
+            ],
+        ),
+        spl_token::instruction::close_account(&spl_token::ID, &wsol, wallet, wallet, &[]).unwrap(),
+    ]
+}
+
+#[test]
+fn test_plain_swap_passes() {
+    let wallet = Pubkey::new_unique();
+    let verification = verify(&swap_instructions(&wallet), &wallet, &wallet);
+
+    assert!(
+        verification.problems.is_empty(),
+        "{:?}",
+        verification.problems
+    );
+    let descriptions: Vec<_> = verification
+        .instructions
+        .iter()
+        .map(|ix| ix.description.as_str())
+        .collect();
+    assert_eq!(descriptions[0], "set_compute_unit_limit 1400000");
+    assert_eq!(descriptions[4], "route");
+}
+
+#[test]
+fn test_wrong_fee_payer() {
+    let wallet = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let verification = verify(&swap_instructions(&wallet), &other, &wallet);
+
+    assert!(verification.problems[0].contains("fee payer"));
+    assert!(verification.into_result().is_err());
+}
+
+#[test]
+fn test_program_not_on_allowlist() {
+    let wallet = Pubkey::new_unique();
+    let unknown = Pubkey::new_unique();
+    let mut instructions = swap_instructions(&wallet);
+    instructions.push(Instruction::new_with_bytes(unknown, &[1, 2, 3], vec![]));
+
+    let verification = verify(&instructions, &wallet, &wallet);
+    assert_eq!(verification.problems.len(), 1);
+    assert!(verification.problems[0].contains("isn't on the allowlist"));
+
+    //--allow-program lets it through
+    let mut allowlist = Allowlist::default();
+    allowlist.allow(unknown);
+    let verification = verify_message_with(&instructions, &wallet, &wallet, &allowlist);
+    assert!(verification.problems.is_empty());
+}
+
+#[test]
+fn test_sol_transfer_to_third_party_flagged() {
+    let wallet = Pubkey::new_unique();
+    let thief = Pubkey::new_unique();
+    let mut instructions = swap_instructions(&wallet);
+    instructions.push(system_instruction::transfer(&wallet, &thief, 1_000_000));
+
+    let verification = verify(&instructions, &wallet, &wallet);
+    assert_eq!(verification.problems.len(), 1);
+    assert!(verification.problems[0].contains(&thief.to_string()));
+}
+
+#[test]
+fn test_token_approval_and_close_to_third_party_flagged() {
+    let wallet = Pubkey::new_unique();
+    let thief = Pubkey::new_unique();
+    let token_account = Pubkey::new_unique();
+    let mut instructions = swap_instructions(&wallet);
+    instructions.push(
+        spl_token::instruction::approve(
+            &spl_token::ID,
+            &token_account,
+            &thief,
+            &wallet,
+            &[],
+            u64::MAX,
+        )
+        .unwrap(),
+    );
+    instructions.push(
+        spl_token::instruction::close_account(&spl_token::ID, &token_account, &thief, &wallet, &[])
+            .unwrap(),
+    );
+
+    let verification = verify(&instructions, &wallet, &wallet);
+    assert_eq!(verification.problems.len(), 2);
+    assert!(verification.problems[0].contains("approves"));
+    assert!(verification.problems[1].contains("sends the rent"));
+}